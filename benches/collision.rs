@@ -0,0 +1,116 @@
+//! Measures the per-frame cost of the collision and scoring systems as the
+//! number of pipes grows. Run with `cargo bench --bench collision`.
+
+use bevy::{ecs::schedule::ExecutorKind, prelude::*};
+use bevy_flappy_bird::{
+    config::{GameConfig, GapPattern, HitboxShape},
+    detect_pipe_passes, handle_bird_deaths,
+    input::FlapInput,
+    modes::GameMode,
+    update_bird, update_score, Bird, BirdDied, BirdFlapped, Difficulty, GameManager, GameState,
+    LastDeath, Obstacle, PipePassed, PipeQueue, Score,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const PIPE_PAIR_COUNTS: [usize; 3] = [5, 50, 500];
+const PIPE_PAIR_SPACING: f32 = 240.;
+/// The usual pipe length in world units.
+const PIPE_HEIGHT: f32 = 576.;
+
+/// A bird at the origin with `pipe_pairs` pairs lined up ahead of it, far
+/// enough that nothing collides or scores.
+fn build_world(pipe_pairs: usize) -> World {
+    let mut world = World::new();
+    world.init_resource::<Time>();
+    world.init_resource::<ButtonInput<KeyCode>>();
+    world.init_resource::<FlapInput>();
+    world.init_resource::<GameConfig>();
+    world.init_resource::<NextState<GameState>>();
+    world.init_resource::<Score>();
+    world.init_resource::<Difficulty>();
+    world.init_resource::<LastDeath>();
+    world.insert_resource(GameMode::Normal.rules(&GameConfig::default()));
+    world.init_resource::<Events<BirdFlapped>>();
+    world.init_resource::<Events<PipePassed>>();
+    world.init_resource::<Events<BirdDied>>();
+    world.insert_resource(GameManager {
+        pipe_image: Handle::default(),
+        window_dimentions: Vec2::new(512., 512.),
+        facing: 1.,
+        bird_start: Vec3::ZERO,
+        pipe_spacing_jitter: 0.,
+        hit_invulnerability: 0.,
+        dive_strength: None,
+        soft_ceiling: None,
+        gap_pattern: GapPattern::Uniform,
+        bird_scale: 1.,
+        first_pipe_distance: 0.,
+        tutorial_lead_in: 0.,
+        pipe_tiers: Vec::new(),
+        finish_line: None,
+        autopilot: false,
+        hitbox_shape: HitboxShape::Rect,
+        pipe_pairs: pipe_pairs as u32,
+    });
+
+    world.spawn((
+        Transform::IDENTITY,
+        Bird {
+            velocity: 0.,
+            flap_hold: None,
+        },
+    ));
+    let mut pipe_queue = PipeQueue::default();
+    for i in 0..pipe_pairs {
+        let x = PIPE_PAIR_SPACING * (i + 1) as f32;
+        let [top, bottom] = [1., -1.].map(|pipe_direction| {
+            world
+                .spawn((
+                    Transform::from_xyz(x, 350. * pipe_direction, 0.),
+                    Obstacle {
+                        pipe_direction,
+                        pair_number: i as u32,
+                        flips_gravity: false,
+                        height: PIPE_HEIGHT,
+                        spacing_offset: 0.,
+                    },
+                ))
+                .id()
+        });
+        pipe_queue.0.push_back([top, bottom]);
+    }
+    world.insert_resource(pipe_queue);
+
+    world
+}
+
+fn collision_and_scoring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision_and_scoring");
+    for pipe_pairs in PIPE_PAIR_COUNTS {
+        let mut world = build_world(pipe_pairs);
+        let mut schedule = Schedule::default();
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        schedule.add_systems(
+            (
+                detect_pipe_passes,
+                update_score,
+                update_bird,
+                handle_bird_deaths,
+            )
+                .chain(),
+        );
+
+        // The first run initializes the systems, keep it out of the timing
+        schedule.run(&mut world);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(pipe_pairs),
+            &pipe_pairs,
+            |b, _| b.iter(|| schedule.run(&mut world)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, collision_and_scoring);
+criterion_main!(benches);
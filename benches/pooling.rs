@@ -0,0 +1,120 @@
+//! Compares starting a run by moving the pooled pipe pairs back into place
+//! with despawning them and spawning new ones. Run with
+//! `cargo bench --bench pooling`.
+
+use bevy::{ecs::schedule::ExecutorKind, prelude::*};
+use bevy_flappy_bird::{
+    config::{GapPattern, HitboxShape},
+    gauntlet::PatternSource,
+    lay_out_pipes, GameManager, GameRng, Obstacle, PipeQueue,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, SeedableRng};
+
+const PIPE_PAIR_COUNTS: [u32; 3] = [5, 16, 64];
+const PIPE_PAIR_SPACING: f32 = 240.;
+/// The usual pipe length in world units.
+const PIPE_HEIGHT: f32 = 576.;
+
+fn spawn_pipe(commands: &mut Commands, x: f32, pipe_direction: f32, pair_number: u32) -> Entity {
+    commands
+        .spawn((
+            Sprite::default(),
+            Transform::from_xyz(x, 350. * pipe_direction, 0.),
+            Visibility::Inherited,
+            Obstacle {
+                pipe_direction,
+                pair_number,
+                flips_gravity: false,
+                height: PIPE_HEIGHT,
+                spacing_offset: 0.,
+            },
+        ))
+        .id()
+}
+
+/// What starting a run used to do.
+fn respawn_pipes(
+    mut commands: Commands,
+    mut pipe_queue: ResMut<PipeQueue>,
+    game_manager: Res<GameManager>,
+) {
+    for pair in pipe_queue.0.drain(..) {
+        for entity in pair {
+            commands.entity(entity).despawn();
+        }
+    }
+    for pair_number in 0..game_manager.pipe_pairs {
+        let x = PIPE_PAIR_SPACING * pair_number as f32;
+        let pair = [1., -1.]
+            .map(|pipe_direction| spawn_pipe(&mut commands, x, pipe_direction, pair_number));
+        pipe_queue.0.push_back(pair);
+    }
+}
+
+fn build_world(pipe_pairs: u32) -> World {
+    let mut world = World::new();
+    world.insert_resource(GameManager {
+        pipe_image: Handle::default(),
+        window_dimentions: Vec2::new(512., 512.),
+        facing: 1.,
+        bird_start: Vec3::ZERO,
+        pipe_spacing_jitter: 0.,
+        hit_invulnerability: 0.,
+        dive_strength: None,
+        soft_ceiling: None,
+        gap_pattern: GapPattern::Uniform,
+        bird_scale: 1.,
+        first_pipe_distance: 0.,
+        tutorial_lead_in: 0.,
+        pipe_tiers: Vec::new(),
+        finish_line: None,
+        autopilot: false,
+        hitbox_shape: HitboxShape::Rect,
+        pipe_pairs,
+    });
+    world.insert_resource(GameRng(StdRng::seed_from_u64(0)));
+    world.init_resource::<PatternSource>();
+    world.init_resource::<PipeQueue>();
+
+    // Starts out with a pool either way
+    let mut schedule = Schedule::default();
+    schedule.add_systems(respawn_pipes);
+    schedule.run(&mut world);
+
+    world
+}
+
+/// A world with `pipe_pairs` pooled pipes and a schedule laying them out
+/// for a run with `system`, already run once to initialize it.
+fn run_start<M>(pipe_pairs: u32, system: impl IntoSystemConfigs<M>) -> (World, Schedule) {
+    let mut world = build_world(pipe_pairs);
+    let mut schedule = Schedule::default();
+    schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    schedule.add_systems(system);
+    schedule.run(&mut world);
+    (world, schedule)
+}
+
+fn starting_a_run(c: &mut Criterion) {
+    let mut group = c.benchmark_group("starting_a_run");
+    for pipe_pairs in PIPE_PAIR_COUNTS {
+        let (mut world, mut schedule) = run_start(pipe_pairs, lay_out_pipes);
+        group.bench_with_input(
+            BenchmarkId::new("pooled", pipe_pairs),
+            &pipe_pairs,
+            |b, _| b.iter(|| schedule.run(&mut world)),
+        );
+
+        let (mut world, mut schedule) = run_start(pipe_pairs, respawn_pipes);
+        group.bench_with_input(
+            BenchmarkId::new("respawned", pipe_pairs),
+            &pipe_pairs,
+            |b, _| b.iter(|| schedule.run(&mut world)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, starting_a_run);
+criterion_main!(benches);
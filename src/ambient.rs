@@ -0,0 +1,78 @@
+//! Background loops like wind or birdsong, played quietly for as long as a
+//! run lasts. They fade in when it starts and out once it's over, and carry
+//! on through pausing and retrying rather than starting over.
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{assets::GameAssets, config::GameConfig, GameState};
+
+/// Seconds a loop takes to fade all the way in or out.
+const AMBIENT_FADE_DURATION: f32 = 1.;
+
+#[derive(Component, Default)]
+pub struct AmbientLoop {
+    /// Where the fade has got to, from 0 to the configured volume.
+    pub volume: f32,
+}
+
+/// Whether the loops should be heard in `state`.
+pub fn ambient_playing(state: GameState) -> bool {
+    matches!(
+        state,
+        GameState::Playing | GameState::Paused | GameState::Dying
+    )
+}
+
+/// Loops still fading out from the last run fade back in instead, so they
+/// never play twice over.
+pub fn start_ambient_loops(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    ambient_query: Query<(), With<AmbientLoop>>,
+) {
+    if !ambient_query.is_empty() {
+        return;
+    }
+    for sound in &game_assets.ambient_sounds {
+        commands.spawn((
+            AudioPlayer::new(sound.clone()),
+            PlaybackSettings::LOOP.with_volume(Volume::new(0.)),
+            AmbientLoop::default(),
+        ));
+    }
+}
+
+/// Moves every loop's volume toward the configured one during a run and
+/// toward silence otherwise, dropping loops once they're silent.
+pub fn fade_ambient_loops(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    state: Res<State<GameState>>,
+    config: Res<GameConfig>,
+    mut ambient_query: Query<(Entity, &mut AmbientLoop, Option<&AudioSink>)>,
+) {
+    let full_volume = config.ambient_volume.clamp(0., 1.);
+    let target = if ambient_playing(*state.get()) {
+        full_volume
+    } else {
+        0.
+    };
+    // Real time, so the fade still goes on while a transition has the
+    // world paused
+    let step = time.delta_secs() / AMBIENT_FADE_DURATION * full_volume.max(f32::EPSILON);
+
+    for (entity, mut ambient, sink) in ambient_query.iter_mut() {
+        ambient.volume = if ambient.volume < target {
+            (ambient.volume + step).min(target)
+        } else {
+            (ambient.volume - step).max(target)
+        };
+        if target == 0. && ambient.volume == 0. {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if let Some(sink) = sink {
+            sink.set_volume(ambient.volume);
+        }
+    }
+}
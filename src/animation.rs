@@ -0,0 +1,97 @@
+//! Wing flapping for a bird drawn from a sprite sheet. The image named by
+//! the `bird_texture` config entry holds `bird_frames` frames of the same
+//! width side by side, which the bird cycles through. The default sprite is
+//! a single frame, so it stays still.
+//!
+//! The wings beat faster for a moment after every flap, and stop on
+//! whatever frame they were on when the bird crashes.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{assets::GameAssets, config::GameConfig, input::FlapInput, Bird, GameState};
+
+/// Seconds each frame shows for while gliding along.
+const FRAME_TIME: f32 = 0.12;
+/// How much faster the wings beat right after a flap, and for how long.
+const FLAP_SPEEDUP: f32 = 3.;
+const FLAP_SPEEDUP_DURATION: f32 = 0.25;
+
+#[derive(Component)]
+pub struct WingAnimation {
+    pub frames: usize,
+    /// Seconds left of beating faster after a flap.
+    pub speedup: f32,
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct AnimationTimer(pub Timer);
+
+/// Frame `advanced` frames on from `index`, looping around the sheet.
+pub fn next_frame(index: usize, advanced: usize, frames: usize) -> usize {
+    (index + advanced) % frames.max(1)
+}
+
+/// Cuts the loaded sheet into frames for the bird and its flockmates, once
+/// loading is done and its size is known.
+#[allow(clippy::type_complexity)]
+pub fn setup_wing_animation(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    game_assets: Res<GameAssets>,
+    images: Res<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut sprite_query: Query<(Entity, &mut Sprite), With<Bird>>,
+) {
+    let Some(image) = images.get(&game_assets.bird) else {
+        return;
+    };
+    let frames = config.bird_frames.max(1);
+    let frame_size = UVec2::new(image.width() / frames, image.height());
+    let layout = layouts.add(TextureAtlasLayout::from_grid(
+        frame_size, frames, 1, None, None,
+    ));
+
+    for (entity, mut sprite) in sprite_query.iter_mut() {
+        sprite.texture_atlas = Some(TextureAtlas {
+            layout: layout.clone(),
+            index: 0,
+        });
+        commands.entity(entity).insert((
+            WingAnimation {
+                frames: frames as usize,
+                speedup: 0.,
+            },
+            AnimationTimer(Timer::from_seconds(FRAME_TIME, TimerMode::Repeating)),
+        ));
+    }
+}
+
+/// Only runs on the menu and during a run, so the wings freeze when the
+/// bird dies.
+pub fn animate_wings(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    flap_input: Res<FlapInput>,
+    mut query: Query<(&mut Sprite, &mut WingAnimation, &mut AnimationTimer)>,
+) {
+    let flapped = *state.get() == GameState::Playing && flap_input.just_pressed;
+    for (mut sprite, mut animation, mut timer) in query.iter_mut() {
+        if flapped {
+            animation.speedup = FLAP_SPEEDUP_DURATION;
+        }
+        let speed = if animation.speedup > 0. {
+            FLAP_SPEEDUP
+        } else {
+            1.
+        };
+        animation.speedup = (animation.speedup - time.delta_secs()).max(0.);
+
+        timer.tick(Duration::from_secs_f32(time.delta_secs() * speed));
+        let advanced = timer.times_finished_this_tick() as usize;
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = next_frame(atlas.index, advanced, animation.frames);
+        }
+    }
+}
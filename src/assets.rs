@@ -0,0 +1,129 @@
+use bevy::{
+    asset::{LoadState, UntypedAssetId},
+    prelude::*,
+};
+
+use crate::{config::GameConfig, GameState};
+
+const LOADING_BAR_SIZE: Vec2 = Vec2::new(240., 16.);
+
+/// Every asset the game uses, requested up front so the loading screen can
+/// tell how far along they are.
+#[derive(Resource)]
+pub struct GameAssets {
+    pub bird: Handle<Image>,
+    pub pipe: Handle<Image>,
+    /// Optional, the menu shows the name as text without it.
+    pub logo: Handle<Image>,
+    /// Only loaded when the config names one.
+    pub milestone_sound: Option<Handle<AudioSource>>,
+    /// Also only loaded when the config names them.
+    pub ambient_sounds: Vec<Handle<AudioSource>>,
+    pub pipe_whoosh_sound: Option<Handle<AudioSource>>,
+}
+
+impl GameAssets {
+    pub fn ids(&self) -> [UntypedAssetId; 3] {
+        [
+            self.bird.id().untyped(),
+            self.pipe.id().untyped(),
+            self.logo.id().untyped(),
+        ]
+    }
+}
+
+/// The filled part of the loading bar.
+#[derive(Component)]
+pub struct LoadingBar;
+
+pub fn load_game_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+) {
+    commands.insert_resource(GameAssets {
+        bird: asset_server.load(&config.bird_texture),
+        pipe: asset_server.load(&config.pipe_texture),
+        logo: asset_server.load("logo.png"),
+        milestone_sound: config
+            .milestone_sound
+            .as_ref()
+            .map(|sound| asset_server.load(sound)),
+        ambient_sounds: config
+            .ambient_sounds
+            .iter()
+            .map(|sound| asset_server.load(sound))
+            .collect(),
+        pipe_whoosh_sound: config
+            .pipe_whoosh_sound
+            .as_ref()
+            .map(|sound| asset_server.load(sound)),
+    });
+}
+
+pub fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            StateScoped(GameState::Loading),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(LOADING_BAR_SIZE.x),
+                        height: Val::Px(LOADING_BAR_SIZE.y),
+                        border: UiRect::all(Val::Px(2.)),
+                        ..Default::default()
+                    },
+                    BorderColor(Color::WHITE),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Node {
+                            width: Val::Percent(0.),
+                            height: Val::Percent(100.),
+                            ..Default::default()
+                        },
+                        BackgroundColor(Color::WHITE),
+                        LoadingBar,
+                    ));
+                });
+        });
+}
+
+/// Fills the bar as assets come in and moves on to the menu once they all
+/// have. An asset that fails to load is only logged, the game can still run
+/// with it missing.
+pub fn update_loading(
+    asset_server: Res<AssetServer>,
+    game_assets: Res<GameAssets>,
+    mut bar_query: Query<&mut Node, With<LoadingBar>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let ids = game_assets.ids();
+    let mut done = 0;
+    for id in ids {
+        if asset_server.is_loaded_with_dependencies(id) {
+            done += 1;
+        } else if let LoadState::Failed(error) = asset_server.load_state(id) {
+            warn!("Failed to load asset: {error}");
+            done += 1;
+        }
+    }
+
+    if let Ok(mut bar) = bar_query.get_single_mut() {
+        bar.width = Val::Percent(100. * done as f32 / ids.len() as f32);
+    }
+
+    if done == ids.len() {
+        next_state.set(GameState::Menu);
+    }
+}
@@ -0,0 +1,116 @@
+//! Attract mode for arcade and kiosk setups. Left on a result screen or
+//! paused without input for the configured `idle_timeout`, the game goes
+//! back to the menu and plays demo runs on autopilot until someone touches
+//! a control.
+
+use bevy::prelude::*;
+
+use crate::{config::GameConfig, input::any_input_pressed, transition, GameManager, GameState};
+
+/// Counts how long the game has sat idle, and starts and ends the demo
+/// runs.
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IdleTimer>()
+            .add_systems(
+                Update,
+                (
+                    reset_idle_timer.run_if(state_changed::<GameState>),
+                    update_idle_timer.run_if(
+                        in_state(GameState::GameOver)
+                            .or(in_state(GameState::Victory))
+                            .or(in_state(GameState::Paused)),
+                    ),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                start_attract_run
+                    .run_if(in_state(GameState::Menu).and(resource_exists::<AttractMode>)),
+            )
+            .add_systems(
+                PostUpdate,
+                end_attract_mode
+                    .before(transition::run_transitions)
+                    .run_if(resource_exists::<AttractMode>),
+            );
+    }
+}
+
+/// Seconds without input on the screen the game is waiting on.
+#[derive(Resource, Default)]
+pub struct IdleTimer {
+    pub idle: f32,
+}
+
+/// Present while demo runs are playing.
+#[derive(Resource)]
+pub struct AttractMode;
+
+/// Each screen gets the full timeout from when it comes up.
+pub fn reset_idle_timer(mut idle_timer: ResMut<IdleTimer>) {
+    idle_timer.idle = 0.;
+}
+
+/// Only counts on the screens where the game waits for the player, so it
+/// can never end a run that's being played.
+#[allow(clippy::too_many_arguments)]
+pub fn update_idle_timer(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut idle_timer: ResMut<IdleTimer>,
+    mut game_manager: ResMut<GameManager>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if config.idle_timeout <= 0. {
+        return;
+    }
+    if any_input_pressed(&keys, &mouse, &touches) {
+        idle_timer.idle = 0.;
+        return;
+    }
+
+    idle_timer.idle += time.delta_secs();
+    if idle_timer.idle >= config.idle_timeout {
+        idle_timer.idle = 0.;
+        commands.insert_resource(AttractMode);
+        game_manager.autopilot = true;
+        next_state.set(GameState::Menu);
+    }
+}
+
+/// Starts the next demo run as soon as the menu is up.
+pub fn start_attract_run(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
+
+/// Any input hands the game back to the player at the menu. Runs after
+/// everything else that changes state, so the input can't also pause or
+/// start anything on the way out.
+#[allow(clippy::too_many_arguments)]
+pub fn end_attract_mode(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    state: Res<State<GameState>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut game_manager: ResMut<GameManager>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !any_input_pressed(&keys, &mouse, &touches) {
+        return;
+    }
+    commands.remove_resource::<AttractMode>();
+    game_manager.autopilot = config.autopilot;
+    if *state.get() != GameState::Menu {
+        next_state.set(GameState::Menu);
+    }
+}
@@ -0,0 +1,155 @@
+//! The game's own sounds: a flap, a point for every pipe passed, a hit when
+//! the bird crashes and a swoosh as the result screen comes up, over music
+//! looped for as long as the game is open.
+//!
+//! None ship with the game, each plays once the config names a file for it.
+//! They're played from the gameplay events and the game state, so gameplay
+//! never has to reach for them.
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    ambient, audio_cues, beat, config::GameConfig, detect_pipe_passes, update_bird, whoosh,
+    BirdDied, BirdFlapped, GameState, PipePassed, RunSet,
+};
+
+/// The game's own sounds and music, and the beat the pipes pulse to. The
+/// optional extras, like the pipe whoosh and the gap cues, are only added
+/// when the config turns them on.
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        let config = app.world().resource::<GameConfig>();
+        let ambient_sounds = !config.ambient_sounds.is_empty();
+        let pipe_whoosh = config.pipe_whoosh_sound.is_some();
+        let gap_audio_cues = config.gap_audio_cues;
+
+        app.init_resource::<beat::BeatClock>()
+            .add_systems(PreStartup, load_game_audio)
+            .add_systems(OnExit(GameState::Loading), start_music)
+            .add_systems(OnEnter(GameState::GameOver), play_swoosh_sound)
+            .add_systems(OnEnter(GameState::Victory), play_swoosh_sound)
+            .add_systems(
+                Update,
+                (
+                    play_flap_sound.after(update_bird),
+                    play_point_sound.after(detect_pipe_passes),
+                    play_hit_sound.after(update_bird),
+                ),
+            )
+            .add_systems(Update, (beat::update_beat_clock, beat::beat_pulse).chain());
+
+        if ambient_sounds {
+            app.add_systems(OnEnter(GameState::Playing), ambient::start_ambient_loops)
+                .add_systems(Update, ambient::fade_ambient_loops);
+        }
+
+        if pipe_whoosh {
+            app.add_systems(
+                Update,
+                whoosh::play_pipe_whoosh
+                    .after(RunSet::Pipes)
+                    .run_if(in_state(GameState::Playing)),
+            );
+        }
+
+        if gap_audio_cues {
+            app.init_resource::<audio_cues::ClickPhase>()
+                .add_systems(Startup, audio_cues::setup_gap_cue_sounds)
+                .add_systems(OnEnter(GameState::Playing), audio_cues::start_gap_tone)
+                .add_systems(
+                    Update,
+                    audio_cues::update_gap_cues
+                        .after(RunSet::Pipes)
+                        .run_if(in_state(GameState::Playing)),
+                );
+        }
+    }
+}
+
+/// Only loaded when the config names them.
+#[derive(Resource, Default)]
+pub struct GameAudio {
+    pub flap: Option<Handle<AudioSource>>,
+    pub point: Option<Handle<AudioSource>>,
+    pub hit: Option<Handle<AudioSource>>,
+    pub swoosh: Option<Handle<AudioSource>>,
+    pub music: Option<Handle<AudioSource>>,
+}
+
+/// The music, which keeps playing from menu to run and back.
+#[derive(Component)]
+pub struct Music;
+
+pub fn load_game_audio(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+) {
+    let load = |path: &Option<String>| path.as_ref().map(|path| asset_server.load(path));
+    commands.insert_resource(GameAudio {
+        flap: load(&config.flap_sound),
+        point: load(&config.point_sound),
+        hit: load(&config.hit_sound),
+        swoosh: load(&config.swoosh_sound),
+        music: load(&config.music),
+    });
+}
+
+fn play_sound(commands: &mut Commands, sound: &Option<Handle<AudioSource>>, config: &GameConfig) {
+    if let Some(sound) = sound {
+        commands.spawn((
+            AudioPlayer::new(sound.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(config.sound_volume.clamp(0., 1.))),
+        ));
+    }
+}
+
+pub fn start_music(mut commands: Commands, config: Res<GameConfig>, audio: Res<GameAudio>) {
+    if let Some(music) = &audio.music {
+        commands.spawn((
+            AudioPlayer::new(music.clone()),
+            PlaybackSettings::LOOP.with_volume(Volume::new(config.music_volume.clamp(0., 1.))),
+            Music,
+        ));
+    }
+}
+
+pub fn play_flap_sound(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    audio: Res<GameAudio>,
+    mut flaps: EventReader<BirdFlapped>,
+) {
+    for _ in flaps.read() {
+        play_sound(&mut commands, &audio.flap, &config);
+    }
+}
+
+pub fn play_point_sound(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    audio: Res<GameAudio>,
+    mut passes: EventReader<PipePassed>,
+) {
+    for _ in passes.read() {
+        play_sound(&mut commands, &audio.point, &config);
+    }
+}
+
+/// Plays for every bird that crashes, not only the last.
+pub fn play_hit_sound(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    audio: Res<GameAudio>,
+    mut deaths: EventReader<BirdDied>,
+) {
+    for _ in deaths.read() {
+        play_sound(&mut commands, &audio.hit, &config);
+    }
+}
+
+pub fn play_swoosh_sound(mut commands: Commands, config: Res<GameConfig>, audio: Res<GameAudio>) {
+    play_sound(&mut commands, &audio.swoosh, &config);
+}
@@ -0,0 +1,127 @@
+//! Sound cues for playing without looking at the screen. A steady tone
+//! follows the height of the next gap, higher for a higher gap, and a click
+//! repeats faster and faster as its pipes get closer.
+//!
+//! Both are plain sine waves made on the spot, so there are no sound files
+//! to ship. The tone keeps playing and only has its pitch bent, which is
+//! what lets it slide along smoothly as the pipes scroll in.
+
+use std::time::Duration;
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    autopilot::next_pair, gap_center, GameManager, GameState, Lead, Obstacle, PipeQueue, Score,
+    OBSTACLE_WIDTH, PIXEL_RATIO,
+};
+
+/// Pitch of the tone for a gap level with the middle of the screen.
+const TONE_BASE_FREQUENCY: f32 = 440.;
+/// Octaves the tone rises or falls from its base for a gap at the very top
+/// or bottom of the screen.
+const TONE_RANGE_OCTAVES: f32 = 1.;
+const TONE_VOLUME: f32 = 0.25;
+
+const CLICK_FREQUENCY: f32 = 1760.;
+const CLICK_DURATION: Duration = Duration::from_millis(30);
+const CLICK_VOLUME: f32 = 0.4;
+/// Seconds between clicks for pipes at `CLICK_MAX_DISTANCE` or further, and
+/// right in front of the bird.
+const CLICK_MAX_INTERVAL: f32 = 0.8;
+const CLICK_MIN_INTERVAL: f32 = 0.1;
+const CLICK_MAX_DISTANCE: f32 = 100. * PIXEL_RATIO;
+
+#[derive(Resource)]
+pub struct GapCueSounds {
+    pub tone: Handle<Pitch>,
+    pub click: Handle<Pitch>,
+}
+
+/// The looping tone, which only plays during a run.
+#[derive(Component)]
+pub struct GapTone;
+
+/// How far the click has got towards the next one, from 0 to 1.
+#[derive(Resource, Default)]
+pub struct ClickPhase(pub f32);
+
+/// Pitch of the tone for a gap centered `gap_center` from the middle of a
+/// screen `half_height` tall either way. The top and bottom are
+/// `TONE_RANGE_OCTAVES` above and below the base.
+pub fn gap_tone_frequency(gap_center: f32, half_height: f32) -> f32 {
+    let height = (gap_center / half_height).clamp(-1., 1.);
+    TONE_BASE_FREQUENCY * 2f32.powf(height * TONE_RANGE_OCTAVES)
+}
+
+/// Seconds between clicks for pipes `distance` ahead of the bird.
+pub fn click_interval(distance: f32) -> f32 {
+    let closeness = 1. - (distance / CLICK_MAX_DISTANCE).clamp(0., 1.);
+    CLICK_MAX_INTERVAL + (CLICK_MIN_INTERVAL - CLICK_MAX_INTERVAL) * closeness
+}
+
+pub fn setup_gap_cue_sounds(mut commands: Commands, mut pitches: ResMut<Assets<Pitch>>) {
+    commands.insert_resource(GapCueSounds {
+        // Long enough that looping it can't be heard
+        tone: pitches.add(Pitch::new(TONE_BASE_FREQUENCY, Duration::from_secs(4))),
+        click: pitches.add(Pitch::new(CLICK_FREQUENCY, CLICK_DURATION)),
+    });
+}
+
+/// Leaving `Playing` for the pause menu or the end of the run stops it.
+pub fn start_gap_tone(mut commands: Commands, sounds: Res<GapCueSounds>) {
+    commands.spawn((
+        AudioPlayer(sounds.tone.clone()),
+        PlaybackSettings::LOOP.with_volume(Volume::new(0.)),
+        StateScoped(GameState::Playing),
+        GapTone,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_gap_cues(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_manager: Res<GameManager>,
+    sounds: Res<GapCueSounds>,
+    score: Res<Score>,
+    pipe_queue: Res<PipeQueue>,
+    bird_query: Query<&Transform, With<Lead>>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+    tone_query: Query<&AudioSink, With<GapTone>>,
+    mut click_phase: ResMut<ClickPhase>,
+) {
+    let next_pair = next_pair(&pipe_queue, &score, &obstacle_query);
+    let bird = bird_query.get_single().ok();
+    // To the front edge of the pipe, which is where the bird can first hit it
+    let next_pipe = bird.zip(next_pair).map(|(bird, [top, bottom])| {
+        let distance = (top.translation.x - bird.translation.x) * game_manager.facing
+            - OBSTACLE_WIDTH * PIXEL_RATIO / 2.;
+        (gap_center(top.translation, bottom.translation), distance)
+    });
+
+    for sink in tone_query.iter() {
+        match next_pipe {
+            Some((gap_center, _)) => {
+                let half_height = game_manager.window_dimentions.y / 2.;
+                sink.set_speed(gap_tone_frequency(gap_center, half_height) / TONE_BASE_FREQUENCY);
+                sink.set_volume(TONE_VOLUME);
+            }
+            None => sink.set_volume(0.),
+        }
+    }
+
+    let Some((_, distance)) = next_pipe else {
+        click_phase.0 = 0.;
+        return;
+    };
+    // Advancing a phase rather than counting down a fixed wait lets the
+    // clicks speed up between one and the next
+    click_phase.0 += time.delta_secs() / click_interval(distance.max(0.));
+    if click_phase.0 >= 1. {
+        click_phase.0 = click_phase.0.fract();
+        commands.spawn((
+            AudioPlayer(sounds.click.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(CLICK_VOLUME)),
+        ));
+    }
+}
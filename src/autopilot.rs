@@ -0,0 +1,220 @@
+//! Flies the bird on its own for demos, and to check that finish-line runs
+//! can actually be finished. It taps in place of the player and the bird
+//! flies those as plain flaps whatever the controls, so its runs aren't
+//! recorded.
+
+use bevy::prelude::*;
+
+use crate::{
+    gap_center, input::FlapInput, Bird, Difficulty, Flight, GameManager, Lead, Obstacle, PipeQueue,
+    Score, FLAP_FORCE, GRAVITY, OBSTACLE_SCROLL_SPEED, PIXEL_RATIO,
+};
+
+/// How far ahead the autopilot predicts the bird's height at the starting
+/// scroll speed, a frame at 60fps. Its flaps land on the frame they're
+/// decided, so looking much further only has it flap early and fly over
+/// the gap. Faster pipes leave less room, so it looks further ahead.
+const AUTOPILOT_REACTION_TIME: f32 = 1. / 60.;
+/// Closest the aim point gets to the ends of a gap, in world units.
+const AUTOPILOT_SAFETY_MARGIN: f32 = 4. * PIXEL_RATIO;
+/// Seconds between points of a simulated path, a frame at 60fps.
+const SIMULATION_STEP: f32 = 1. / 60.;
+/// Longest a simulated path runs, however far off its last gap is.
+const MAX_SIMULATION_STEPS: u32 = 600;
+
+/// Whether a bird at `bird_y` moving at `bird_vy` should flap to stay level
+/// with `next_gap_center`, with pipes scrolling at `speed`. It only flaps
+/// once the bird is falling, so it never stacks flaps on the way up.
+pub fn should_flap(bird_y: f32, bird_vy: f32, next_gap_center: f32, speed: f32) -> bool {
+    let reaction = AUTOPILOT_REACTION_TIME * (speed / OBSTACLE_SCROLL_SPEED).max(1.);
+    let predicted_y = bird_y + bird_vy * reaction - GRAVITY * reaction * reaction / 2.;
+    bird_vy <= 0. && predicted_y < next_gap_center
+}
+
+/// Height to aim for in a gap of half height `gap_half_height` centered on
+/// `gap_center`. A flap carries the bird above where it started, so the aim
+/// is lowered by half that, as far as the margin to the lower pipe allows.
+pub fn aim_height(gap_center: f32, gap_half_height: f32) -> f32 {
+    let flap_rise = FLAP_FORCE * FLAP_FORCE / (2. * GRAVITY);
+    let lowering = (flap_rise / 2.).min(gap_half_height - AUTOPILOT_SAFETY_MARGIN);
+    gap_center - lowering.max(0.)
+}
+
+/// The path the autopilot would fly from a bird at `bird_y` moving at
+/// `velocity`, through `gaps` given as how far ahead each one is cleared
+/// and the height to aim for in it, with pipes scrolling at `speed`.
+/// `flight` is how the bird flies where it is now, and is carried forward
+/// as it goes.
+///
+/// Returns how far ahead and how high the bird is after each step until
+/// the last gap is cleared. Each step is flown with `Flight::step`, the
+/// same as `update_bird` flies the bird, so `flight` should have plain
+/// flaps for the path the autopilot flies.
+pub fn simulate_path(
+    bird_y: f32,
+    velocity: f32,
+    gaps: &[(f32, f32)],
+    speed: f32,
+    flight: Flight,
+) -> Vec<Vec2> {
+    let Some(&(end, _)) = gaps.last() else {
+        return Vec::new();
+    };
+    let mut bird = Bird {
+        velocity,
+        flap_hold: None,
+    };
+    let (mut y, mut ahead) = (bird_y, 0.);
+    let mut path = vec![Vec2::new(ahead, y)];
+    for _ in 0..MAX_SIMULATION_STEPS {
+        if ahead >= end {
+            break;
+        }
+        let target = gaps
+            .iter()
+            .find(|&&(cleared_at, _)| cleared_at > ahead)
+            .map_or(0., |&(_, aim)| aim);
+        let flap = should_flap(y, bird.velocity, target, speed);
+        let flight = Flight {
+            distance: flight.distance + ahead,
+            ..flight
+        };
+        y = flight.step(&mut bird, y, flap, false, SIMULATION_STEP);
+        ahead += speed * SIMULATION_STEP;
+        path.push(Vec2::new(ahead, y));
+    }
+    path
+}
+
+/// Top and bottom pipe of the first pair that hasn't scored yet, which is
+/// the one the bird still has to get through.
+pub fn next_pair<'a>(
+    pipe_queue: &PipeQueue,
+    score: &Score,
+    obstacle_query: &'a Query<(&Obstacle, &Transform)>,
+) -> Option<[&'a Transform; 2]> {
+    pipe_queue
+        .0
+        .iter()
+        .filter_map(|&pair| obstacle_query.get_many(pair).ok())
+        .find(|[(obstacle, _), _]| !score.has_scored(obstacle.pair_number))
+        .map(|[(_, top), (_, bottom)]| [top, bottom])
+}
+
+/// Replaces this frame's player input with the autopilot's decision.
+pub fn drive_autopilot(
+    game_manager: Res<GameManager>,
+    difficulty: Res<Difficulty>,
+    score: Res<Score>,
+    pipe_queue: Res<PipeQueue>,
+    bird_query: Query<(&Bird, &Transform), With<Lead>>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+    mut flap_input: ResMut<FlapInput>,
+) {
+    if !game_manager.autopilot {
+        return;
+    }
+    let Ok((bird, bird_transform)) = bird_query.get_single() else {
+        return;
+    };
+
+    let next_gap = next_pair(&pipe_queue, &score, &obstacle_query)
+        .map(|[top, bottom]| gap_center(top.translation, bottom.translation));
+    let gap_half_height = game_manager.effective_gap_size(difficulty.gap_size()) * PIXEL_RATIO;
+    let target = aim_height(next_gap.unwrap_or(0.), gap_half_height);
+
+    flap_input.just_pressed = should_flap(
+        bird_transform.translation.y,
+        bird.velocity,
+        target,
+        difficulty.scroll_speed(),
+    );
+    flap_input.held = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ControlScheme, SoftCeiling},
+        WINDOW_SIZE,
+    };
+
+    fn flight(mode_gravity_scale: f32, soft_ceiling: Option<SoftCeiling>) -> Flight {
+        Flight {
+            control_scheme: ControlScheme::Flap,
+            variable_flap: false,
+            mode_gravity_scale,
+            own_gravity_scale: 1.,
+            distance: 0.,
+            tutorial_lead_in: 0.,
+            gravity_flipped: false,
+            dive_strength: None,
+            soft_ceiling,
+            half_height: WINDOW_SIZE.y / 2.,
+        }
+    }
+
+    #[test]
+    fn flaps_when_falling_below_the_gap() {
+        assert!(should_flap(-40., -100., 0., OBSTACLE_SCROLL_SPEED));
+        assert!(should_flap(0., 0., 0., OBSTACLE_SCROLL_SPEED));
+    }
+
+    #[test]
+    fn never_flaps_on_the_way_up() {
+        assert!(!should_flap(-200., 100., 0., OBSTACLE_SCROLL_SPEED));
+        assert!(!should_flap(-200., 1., 0., OBSTACLE_SCROLL_SPEED * 3.));
+    }
+
+    #[test]
+    fn waits_while_well_above_the_gap() {
+        assert!(!should_flap(100., -100., 0., OBSTACLE_SCROLL_SPEED));
+    }
+
+    #[test]
+    fn reacts_earlier_at_higher_speeds() {
+        assert!(!should_flap(3., -100., 0., OBSTACLE_SCROLL_SPEED));
+        assert!(should_flap(3., -100., 0., OBSTACLE_SCROLL_SPEED * 2.));
+        // Slower than the starting speed doesn't leave it any later
+        assert_eq!(
+            should_flap(3., -100., 0., OBSTACLE_SCROLL_SPEED / 2.),
+            should_flap(3., -100., 0., OBSTACLE_SCROLL_SPEED)
+        );
+    }
+
+    #[test]
+    fn simulated_path_falls_with_the_mode_gravity() {
+        // Aiming far below means it never flaps
+        let gaps = [(200., -10_000.)];
+        let end = |flight| {
+            simulate_path(0., 0., &gaps, OBSTACLE_SCROLL_SPEED, flight)
+                .last()
+                .unwrap()
+                .y
+        };
+        assert!(end(flight(0.5, None)) > end(flight(1., None)));
+    }
+
+    #[test]
+    fn simulated_path_stays_under_the_soft_ceiling() {
+        let top = WINDOW_SIZE.y / 2.;
+        let path = simulate_path(
+            top - 10.,
+            FLAP_FORCE,
+            &[(200., top)],
+            OBSTACLE_SCROLL_SPEED,
+            flight(1., Some(SoftCeiling::default())),
+        );
+        assert!(path.iter().all(|point| point.y <= top));
+    }
+
+    #[test]
+    fn aim_stays_inside_a_narrow_gap() {
+        let gap_half_height = AUTOPILOT_SAFETY_MARGIN * 2.;
+        let aim = aim_height(50., gap_half_height);
+        assert!(aim < 50.);
+        assert!(aim >= 50. - gap_half_height + AUTOPILOT_SAFETY_MARGIN);
+        assert_eq!(aim_height(50., AUTOPILOT_SAFETY_MARGIN / 2.), 50.);
+    }
+}
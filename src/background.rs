@@ -0,0 +1,180 @@
+use bevy::{prelude::*, render::mesh::VertexAttributeValues, window::PrimaryWindow};
+
+use crate::{
+    camera::WorldCamera,
+    config::GameConfig,
+    layers::{PARALLAX_Z, PARALLAX_Z_STEP, SKY_Z},
+    Difficulty, GameManager, GameState, PIXEL_RATIO,
+};
+
+/// The sky behind everything and the parallax layers scrolling in front of
+/// it, starting from the colors and layers in the config.
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        let config = app.world().resource::<GameConfig>();
+        let sky_gradient = SkyGradient {
+            top: Color::srgb_from_array(config.sky_top_color),
+            bottom: Color::srgb_from_array(config.sky_bottom_color),
+        };
+
+        app.insert_resource(sky_gradient)
+            .add_systems(Startup, (setup_sky, setup_parallax))
+            .add_systems(Update, (update_sky, scroll_parallax));
+    }
+}
+
+/// Colors of the vertical sky gradient drawn behind everything. Anything
+/// that wants to shift the sky, like a day/night cycle, only needs to change
+/// this resource.
+#[derive(Resource, Clone, Copy)]
+pub struct SkyGradient {
+    pub top: Color,
+    pub bottom: Color,
+}
+
+/// Unit quad stretched over the whole window.
+#[derive(Component)]
+pub struct Sky;
+
+pub fn setup_sky(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((
+        Mesh2d(meshes.add(Rectangle::new(1., 1.))),
+        MeshMaterial2d(materials.add(ColorMaterial::default())),
+        Transform::from_xyz(0., 0., SKY_Z),
+        Sky,
+    ));
+}
+
+pub fn update_sky(
+    gradient: Res<SkyGradient>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut sky_query: Query<(Ref<Mesh2d>, &mut Transform), With<Sky>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok((mesh, mut transform)) = sky_query.get_single_mut() else {
+        return;
+    };
+
+    if let Ok(window) = window_query.get_single() {
+        let scale = Vec3::new(window.width(), window.height(), 1.);
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+
+    if !gradient.is_changed() && !mesh.is_added() {
+        return;
+    }
+    let Some(mesh) = meshes.get_mut(&mesh.0) else {
+        return;
+    };
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+
+    let top = LinearRgba::from(gradient.top).to_f32_array();
+    let bottom = LinearRgba::from(gradient.bottom).to_f32_array();
+    let colors: Vec<[f32; 4]> = positions
+        .iter()
+        .map(|position| if position[1] > 0. { top } else { bottom })
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+}
+
+/// Strip of scenery repeated across the screen, scrolling at a fraction of
+/// the pipes' speed.
+#[derive(Component)]
+pub struct ParallaxLayer {
+    pub speed_factor: f32,
+    /// Height of the bottom of the strip above the bottom of the screen, in
+    /// world units.
+    pub bottom: f32,
+}
+
+pub fn setup_parallax(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    asset_server: Res<AssetServer>,
+) {
+    for (index, layer) in config.parallax_layers.iter().enumerate() {
+        commands.spawn((
+            Sprite {
+                image: asset_server.load(&layer.texture),
+                image_mode: SpriteImageMode::Tiled {
+                    tile_x: true,
+                    tile_y: false,
+                    stretch_value: PIXEL_RATIO,
+                },
+                ..Default::default()
+            },
+            Transform::from_xyz(0., 0., PARALLAX_Z + index as f32 * PARALLAX_Z_STEP),
+            ParallaxLayer {
+                speed_factor: layer.speed_factor,
+                bottom: layer.bottom * PIXEL_RATIO,
+            },
+        ));
+    }
+}
+
+/// Keeps a layer's offset within one tile of the middle. The strip repeats
+/// every tile, so jumping back by a whole one can't be seen.
+pub fn wrap_parallax_offset(offset: f32, tile_width: f32) -> f32 {
+    (offset + tile_width / 2.).rem_euclid(tile_width) - tile_width / 2.
+}
+
+/// Sizes each layer to cover the view with a tile to spare on either side
+/// once its image is in, and scrolls it along with the pipes while playing.
+#[allow(clippy::too_many_arguments)]
+pub fn scroll_parallax(
+    time: Res<Time>,
+    game_manager: Res<GameManager>,
+    difficulty: Res<Difficulty>,
+    state: Res<State<GameState>>,
+    images: Res<Assets<Image>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<&OrthographicProjection, With<WorldCamera>>,
+    mut layer_query: Query<(&ParallaxLayer, &mut Sprite, &mut Transform)>,
+) {
+    let window_width = window_query
+        .get_single()
+        .map_or(0., |window| window.width());
+    let scale = camera_query
+        .iter()
+        .map(|projection| projection.scale)
+        .fold(1., f32::max);
+    let view_width = window_width.max(game_manager.window_dimentions.x) * scale;
+    let scrolling = *state.get() == GameState::Playing;
+
+    for (layer, mut sprite, mut transform) in layer_query.iter_mut() {
+        let Some(image) = images.get(&sprite.image) else {
+            continue;
+        };
+        let tile = image.size_f32() * PIXEL_RATIO;
+        let size = Vec2::new(view_width + 2. * tile.x, tile.y);
+        if sprite.custom_size != Some(size) {
+            sprite.custom_size = Some(size);
+        }
+
+        let y = -game_manager.window_dimentions.y / 2. + layer.bottom + tile.y / 2.;
+        let mut x = transform.translation.x;
+        if scrolling {
+            x -= time.delta_secs()
+                * difficulty.scroll_speed()
+                * layer.speed_factor
+                * game_manager.facing;
+        }
+        let x = wrap_parallax_offset(x, tile.x);
+        if transform.translation.x != x || transform.translation.y != y {
+            transform.translation.x = x;
+            transform.translation.y = y;
+        }
+    }
+}
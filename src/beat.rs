@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+use crate::{config::GameConfig, Obstacle, PIXEL_RATIO};
+
+/// How much wider pipes get at the peak of a beat.
+const BEAT_PULSE_AMOUNT: f32 = 0.08;
+
+/// Position in the background music, wrapped at its loop length so beats
+/// line up again each time the track starts over.
+#[derive(Resource, Default)]
+pub struct BeatClock {
+    pub elapsed: f32,
+}
+
+pub fn update_beat_clock(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut beat_clock: ResMut<BeatClock>,
+) {
+    beat_clock.elapsed += time.delta_secs();
+    if config.music_loop_length > 0. {
+        beat_clock.elapsed %= config.music_loop_length;
+    }
+}
+
+/// Strength of the pulse in `0..=1`, peaking on each beat and easing out
+/// until the next one.
+pub fn beat_strength(elapsed: f32, bpm: f32) -> f32 {
+    let phase = (elapsed * bpm / 60.).fract();
+    (1. - phase).powi(2)
+}
+
+/// Only the width pulses: stretching the height would move the visible edge
+/// of the gap away from where collisions happen, which are worked out from
+/// the pipe's fixed size and never its scale.
+pub fn beat_pulse(
+    config: Res<GameConfig>,
+    beat_clock: Res<BeatClock>,
+    mut pipe_query: Query<&mut Transform, With<Obstacle>>,
+) {
+    let pulse = if config.beat_pulse && config.reduced_motion.pulsing_allowed() {
+        beat_strength(beat_clock.elapsed, config.music_bpm)
+    } else {
+        0.
+    };
+    let width = PIXEL_RATIO * (1. + BEAT_PULSE_AMOUNT * pulse);
+
+    for mut transform in pipe_query.iter_mut() {
+        if transform.scale.x != width {
+            transform.scale.x = width;
+        }
+    }
+}
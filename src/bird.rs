@@ -0,0 +1,831 @@
+//! The player's bird: how it flies, what it's made of and how it looks
+//! while doing it. `update_bird` flies it and tells of its flaps and crashes
+//! with `BirdFlapped` and `BirdDied`, which the rest of the game reacts to.
+
+use bevy::prelude::*;
+
+use crate::{
+    animation,
+    config::{ControlScheme, GameConfig, SoftCeiling},
+    flock::{Flockmate, FLOCKMATE_ALPHA},
+    input,
+    modes::ModeRules,
+    obstacle::{hit_pipe, Obstacle, PipeQueue},
+    Difficulty, GameManager, GameState, RunSet, FLAP_FORCE, GRAVITY, PIXEL_RATIO,
+    TERMINAL_VELOCITY, VELOCITY_TO_ROTATION_RATIO,
+};
+
+/// With variable flaps a tap gives the small hop, and holding through
+/// `VARIABLE_FLAP_WINDOW` builds up to the large one.
+const SMALL_FLAP_FORCE: f32 = 350.;
+const LARGE_FLAP_FORCE: f32 = 600.;
+const VARIABLE_FLAP_WINDOW: f32 = 0.12;
+/// Gravity at the start of a tutorial stretch, relative to the usual.
+const TUTORIAL_GRAVITY_SCALE: f32 = 0.4;
+/// Diving lifts the cap so the extra acceleration isn't wasted.
+const DIVE_TERMINAL_VELOCITY: f32 = 1500.;
+/// Rotation ratio while diving, lower tips the nose down sooner.
+const DIVE_VELOCITY_TO_ROTATION_RATIO: f32 = 3.;
+/// Upward acceleration while holding with the glide controls. Just enough
+/// over gravity for a slow climb, so gaps still have to be lined up early.
+const GLIDE_LIFT: f32 = 2800.;
+const GLIDE_MAX_RISE_SPEED: f32 = 300.;
+/// Most the bird is stretched at full upward speed and squashed on impact,
+/// at an intensity of 1. It keeps its area either way.
+const STRETCH_AMOUNT: f32 = 0.2;
+const SQUASH_AMOUNT: f32 = 0.35;
+/// How long the bird stays hidden or shown while blinking.
+const INVULNERABILITY_BLINK_INTERVAL: f32 = 0.1;
+/// Opacity of an invulnerable bird when blinking is turned off.
+const INVULNERABILITY_FADE_ALPHA: f32 = 0.5;
+
+/// The bird and its flock: flying them in their places in `RunSet`, and the
+/// systems that only change how they look. Reads the config, so the app
+/// needs one before this is added.
+pub struct BirdPlugin;
+
+impl Plugin for BirdPlugin {
+    fn build(&self, app: &mut App) {
+        let bird_frames = app.world().resource::<GameConfig>().bird_frames;
+
+        app.add_event::<BirdFlapped>()
+            .add_event::<BirdDied>()
+            .init_resource::<LastDeath>()
+            .add_systems(
+                Update,
+                (
+                    (update_invulnerability, update_gravity_flip).in_set(RunSet::Timers),
+                    (update_bird, handle_bird_deaths)
+                        .chain()
+                        .in_set(RunSet::Bird),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    flip_bird_sprite,
+                    squash_and_stretch_bird,
+                    fade_invulnerable_bird,
+                ),
+            );
+
+        if bird_frames > 1 {
+            app.add_systems(OnExit(GameState::Loading), animation::setup_wing_animation)
+                .add_systems(
+                    Update,
+                    animation::animate_wings
+                        .run_if(in_state(GameState::Menu).or(in_state(GameState::Playing))),
+                );
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Bird {
+    pub velocity: f32,
+    /// Seconds the latest variable flap has been held, while holding can
+    /// still make it bigger.
+    pub flap_hold: Option<f32>,
+}
+
+/// Pipe hits a bird can still take. The hit that would bring it to 0 is
+/// the one that kills it.
+#[derive(Component)]
+pub struct Health(pub u32);
+
+/// Flattens the bird for a moment as it crashes.
+#[derive(Component)]
+pub struct Squash {
+    pub timer: Timer,
+}
+
+/// Turns gravity upside down until the timer runs out: the bird falls
+/// upwards, flapping pushes it down and the ceiling becomes deadly.
+#[derive(Component, Clone)]
+pub struct GravityFlip {
+    pub timer: Timer,
+}
+
+impl GravityFlip {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// What ended a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathCause {
+    Floor,
+    /// Only while gravity is flipped, otherwise the sky is open.
+    Ceiling,
+    Pipe {
+        pipe: Entity,
+    },
+}
+
+impl DeathCause {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeathCause::Floor => "floor",
+            DeathCause::Ceiling => "ceiling",
+            DeathCause::Pipe { .. } => "pipe",
+        }
+    }
+}
+
+/// Sent by `update_bird` for every flap a bird makes.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BirdFlapped {
+    pub bird: Entity,
+}
+
+/// Sent by `update_bird` when a bird crashes. The run only ends when it was
+/// the last one flying, the others are left down until the next.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BirdDied {
+    pub bird: Entity,
+    pub cause: DeathCause,
+    pub last: bool,
+}
+
+/// How the bird died last, `None` until it has this run or when the run
+/// ended some other way, like a time attack running out.
+#[derive(Resource, Default)]
+pub struct LastDeath(pub Option<DeathCause>);
+
+/// Gives a bird a flap key of its own in place of the shared flap controls,
+/// for more than one player on a keyboard.
+#[derive(Component)]
+pub struct FlapBinding(pub KeyCode);
+
+/// On a bird that crashed while others were still flying. It sits out the
+/// rest of the run.
+#[derive(Component)]
+pub struct Downed;
+
+/// On the one bird the rest of the game follows: the one scored, shown and
+/// checked for a new best. When it's downed the nearest bird still flying
+/// takes over, so the last bird left always has it.
+#[derive(Component)]
+pub struct Lead;
+
+/// Pipes can't hurt a bird while this is on it, and it blinks to show it.
+#[derive(Component, Clone)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// How a bird flies on a frame: the controls, the run's gravity and
+/// ceiling, and the bird's own pull. `update_bird` steps every bird with it,
+/// and anything predicting where a bird will go steps the same way.
+#[derive(Clone, Copy, Debug)]
+pub struct Flight {
+    pub control_scheme: ControlScheme,
+    pub variable_flap: bool,
+    /// Gravity relative to the usual in the mode being played.
+    pub mode_gravity_scale: f32,
+    /// A flockmate's gravity relative to the rest of the flock.
+    pub own_gravity_scale: f32,
+    /// How far into the run, and how long its tutorial stretch is, for the
+    /// tutorial's gentler gravity.
+    pub distance: f32,
+    pub tutorial_lead_in: f32,
+    pub gravity_flipped: bool,
+    /// The extra pull while diving, `None` while not.
+    pub dive_strength: Option<f32>,
+    pub soft_ceiling: Option<SoftCeiling>,
+    pub half_height: f32,
+}
+
+impl Flight {
+    /// How a bird with none of its own tweaks flies `distance` into a run.
+    pub fn new(
+        config: &GameConfig,
+        game_manager: &GameManager,
+        rules: &ModeRules,
+        distance: f32,
+        diving: bool,
+    ) -> Self {
+        Self {
+            control_scheme: config.control_scheme,
+            variable_flap: config.variable_flap,
+            mode_gravity_scale: rules.gravity_scale,
+            own_gravity_scale: 1.,
+            distance,
+            tutorial_lead_in: game_manager.tutorial_lead_in,
+            gravity_flipped: false,
+            dive_strength: game_manager.dive_strength.filter(|_| diving),
+            soft_ceiling: game_manager.soft_ceiling,
+            half_height: game_manager.window_dimentions.y / 2.,
+        }
+    }
+
+    /// Flies `bird` at height `y` on by `delta` seconds, with the flap
+    /// controls `flap_pressed` this frame and `flap_held` down. Returns its
+    /// new height, leaving the edges of the screen to the caller.
+    pub fn step(
+        &self,
+        bird: &mut Bird,
+        y: f32,
+        flap_pressed: bool,
+        flap_held: bool,
+        delta: f32,
+    ) -> f32 {
+        // Velocity is worked out as if gravity pointed down and turned back
+        // around afterwards, so every control flips along with it
+        let gravity_direction = if self.gravity_flipped { -1. } else { 1. };
+        let mut velocity = bird.velocity * gravity_direction;
+        let gravity_scale =
+            tutorial_gravity_scale(self.distance, self.tutorial_lead_in) * self.mode_gravity_scale;
+        // Flaps weaken along with gravity so a hop still rises as high, only
+        // slower. They leave out a flockmate's own pull, which is what
+        // spreads the flock
+        let flap_scale = gravity_scale.sqrt();
+        let gravity_scale = gravity_scale * self.own_gravity_scale;
+
+        let gliding = self.control_scheme == ControlScheme::Glide && flap_held;
+        let flapping = self.control_scheme == ControlScheme::Flap;
+        if flap_pressed {
+            if flapping {
+                velocity = flap_scale
+                    * if self.variable_flap {
+                        variable_flap_force(0.)
+                    } else {
+                        FLAP_FORCE
+                    };
+                bird.flap_hold = self.variable_flap.then_some(0.);
+            }
+        } else if let Some(held) = bird.flap_hold.take() {
+            // Letting go early keeps the hop as it is
+            if flapping && flap_held {
+                let held = held + delta;
+                velocity = velocity.max(variable_flap_force(held) * flap_scale);
+                bird.flap_hold = (held < VARIABLE_FLAP_WINDOW).then_some(held);
+            }
+        }
+
+        let terminal_velocity = match self.dive_strength {
+            Some(_) => DIVE_TERMINAL_VELOCITY,
+            None => TERMINAL_VELOCITY,
+        };
+        let lift = if gliding { GLIDE_LIFT } else { 0. };
+        velocity -= delta * gravity_scale * (GRAVITY + self.dive_strength.unwrap_or(0.) - lift);
+        velocity = velocity.max(-terminal_velocity);
+        if gliding {
+            velocity = velocity.min(GLIDE_MAX_RISE_SPEED);
+        }
+        // Only the top of the screen gets a soft ceiling, with gravity
+        // flipped it's deadly and leaving it is how the run ends
+        if let Some(soft_ceiling) = self.soft_ceiling.filter(|_| !self.gravity_flipped) {
+            // Scaled from the fastest any flap climbs, so it's left alone
+            // below the slowing
+            let rise_cap = LARGE_FLAP_FORCE * soft_ceiling.damping(self.half_height - y);
+            velocity = velocity.min(rise_cap);
+        }
+        bird.velocity = velocity * gravity_direction;
+        y + bird.velocity * delta
+    }
+
+    /// The same flight with plain flaps, whatever the controls. The
+    /// autopilot only ever taps, which this makes a full flap.
+    pub fn plain_flaps(self) -> Self {
+        Self {
+            control_scheme: ControlScheme::Flap,
+            variable_flap: false,
+            ..self
+        }
+    }
+
+    /// How far the bird tips for its speed, lower tips it sooner.
+    pub fn rotation_ratio(&self) -> f32 {
+        match self.dive_strength {
+            Some(_) => DIVE_VELOCITY_TO_ROTATION_RATIO,
+            None => VELOCITY_TO_ROTATION_RATIO,
+        }
+    }
+}
+
+pub fn spawn_bird(
+    commands: &mut Commands,
+    game_manager: &GameManager,
+    config: &GameConfig,
+    rules: &ModeRules,
+    image: &Handle<Image>,
+) {
+    commands.spawn((
+        Sprite {
+            image: image.clone(),
+            flip_x: config.mirror_mode,
+            ..Default::default()
+        },
+        Transform::from_translation(game_manager.bird_start)
+            .with_scale(Vec3::splat(PIXEL_RATIO * game_manager.bird_scale)),
+        Bird {
+            velocity: 0.,
+            flap_hold: None,
+        },
+        Health(rules.max_health),
+        Lead,
+    ));
+}
+
+/// Flies every bird that's still up, telling of each flap and crash as it
+/// happens.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_bird(
+    mut commands: Commands,
+    mut bird_query: Query<
+        (
+            Entity,
+            &mut Bird,
+            &mut Transform,
+            Has<Invulnerable>,
+            Has<GravityFlip>,
+            Option<&FlapBinding>,
+            Option<&Flockmate>,
+            Option<&mut Health>,
+        ),
+        (Without<Obstacle>, Without<Downed>),
+    >,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+    time: Res<Time>,
+    game_manager: Res<GameManager>,
+    rules: Res<ModeRules>,
+    pipe_queue: Res<PipeQueue>,
+    keys: Res<ButtonInput<KeyCode>>,
+    flap_input: Res<input::FlapInput>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    mut flaps: EventWriter<BirdFlapped>,
+    mut deaths: EventWriter<BirdDied>,
+) {
+    // Read from the config every frame so switching schemes in the menu
+    // applies straight away
+    let run_flight = Flight::new(
+        &config,
+        &game_manager,
+        &rules,
+        difficulty.distance,
+        keys.pressed(KeyCode::ArrowDown),
+    );
+    let run_flight = if game_manager.autopilot {
+        run_flight.plain_flaps()
+    } else {
+        run_flight
+    };
+    let half_height = run_flight.half_height;
+    let mut flying = bird_query.iter().count();
+    for (
+        entity,
+        mut bird,
+        mut transform,
+        invulnerable,
+        gravity_flipped,
+        binding,
+        flockmate,
+        mut health,
+    ) in bird_query.iter_mut()
+    {
+        let (flap_pressed, flap_held) = match binding {
+            Some(FlapBinding(key)) => (keys.just_pressed(*key), keys.pressed(*key)),
+            None => (flap_input.just_pressed, flap_input.held),
+        };
+        let flight = Flight {
+            gravity_flipped,
+            own_gravity_scale: flockmate.map_or(1., |flockmate| flockmate.gravity_scale),
+            ..run_flight
+        };
+        transform.translation.y = flight.step(
+            &mut bird,
+            transform.translation.y,
+            flap_pressed,
+            flap_held,
+            time.delta_secs(),
+        );
+        if flap_pressed {
+            flaps.send(BirdFlapped { bird: entity });
+        }
+
+        transform.rotation = Quat::from_axis_angle(
+            Vec3::Z,
+            f32::clamp(bird.velocity / flight.rotation_ratio(), -90., 90.).to_radians()
+                * game_manager.facing,
+        );
+
+        // Where the bird left the screen when it wraps this frame, checked
+        // alongside where it came back so a pipe at either edge still counts
+        let mut wrapped_from = None;
+        let mut death = None;
+
+        if transform.translation.y < -half_height {
+            if !rules.lethal_edges {
+                wrapped_from = Some(transform.translation.with_y(-half_height));
+                transform.translation.y += half_height * 2.;
+            } else {
+                death = Some(DeathCause::Floor);
+            }
+        } else if transform.translation.y > half_height {
+            if !rules.lethal_edges {
+                wrapped_from = Some(transform.translation.with_y(half_height));
+                transform.translation.y -= half_height * 2.;
+            } else if gravity_flipped {
+                death = Some(DeathCause::Ceiling);
+            }
+        }
+
+        if death.is_none() && !invulnerable {
+            death = std::iter::once(transform.translation)
+                .chain(wrapped_from)
+                .find_map(|position| {
+                    hit_pipe(position, &pipe_queue, &obstacle_query, &game_manager)
+                })
+                .map(|pipe| DeathCause::Pipe { pipe });
+        }
+
+        // A pipe only costs a hit point while there are some to spare, and
+        // the invulnerability that follows keeps the same pipe from taking
+        // another on the next frame
+        if let (Some(DeathCause::Pipe { .. }), Some(health)) = (death, health.as_mut()) {
+            if health.0 > 1 {
+                health.0 -= 1;
+                death = None;
+                commands
+                    .entity(entity)
+                    .insert(Invulnerable::new(game_manager.hit_invulnerability));
+            }
+        }
+
+        if let Some(cause) = death {
+            deaths.send(BirdDied {
+                bird: entity,
+                cause,
+                last: flying == 1,
+            });
+            flying -= 1;
+        }
+    }
+}
+
+/// Ends the run when the last bird flying crashes, and downs any other. A
+/// downed lead hands over to the nearest bird still flying.
+#[allow(clippy::type_complexity)]
+pub fn handle_bird_deaths(
+    mut commands: Commands,
+    mut deaths: EventReader<BirdDied>,
+    bird_query: Query<(Entity, &Transform, Has<Lead>), (With<Bird>, Without<Downed>)>,
+    mut last_death: ResMut<LastDeath>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let mut downed = Vec::new();
+    for death in deaths.read() {
+        if death.last {
+            last_death.0 = Some(death.cause);
+            next_state.set(GameState::Dying);
+        } else {
+            commands
+                .entity(death.bird)
+                .insert((Downed, Visibility::Hidden));
+            downed.push(death.bird);
+        }
+    }
+
+    let Some((lead, lead_transform, _)) = bird_query
+        .iter()
+        .find(|(entity, _, is_lead)| *is_lead && downed.contains(entity))
+    else {
+        return;
+    };
+    let lead_y = lead_transform.translation.y;
+    let nearest = bird_query
+        .iter()
+        .filter(|(entity, _, _)| !downed.contains(entity))
+        .min_by(|(_, a, _), (_, b, _)| {
+            let a = (a.translation.y - lead_y).abs();
+            let b = (b.translation.y - lead_y).abs();
+            a.total_cmp(&b)
+        });
+    if let Some((next_lead, _, _)) = nearest {
+        commands.entity(lead).remove::<Lead>();
+        commands.entity(next_lead).insert(Lead);
+    }
+}
+
+/// How strongly gravity pulls `distance` into a run that opens with a
+/// tutorial stretch `lead_in` long. It eases from the gentle tutorial pull
+/// back up to normal over the stretch, so there's no jolt at the end of it.
+pub fn tutorial_gravity_scale(distance: f32, lead_in: f32) -> f32 {
+    if lead_in <= 0. {
+        return 1.;
+    }
+    let progress = (distance / lead_in).clamp(0., 1.);
+    TUTORIAL_GRAVITY_SCALE + (1. - TUTORIAL_GRAVITY_SCALE) * progress
+}
+
+/// Upward speed of a variable flap that has been held for `held` seconds.
+pub fn variable_flap_force(held: f32) -> f32 {
+    let charge = (held / VARIABLE_FLAP_WINDOW).clamp(0., 1.);
+    SMALL_FLAP_FORCE + (LARGE_FLAP_FORCE - SMALL_FLAP_FORCE) * charge
+}
+
+pub fn update_gravity_flip(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut GravityFlip)>,
+) {
+    for (entity, mut gravity_flip) in query.iter_mut() {
+        if gravity_flip.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<GravityFlip>();
+        }
+    }
+}
+
+/// Without blinking an invulnerable bird is faded instead. Worked out fresh
+/// each frame, so however the invulnerability ends the bird comes back.
+#[allow(clippy::type_complexity)]
+fn fade_invulnerable_bird(
+    config: Res<GameConfig>,
+    mut bird_query: Query<(&mut Sprite, Has<Invulnerable>, Has<Flockmate>), With<Bird>>,
+) {
+    for (mut sprite, invulnerable, flockmate) in bird_query.iter_mut() {
+        let alpha = if flockmate { FLOCKMATE_ALPHA } else { 1. };
+        let alpha = if invulnerable && !config.reduced_motion.flashing_allowed() {
+            alpha * INVULNERABILITY_FADE_ALPHA
+        } else {
+            alpha
+        };
+        if sprite.color.alpha() != alpha {
+            sprite.color.set_alpha(alpha);
+        }
+    }
+}
+
+/// Scale of the bird's sprite across and up relative to its usual size at
+/// `velocity` in the direction flaps push it, or `squash` of the way
+/// through a crash. `intensity` scales how far it gets from its usual shape.
+pub fn squash_stretch(velocity: f32, squash: Option<f32>, intensity: f32) -> Vec2 {
+    let stretch = match squash {
+        // Flattened on impact, springing back by the end of the squash
+        Some(progress) => -SQUASH_AMOUNT * (1. - progress.clamp(0., 1.)),
+        None => STRETCH_AMOUNT * (velocity / LARGE_FLAP_FORCE).clamp(0., 1.),
+    };
+    let height = (1. + stretch * intensity.max(0.)).max(0.1);
+    Vec2::new(1. / height, height)
+}
+
+/// Only ever changes the sprite's scale, collisions go by the bird's size in
+/// `GameManager`.
+fn squash_and_stretch_bird(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    game_manager: Res<GameManager>,
+    mut bird_query: Query<(&Bird, &mut Transform, Option<&mut Squash>, Has<GravityFlip>)>,
+) {
+    let intensity = if config.reduced_motion.pulsing_allowed() {
+        config.squash_stretch
+    } else {
+        0.
+    };
+    let base_scale = PIXEL_RATIO * game_manager.bird_scale;
+    for (bird, mut transform, squash, gravity_flipped) in bird_query.iter_mut() {
+        let squash = squash.map(|mut squash| {
+            squash.timer.tick(time.delta());
+            squash.timer.fraction()
+        });
+        let flap_velocity = if gravity_flipped {
+            -bird.velocity
+        } else {
+            bird.velocity
+        };
+        let scale = squash_stretch(flap_velocity, squash, intensity) * base_scale;
+        let scale = scale.extend(base_scale);
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
+
+/// The bird is drawn upside down for as long as gravity is, however the
+/// flip ended.
+fn flip_bird_sprite(mut bird_query: Query<(&mut Sprite, Has<GravityFlip>), With<Bird>>) {
+    for (mut sprite, gravity_flipped) in bird_query.iter_mut() {
+        if sprite.flip_y != gravity_flipped {
+            sprite.flip_y = gravity_flipped;
+        }
+    }
+}
+
+pub fn update_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    // A downed bird stays hidden, whatever is left of its invulnerability
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility), Without<Downed>>,
+) {
+    for (entity, mut invulnerable, mut visibility) in query.iter_mut() {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            *visibility = Visibility::Inherited;
+            continue;
+        }
+        if !config.reduced_motion.flashing_allowed() {
+            continue;
+        }
+
+        let blink = (invulnerable.timer.elapsed_secs() / INVULNERABILITY_BLINK_INTERVAL) as u32;
+        *visibility = if blink.is_multiple_of(2) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::{modes::GameMode, OBSTACLE_HEIGHT};
+
+    const WINDOW_SIZE: Vec2 = Vec2::new(512., 512.);
+
+    /// A world with what `update_bird` reads, one frame into a run of `mode`.
+    fn bird_world(mode: GameMode) -> World {
+        let config = GameConfig::default();
+        let mut world = World::new();
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_secs_f32(1. / 60.));
+        world.insert_resource(time);
+        world.insert_resource(GameManager::new(&config, Handle::default(), WINDOW_SIZE));
+        world.insert_resource(mode.rules(&config));
+        world.insert_resource(config);
+        world.insert_resource(Difficulty::default());
+        world.init_resource::<PipeQueue>();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<input::FlapInput>();
+        world.init_resource::<Events<BirdFlapped>>();
+        world.init_resource::<Events<BirdDied>>();
+        world
+    }
+
+    fn spawn_bird_at(world: &mut World, y: f32, velocity: f32) -> Entity {
+        world
+            .spawn((
+                Bird {
+                    velocity,
+                    flap_hold: None,
+                },
+                Transform::from_xyz(0., y, 0.),
+            ))
+            .id()
+    }
+
+    fn spawn_pipe_pair_at(world: &mut World, x: f32) {
+        let pair = [(1., WINDOW_SIZE.y), (-1., 0.)].map(|(direction, y)| {
+            world
+                .spawn((
+                    Obstacle {
+                        pipe_direction: direction,
+                        pair_number: 0,
+                        flips_gravity: false,
+                        height: OBSTACLE_HEIGHT * PIXEL_RATIO,
+                        spacing_offset: 0.,
+                    },
+                    Transform::from_xyz(x, y, 0.),
+                ))
+                .id()
+        });
+        world.resource_mut::<PipeQueue>().0.push_back(pair);
+    }
+
+    /// Flies the birds for a frame, returning how each that crashed died.
+    fn fly(world: &mut World) -> Vec<(Entity, DeathCause)> {
+        world.run_system_once(update_bird).unwrap();
+        world
+            .resource_mut::<Events<BirdDied>>()
+            .drain()
+            .map(|death| (death.bird, death.cause))
+            .collect()
+    }
+
+    #[test]
+    fn falling_off_the_bottom_hits_the_floor() {
+        let mut world = bird_world(GameMode::Normal);
+        let bird = spawn_bird_at(&mut world, -WINDOW_SIZE.y / 2. + 1., -600.);
+        assert_eq!(fly(&mut world), [(bird, DeathCause::Floor)]);
+    }
+
+    #[test]
+    fn leaving_the_top_only_kills_with_gravity_flipped() {
+        let mut world = bird_world(GameMode::Normal);
+        spawn_bird_at(&mut world, WINDOW_SIZE.y / 2. + 10., 600.);
+        assert_eq!(fly(&mut world), []);
+
+        let mut world = bird_world(GameMode::Normal);
+        let bird = spawn_bird_at(&mut world, WINDOW_SIZE.y / 2. - 1., 600.);
+        world.entity_mut(bird).insert(GravityFlip::new(4.));
+        assert_eq!(fly(&mut world), [(bird, DeathCause::Ceiling)]);
+    }
+
+    #[test]
+    fn flying_into_a_pipe_hits_it() {
+        let mut world = bird_world(GameMode::Normal);
+        let bird = spawn_bird_at(&mut world, 0., 0.);
+        spawn_pipe_pair_at(&mut world, 0.);
+        let pipe = world.resource::<PipeQueue>().0[0][1];
+        assert_eq!(fly(&mut world), [(bird, DeathCause::Pipe { pipe })]);
+    }
+
+    #[test]
+    fn spare_health_survives_a_pipe() {
+        let mut world = bird_world(GameMode::Normal);
+        let bird = spawn_bird_at(&mut world, 0., 0.);
+        world.entity_mut(bird).insert(Health(2));
+        spawn_pipe_pair_at(&mut world, 0.);
+        assert_eq!(fly(&mut world), []);
+        world.flush();
+        assert_eq!(world.get::<Health>(bird).unwrap().0, 1);
+        assert!(world.get::<Invulnerable>(bird).is_some());
+    }
+
+    #[test]
+    fn each_bird_dies_on_its_own() {
+        let mut world = bird_world(GameMode::Normal);
+        world.init_resource::<LastDeath>();
+        world.init_resource::<NextState<GameState>>();
+        let falling = spawn_bird_at(&mut world, -WINDOW_SIZE.y / 2. + 1., -600.);
+        world.entity_mut(falling).insert(Lead);
+        let flying = spawn_bird_at(&mut world, 0., 0.);
+
+        // The first crash only downs its bird
+        world.run_system_once(update_bird).unwrap();
+        let deaths: Vec<_> = world
+            .resource::<Events<BirdDied>>()
+            .iter_current_update_events()
+            .map(|death| (death.bird, death.last))
+            .collect();
+        assert_eq!(deaths, [(falling, false)]);
+        world.run_system_once(handle_bird_deaths).unwrap();
+        assert!(world.get::<Downed>(falling).is_some());
+        assert!(world.get::<Downed>(flying).is_none());
+        // The bird still flying takes over as the lead
+        assert!(world.get::<Lead>(falling).is_none());
+        assert!(world.get::<Lead>(flying).is_some());
+        assert!(matches!(
+            *world.resource::<NextState<GameState>>(),
+            NextState::Unchanged
+        ));
+        assert_eq!(world.resource::<LastDeath>().0, None);
+        world.resource_mut::<Events<BirdDied>>().clear();
+
+        // Once the other bird hits a pipe the run is over
+        spawn_pipe_pair_at(&mut world, 0.);
+        let pipe = world.resource::<PipeQueue>().0[0][1];
+        world.run_system_once(update_bird).unwrap();
+        world.run_system_once(handle_bird_deaths).unwrap();
+        assert!(world.get::<Downed>(flying).is_none());
+        assert_eq!(
+            world.resource::<LastDeath>().0,
+            Some(DeathCause::Pipe { pipe })
+        );
+        assert!(matches!(
+            *world.resource::<NextState<GameState>>(),
+            NextState::Pending(GameState::Dying)
+        ));
+    }
+
+    #[test]
+    fn flockmates_fall_with_their_own_gravity() {
+        let mut world = bird_world(GameMode::Normal);
+        let lead = spawn_bird_at(&mut world, 0., 0.);
+        let flockmate = spawn_bird_at(&mut world, 0., 0.);
+        world.entity_mut(flockmate).insert(Flockmate {
+            gravity_scale: 1.5,
+            start_offset: 0.,
+        });
+        assert_eq!(fly(&mut world), []);
+        let velocity = |world: &World, bird| world.get::<Bird>(bird).unwrap().velocity;
+        assert!(velocity(&world, flockmate) < velocity(&world, lead));
+        assert!(velocity(&world, lead) < 0.);
+    }
+
+    #[test]
+    fn edges_wrap_without_lethal_edges() {
+        let mut world = bird_world(GameMode::Zen);
+        let bird = spawn_bird_at(&mut world, -WINDOW_SIZE.y / 2. + 1., -600.);
+        assert_eq!(fly(&mut world), []);
+        assert!(world.get::<Transform>(bird).unwrap().translation.y > 0.);
+    }
+}
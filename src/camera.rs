@@ -0,0 +1,200 @@
+//! Cameras for the two rendering setups the config can pick between.
+//!
+//! Free scaling draws the world straight to the window, so it stretches
+//! smoothly with it, but pixel art scaled by a fraction can shimmer as it
+//! moves. Pixel-perfect rendering draws the world at the fixed
+//! `WINDOW_SIZE` resolution into an image, then shows that image scaled by
+//! the largest whole factor that fits, leaving black bars around it. Pixels
+//! stay crisp and even at the cost of sometimes not filling the window. The
+//! HUD is drawn at the window's resolution either way.
+//!
+//! Zooming only changes what the world camera shows. Everything gameplay
+//! checks against stays in world units. Either way a window smaller than the
+//! play area shrinks the world down to fit rather than cutting off the floor
+//! or the top of the gaps.
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    window::PrimaryWindow,
+};
+
+use crate::{config::GameConfig, monitor, screenshot, GameManager, WINDOW_SIZE};
+
+/// Layer the scaled up canvas lives on, kept apart from the world.
+const CANVAS_LAYER: usize = 1;
+
+/// Zooming out further would show pipes appearing past the screen edge.
+const MIN_CAMERA_ZOOM: f32 = 0.8;
+const MAX_CAMERA_ZOOM: f32 = 2.;
+const CAMERA_ZOOM_STEP: f32 = 0.1;
+
+/// Keeps the world and the window showing it in shape: zooming, fitting
+/// the world to the window, keeping the window on a monitor and taking
+/// screenshots. The cameras themselves are spawned with the level.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (adjust_camera_zoom, apply_camera_zoom).chain(),
+                scale_canvas,
+                monitor::keep_window_on_screen,
+                screenshot::take_screenshot,
+            ),
+        );
+    }
+}
+
+/// The camera drawing the world, as opposed to the scaled canvas.
+#[derive(Component)]
+pub struct WorldCamera;
+
+/// Sprite showing the world as rendered at the fixed resolution.
+#[derive(Component)]
+pub struct Canvas;
+
+pub fn spawn_pixel_perfect_cameras(commands: &mut Commands, images: &mut Assets<Image>, zoom: f32) {
+    let size = Extent3d {
+        width: WINDOW_SIZE.x as u32,
+        height: WINDOW_SIZE.y as u32,
+        ..Default::default()
+    };
+    let mut canvas = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..Default::default()
+    };
+    canvas.resize(size);
+    let canvas = images.add(canvas);
+
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: -1,
+            target: RenderTarget::Image(canvas.clone()),
+            ..Default::default()
+        },
+        world_projection(zoom),
+        WorldCamera,
+        Msaa::Off,
+    ));
+    commands.spawn((
+        Sprite::from_image(canvas),
+        Canvas,
+        RenderLayers::layer(CANVAS_LAYER),
+    ));
+    commands.spawn((
+        Camera2d,
+        Camera {
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..Default::default()
+        },
+        Msaa::Off,
+        RenderLayers::layer(CANVAS_LAYER),
+    ));
+}
+
+/// Projection showing the world `zoom` times bigger than usual, kept within
+/// the allowed zoom range.
+pub fn world_projection(zoom: f32) -> OrthographicProjection {
+    OrthographicProjection {
+        scale: 1. / zoom.clamp(MIN_CAMERA_ZOOM, MAX_CAMERA_ZOOM),
+        ..OrthographicProjection::default_2d()
+    }
+}
+
+/// `+` and `-` zoom in and out in stream mode. The zoom is kept in the
+/// config so it's still there next time.
+pub fn adjust_camera_zoom(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<GameConfig>) {
+    if !config.stream_mode {
+        return;
+    }
+    let mut zoom = config.camera_zoom;
+    if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        zoom += CAMERA_ZOOM_STEP;
+    }
+    if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        zoom -= CAMERA_ZOOM_STEP;
+    }
+    let zoom = zoom.clamp(MIN_CAMERA_ZOOM, MAX_CAMERA_ZOOM);
+    if zoom != config.camera_zoom {
+        config.camera_zoom = zoom;
+    }
+}
+
+/// World units each window pixel has to cover for the whole play area to be
+/// visible, 1 unless the window is smaller than it.
+pub fn fit_scale(play_area: Vec2, window_size: Vec2) -> f32 {
+    (play_area / window_size).max_element().max(1.)
+}
+
+pub fn apply_camera_zoom(
+    config: Res<GameConfig>,
+    game_manager: Res<GameManager>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut OrthographicProjection, With<WorldCamera>>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    // The pixel-perfect camera draws to a canvas of a fixed size, which is
+    // fitted to the window on its own
+    let fit = if config.pixel_perfect {
+        1.
+    } else {
+        fit_scale(game_manager.window_dimentions, window.size())
+    };
+
+    let scale = world_projection(config.camera_zoom).scale * fit;
+    for mut projection in camera_query.iter_mut() {
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+    }
+}
+
+/// Largest whole number of window pixels per canvas pixel that still fits.
+/// Windows too small for even one get the canvas shrunk to fit instead.
+pub fn integer_scale(window_size: Vec2) -> f32 {
+    let scale = (window_size / WINDOW_SIZE).min_element();
+    if scale < 1. {
+        scale
+    } else {
+        scale.floor()
+    }
+}
+
+pub fn scale_canvas(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut canvas_query: Query<&mut Transform, With<Canvas>>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    let scale = Vec3::splat(integer_scale(window.size()));
+    for mut transform in canvas_query.iter_mut() {
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
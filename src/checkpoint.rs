@@ -0,0 +1,254 @@
+//! Practice checkpoints: pressing K during a run saves where everything is,
+//! and crashing afterwards puts it all back instead of ending the run. Only
+//! the lead bird is put back, flockmates carry on from wherever they are.
+//!
+//! A checkpoint is kept until the next one is set or the run ends, so the
+//! same stretch can be tried over and over. Setting one makes the run a
+//! practice run, which isn't recorded.
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::{
+    daily::DailyChallenge, gauntlet::PatternSource, modes::ModeRules, Bird, Difficulty, FinishLine,
+    GameRng, GameState, GravityFlip, Health, Invulnerable, LastDeath, Lead, Obstacle, PipeQueue,
+    RunTime, Score,
+};
+
+const CHECKPOINT_KEY: KeyCode = KeyCode::KeyK;
+
+/// Everything about a run that moves on from frame to frame, enough to play
+/// on from the same spot with the same pipes still to come.
+#[derive(Resource, Clone)]
+pub struct Checkpoint {
+    pub bird: BirdSnapshot,
+    /// Every pooled pipe, front of the queue first.
+    pub pipes: Vec<PipeSnapshot>,
+    pub pipe_queue: PipeQueue,
+    pub finish_line: Option<(Transform, Visibility)>,
+    pub difficulty: Difficulty,
+    pub score: Score,
+    pub run_time: f32,
+    /// Restored along with the rest so the pipes recycled afterwards get the
+    /// same gaps as the first time.
+    pub rng: StdRng,
+    pub pattern_source: PatternSource,
+}
+
+#[derive(Clone)]
+pub struct BirdSnapshot {
+    pub entity: Entity,
+    pub transform: Transform,
+    pub velocity: f32,
+    pub health: Option<u32>,
+    pub gravity_flip: Option<GravityFlip>,
+}
+
+#[derive(Clone)]
+pub struct PipeSnapshot {
+    pub entity: Entity,
+    pub obstacle: Obstacle,
+    pub transform: Transform,
+    pub visibility: Visibility,
+    pub color: Color,
+}
+
+impl Checkpoint {
+    /// Takes a snapshot of the run in `world`, `None` without a bird to take
+    /// it of.
+    pub fn capture(world: &mut World) -> Option<Self> {
+        let mut bird_query = world.query_filtered::<(
+            Entity,
+            &Bird,
+            &Transform,
+            Option<&Health>,
+            Option<&GravityFlip>,
+        ), (With<Lead>, Without<Obstacle>)>();
+        let bird = bird_query.get_single(world).ok().map(
+            |(entity, bird, transform, health, gravity_flip)| BirdSnapshot {
+                entity,
+                transform: *transform,
+                velocity: bird.velocity,
+                health: health.map(|health| health.0),
+                gravity_flip: gravity_flip.cloned(),
+            },
+        )?;
+
+        let pipe_queue = PipeQueue(world.resource::<PipeQueue>().0.clone());
+        let pipes = pipe_queue
+            .0
+            .iter()
+            .flatten()
+            .filter_map(|&entity| {
+                let entity_ref = world.get_entity(entity).ok()?;
+                Some(PipeSnapshot {
+                    entity,
+                    obstacle: entity_ref.get::<Obstacle>()?.clone(),
+                    transform: *entity_ref.get::<Transform>()?,
+                    visibility: *entity_ref.get::<Visibility>()?,
+                    color: entity_ref.get::<Sprite>()?.color,
+                })
+            })
+            .collect();
+
+        let mut finish_line_query =
+            world.query_filtered::<(&Transform, &Visibility), With<FinishLine>>();
+        let finish_line = finish_line_query
+            .get_single(world)
+            .ok()
+            .map(|(transform, visibility)| (*transform, *visibility));
+
+        Some(Self {
+            bird,
+            pipes,
+            pipe_queue,
+            finish_line,
+            difficulty: world.resource::<Difficulty>().clone(),
+            score: world.resource::<Score>().clone(),
+            run_time: world.resource::<RunTime>().0,
+            rng: world.resource::<GameRng>().0.clone(),
+            pattern_source: world.resource::<PatternSource>().clone(),
+        })
+    }
+
+    /// Puts the run in `world` back the way it was when the snapshot was
+    /// taken, with the bird briefly invulnerable like on any respawn.
+    pub fn restore(&self, world: &mut World) {
+        let spawn_invulnerability = world.resource::<ModeRules>().spawn_invulnerability;
+        if let Ok(mut bird_entity) = world.get_entity_mut(self.bird.entity) {
+            if let Some(mut bird) = bird_entity.get_mut::<Bird>() {
+                bird.velocity = self.bird.velocity;
+                bird.flap_hold = None;
+            }
+            if let Some(mut transform) = bird_entity.get_mut::<Transform>() {
+                *transform = self.bird.transform;
+            }
+            bird_entity.remove::<GravityFlip>().insert((
+                Invulnerable::new(spawn_invulnerability),
+                Visibility::Inherited,
+            ));
+            if let Some(health) = self.bird.health {
+                bird_entity.insert(Health(health));
+            }
+            if let Some(gravity_flip) = &self.bird.gravity_flip {
+                bird_entity.insert(gravity_flip.clone());
+            }
+        }
+
+        for pipe in &self.pipes {
+            let Ok(mut pipe_entity) = world.get_entity_mut(pipe.entity) else {
+                continue;
+            };
+            pipe_entity.insert((pipe.obstacle.clone(), pipe.transform, pipe.visibility));
+            if let Some(mut sprite) = pipe_entity.get_mut::<Sprite>() {
+                sprite.color = pipe.color;
+            }
+        }
+        world.resource_mut::<PipeQueue>().0 = self.pipe_queue.0.clone();
+
+        if let Some((transform, visibility)) = self.finish_line {
+            let mut finish_line_query =
+                world.query_filtered::<(&mut Transform, &mut Visibility), With<FinishLine>>();
+            for (mut finish_line_transform, mut finish_line_visibility) in
+                finish_line_query.iter_mut(world)
+            {
+                *finish_line_transform = transform;
+                *finish_line_visibility = visibility;
+            }
+        }
+
+        world.insert_resource(self.difficulty.clone());
+        world.insert_resource(self.score.clone());
+        world.insert_resource(RunTime(self.run_time));
+        world.insert_resource(GameRng(self.rng.clone()));
+        world.insert_resource(self.pattern_source.clone());
+    }
+}
+
+/// Saves a checkpoint when K is pressed. Daily challenge attempts are one
+/// go each, with nothing to practice.
+pub fn set_checkpoint(world: &mut World) {
+    if world.contains_resource::<DailyChallenge>()
+        || !world
+            .resource::<ButtonInput<KeyCode>>()
+            .just_pressed(CHECKPOINT_KEY)
+    {
+        return;
+    }
+    if let Some(checkpoint) = Checkpoint::capture(world) {
+        info!("Checkpoint set");
+        world.insert_resource(checkpoint);
+    }
+}
+
+/// Calls off the crash this frame and puts the run back at the checkpoint,
+/// when there is one.
+pub fn respawn_at_checkpoint(world: &mut World) {
+    if !matches!(
+        *world.resource::<NextState<GameState>>(),
+        NextState::Pending(GameState::Dying)
+    ) {
+        return;
+    }
+    let Some(checkpoint) = world.remove_resource::<Checkpoint>() else {
+        return;
+    };
+
+    checkpoint.restore(world);
+    world.resource_mut::<LastDeath>().0 = None;
+    world.resource_mut::<NextState<GameState>>().reset();
+    world.insert_resource(checkpoint);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{GameConfig, GapPattern},
+        headless::{build_headless_app, ScriptedRun},
+    };
+
+    /// Where the bird and every pipe are, and the score.
+    fn positions(world: &mut World) -> (Vec3, Vec<Vec3>, u32, u32) {
+        let mut bird_query = world.query_filtered::<&Transform, With<Bird>>();
+        let bird = bird_query.single(world).translation;
+        let pipes = world.resource::<PipeQueue>().0.clone();
+        let pipes = pipes
+            .iter()
+            .flatten()
+            .map(|&pipe| world.get::<Transform>(pipe).unwrap().translation)
+            .collect();
+        let score = world.resource::<Score>();
+        (bird, pipes, score.points, score.pipes_passed)
+    }
+
+    #[test]
+    fn restore_puts_the_run_back() {
+        let mut app = build_headless_app(&ScriptedRun {
+            config: GameConfig {
+                gap_pattern: GapPattern::Fixed,
+                ..default()
+            },
+            seed: 7,
+            flap_frames: (0..900).step_by(8).collect(),
+            max_frames: 900,
+        });
+        for _ in 0..300 {
+            app.update();
+        }
+        let checkpoint = Checkpoint::capture(app.world_mut()).unwrap();
+        let saved = positions(app.world_mut());
+        let saved_run_time = app.world().resource::<RunTime>().0;
+
+        for _ in 0..300 {
+            app.update();
+        }
+        assert_ne!(positions(app.world_mut()), saved);
+
+        checkpoint.restore(app.world_mut());
+        assert_eq!(positions(app.world_mut()), saved);
+        assert_eq!(app.world().resource::<RunTime>().0, saved_run_time);
+        let bird = checkpoint.bird.entity;
+        assert!(app.world().get::<Invulnerable>(bird).is_some());
+    }
+}
@@ -0,0 +1,647 @@
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modes::GameMode,
+    save::{data_dir, write_atomically},
+    WINDOW_SIZE,
+};
+
+const CONFIG_FILE_NAME: &str = "config.ron";
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GameConfig {
+    /// The mode last picked on the mode screen. Unset, it's worked out from
+    /// the flags below.
+    pub game_mode: Option<GameMode>,
+    pub time_attack: bool,
+    pub time_attack_duration: f32,
+    pub daily_challenge: bool,
+    /// Debug override that forgets today's daily attempt on launch.
+    pub reset_daily_challenge: bool,
+    /// Darken the pipes that come right before a difficulty step. Off by
+    /// default.
+    pub difficulty_warning: bool,
+    /// Pipes already on screen close up to the narrower gap when the
+    /// difficulty steps up, instead of only the pipes that come after.
+    pub gap_shrink_telegraph: bool,
+    /// Pipes scroll in from the left and the bird faces left.
+    pub mirror_mode: bool,
+    /// Falling off the bottom comes back in at the top and the other way
+    /// round, instead of the floor being deadly.
+    pub wrap_vertical: bool,
+    /// Holding Down makes the bird dive, accelerating by `dive_strength`
+    /// on top of gravity.
+    pub dive: bool,
+    pub dive_strength: f32,
+    /// Bird spawn offset from the center of the screen, in world units.
+    pub bird_start: [f32; 2],
+    /// Seconds pipes can't hurt the bird after it spawns or continues.
+    pub spawn_invulnerability: f32,
+    /// Pipes the bird can hit before it dies, 1 to die on the first.
+    /// Leaving the screen always ends the run.
+    pub max_health: u32,
+    /// Seconds pipes can't hurt the bird after one has.
+    pub hit_invulnerability: f32,
+    /// sRGB colors at the top and bottom of the sky gradient.
+    pub sky_top_color: [f32; 3],
+    pub sky_bottom_color: [f32; 3],
+    pub gap_pattern: GapPattern,
+    /// When the difficulty starts stepping up. Until then the gap and the
+    /// scroll speed stay at their starting values.
+    pub ramp_start: RampStart,
+    pub difficulty_curve: DifficultyCurve,
+    /// Log each run's flaps, passes and death to a CSV file for balancing.
+    pub telemetry: bool,
+    /// Render at a fixed resolution scaled by whole numbers, with black bars
+    /// when the window doesn't fit it evenly. See the `camera` module for
+    /// the tradeoffs.
+    pub pixel_perfect: bool,
+    /// Longest frame, in seconds, gameplay gets to see. A frame spike past
+    /// it plays out as a brief slowdown instead of the bird jumping ahead,
+    /// possibly through a pipe. Bevy's own default of a quarter second only
+    /// catches the worst hitches, something like 1/30 smooths most of them.
+    pub max_frame_delta: f32,
+    pub control_scheme: ControlScheme,
+    pub hitbox_shape: HitboxShape,
+    /// Size of the bird and its hitbox, with the gap following along. Big
+    /// birds are easier to follow but their runs don't count for records.
+    pub bird_scale: f32,
+    /// How strongly the bird stretches as it flaps up and squashes as it
+    /// crashes, 0 for not at all. Only the sprite changes shape, not the
+    /// hitbox.
+    pub squash_stretch: f32,
+    /// Pulse the pipes in time with the music, purely for looks.
+    pub beat_pulse: bool,
+    /// Tempo of the background music.
+    pub music_bpm: f32,
+    /// Seconds before the music loops, 0 when it doesn't.
+    pub music_loop_length: f32,
+    /// Faint lines leading from the bird to the next gaps.
+    pub gap_guide: bool,
+    /// A faint line tracing a good path through the next few gaps from
+    /// where the bird is.
+    pub perfect_line: bool,
+    /// Dying goes straight to a blank game over screen where the next flap
+    /// starts over.
+    pub quick_restart: bool,
+    /// Distance from the bird to the first pipe of a run, in world units.
+    /// The pipes after it keep the usual spacing.
+    pub first_pipe_distance: f32,
+    /// Stretch of open sky before the first pipe for new players to get a
+    /// feel for flapping, in world units, with gentler gravity and a hint on
+    /// screen. Difficulty only starts ramping up once it's behind them. 0 to
+    /// go straight to the pipes.
+    pub tutorial_lead_in: f32,
+    /// Chance for each new pair of pipes to flip gravity once flown through,
+    /// 0 for never.
+    pub gravity_flip_chance: f32,
+    /// Seconds gravity stays flipped.
+    pub gravity_flip_duration: f32,
+    /// How much closer the camera frames the world, 1 for the usual view.
+    pub camera_zoom: f32,
+    /// Extra controls for streaming and recording, like zooming with the +
+    /// and - keys.
+    pub stream_mode: bool,
+    /// Pattern file in the data directory to take the pipe gaps from, see
+    /// the `gauntlet` module for its format.
+    pub gauntlet: Option<String>,
+    /// Hitting a pipe freezes the game for a moment with the hitboxes shown.
+    pub kill_cam: bool,
+    /// Flaps start as a small hop that grows the longer the press is held.
+    pub variable_flap: bool,
+    /// Tint pipes by difficulty tier, from `pipe_tiers`.
+    pub pipe_tier_colors: bool,
+    /// Each tier's tint applies from its difficulty level up to the next
+    /// tier's.
+    pub pipe_tiers: Vec<PipeTier>,
+    /// Pairs of pipes before a finish line that wins the run, 0 for endless
+    /// play.
+    pub finish_line_pipes: u32,
+    /// The bird flies itself, for demos. Its runs aren't recorded.
+    pub autopilot: bool,
+    /// Pipe pairs kept around and reused as they scroll past, for modes that
+    /// put lots of them on the screen. Never fewer than the game needs.
+    pub pipe_pool_size: u32,
+    /// Most the distance between neighbouring pairs of pipes varies by, in
+    /// sprite pixels, so they don't arrive at a perfectly steady beat. 0
+    /// keeps them evenly spaced, and it's capped well short of pairs
+    /// getting too close to react to.
+    pub pipe_spacing_jitter: f32,
+    /// Scenery scrolling behind the pipes, farthest first. None by default,
+    /// since the game ships without scenery images. To add a layer, put its
+    /// image in the assets directory and list it here, for example
+    /// `(texture: "hills.png", speed_factor: 0.3, bottom: 0.)`.
+    pub parallax_layers: Vec<ParallaxLayerConfig>,
+    /// Image in the assets directory the bird is drawn from. It can be a
+    /// sprite sheet of wing frames side by side, `bird_frames` of them.
+    pub bird_texture: String,
+    pub bird_frames: u32,
+    /// Image in the assets directory the pipes are drawn from, with the cap
+    /// at the top. The cap keeps its size and the rest of the image stretches
+    /// to however long a pipe is.
+    pub pipe_texture: String,
+    /// Height of the cap in `pipe_texture`, in pixels.
+    pub pipe_cap_height: f32,
+    /// Seconds a fade between the menu, a run and its result takes, 0 to
+    /// change straight away.
+    pub transition_duration: f32,
+    pub reduced_motion: ReducedMotion,
+    /// Put a target in the middle of every gap that scores precision points,
+    /// shown apart from the main score.
+    pub training_targets: bool,
+    pub comeback: Comeback,
+    pub mercy: Mercy,
+    pub soft_ceiling: SoftCeiling,
+    /// Birds flying together, the player's included. The run lasts as long as
+    /// any of them does.
+    pub flock_size: u32,
+    /// Let K set a checkpoint to respawn at after crashing, for practicing
+    /// a stretch of a long run. Runs that set one aren't recorded.
+    pub practice_checkpoints: bool,
+    /// Keep the replay of the run that set the best score, to watch from
+    /// the menu and check the record against.
+    pub best_replay: bool,
+    /// Most effect particles alive at once. Past it, the oldest are reused.
+    pub particle_cap: u32,
+    /// Sound cues for playing by ear: a tone following the height of the
+    /// next gap and a click that speeds up as its pipes come closer.
+    pub gap_audio_cues: bool,
+    /// Scores celebrated with a banner and confetti the first time a run
+    /// reaches them.
+    pub milestones: Vec<u32>,
+    /// Sound in the assets directory played at each milestone, none by
+    /// default.
+    pub milestone_sound: Option<String>,
+    /// Sounds in the assets directory looped quietly under each run, none
+    /// by default.
+    pub ambient_sounds: Vec<String>,
+    /// Volume of the ambient loops, from 0 to 1.
+    pub ambient_volume: f32,
+    /// Sound in the assets directory played as each pair of pipes scrolls
+    /// onto the screen, none by default.
+    pub pipe_whoosh_sound: Option<String>,
+    /// Volume of the whoosh, from 0 to 1.
+    pub pipe_whoosh_volume: f32,
+    /// Sounds in the assets directory for flapping, passing a pipe,
+    /// crashing and the result screen coming up, none by default.
+    pub flap_sound: Option<String>,
+    pub point_sound: Option<String>,
+    pub hit_sound: Option<String>,
+    pub swoosh_sound: Option<String>,
+    /// Volume of those four, from 0 to 1.
+    pub sound_volume: f32,
+    /// Music in the assets directory looped the whole time the game is
+    /// open, none by default.
+    pub music: Option<String>,
+    /// Volume of the music, from 0 to 1.
+    pub music_volume: f32,
+    /// Seconds a result screen or the pause menu waits for input before going
+    /// back to the menu and playing demo runs, 0 to wait forever.
+    pub idle_timeout: f32,
+    /// Show a gauge of how fast the bird is rising or falling.
+    pub velocity_gauge: bool,
+    /// Show how long the run has lasted on the HUD.
+    pub show_run_time: bool,
+    /// Show the best score to beat on the HUD during a run.
+    pub show_best_score: bool,
+    /// Keep the pipes slowly scrolling behind the menu. They can't be hit
+    /// or scored there.
+    pub demo_pipes: bool,
+    /// Directory F12 saves screenshots to, a `screenshots` directory next
+    /// to the save file when unset.
+    pub screenshot_dir: Option<String>,
+}
+
+/// Effects that can be turned off for photosensitive players, or anyone who
+/// finds them distracting. There's no portable way to ask the OS whether it
+/// prefers reduced motion, so it has to be switched on here.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct ReducedMotion {
+    /// Everything below at once.
+    pub enabled: bool,
+    /// Nothing blinks or flashes. An invulnerable bird is shown faded
+    /// instead of blinking.
+    pub no_flashing: bool,
+    /// Pipes don't pulse to the music, the menu bird holds still and the
+    /// bird keeps its shape.
+    pub no_pulsing: bool,
+}
+
+impl ReducedMotion {
+    pub fn flashing_allowed(&self) -> bool {
+        !self.enabled && !self.no_flashing
+    }
+
+    pub fn pulsing_allowed(&self) -> bool {
+        !self.enabled && !self.no_pulsing
+    }
+}
+
+/// Slightly wider gaps for a player stuck dying at very low scores, until
+/// they do better. Runs played with them aren't recorded.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct Comeback {
+    pub enabled: bool,
+    /// Runs in a row ending below `low_score` before the gaps widen.
+    pub deaths: u32,
+    pub low_score: u32,
+    /// Sprite pixels added above and below the middle of each gap.
+    pub gap_bonus: f32,
+}
+
+impl Default for Comeback {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            deaths: 3,
+            low_score: 3,
+            gap_bonus: 1.5,
+        }
+    }
+}
+
+/// A one-off helping hand for the run after a few quick deaths in a row at
+/// low scores. Runs given it aren't recorded.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct Mercy {
+    pub enabled: bool,
+    /// Low-scoring deaths within `window` that earn mercy.
+    pub deaths: u32,
+    /// Seconds of real time the deaths have to fit in.
+    pub window: f32,
+    /// Deaths on this many points or more don't count, and start the
+    /// count over.
+    pub low_score: u32,
+    pub effect: MercyEffect,
+    /// Seconds the bird is invulnerable for with `MercyEffect::Invulnerability`.
+    pub invulnerability: f32,
+    /// Sprite pixels added above and below the middle of each gap with
+    /// `MercyEffect::WiderGap`.
+    pub gap_bonus: f32,
+}
+
+impl Default for Mercy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            deaths: 3,
+            window: 45.,
+            low_score: 3,
+            effect: MercyEffect::default(),
+            invulnerability: 3.,
+            gap_bonus: 2.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MercyEffect {
+    /// The bird starts out invulnerable for longer than usual.
+    #[default]
+    Invulnerability,
+    /// Every gap is wider for the whole run.
+    WiderGap,
+}
+
+impl Comeback {
+    /// Gap bonus for a run started after `low_score_deaths` low runs in a
+    /// row.
+    pub fn gap_bonus_after(&self, low_score_deaths: u32) -> f32 {
+        if self.enabled && low_score_deaths >= self.deaths.max(1) {
+            self.gap_bonus.max(0.)
+        } else {
+            0.
+        }
+    }
+}
+
+/// Slows the bird's climb as it nears the top of the screen, so it can't
+/// sail over the pipes. Nothing happens at the top itself, the bird just
+/// can't rise any further.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct SoftCeiling {
+    pub enabled: bool,
+    /// How far below the top of the screen the slowing starts, in world
+    /// units.
+    pub depth: f32,
+    /// Shape of the slowing: 1 eases off evenly all the way up, higher
+    /// leaves the climb alone for longer and brakes harder near the top.
+    pub curve: f32,
+}
+
+impl Default for SoftCeiling {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            depth: 96.,
+            curve: 2.,
+        }
+    }
+}
+
+impl SoftCeiling {
+    /// Fraction of its full climbing speed the bird keeps `distance` below
+    /// the top of the screen, from 1 at `depth` down to 0 at the top.
+    pub fn damping(&self, distance: f32) -> f32 {
+        if self.depth <= 0. {
+            return if distance > 0. { 1. } else { 0. };
+        }
+        (distance / self.depth)
+            .clamp(0., 1.)
+            .powf(self.curve.max(0.))
+    }
+}
+
+/// One repeating strip of scenery.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ParallaxLayerConfig {
+    /// Image in the assets directory, repeated across the screen.
+    pub texture: String,
+    /// Scroll speed as a fraction of the pipes', lower looks further away.
+    pub speed_factor: f32,
+    /// Height of the bottom of the strip above the bottom of the screen, in
+    /// sprite pixels.
+    pub bottom: f32,
+}
+
+/// Pipe tint from a difficulty level on.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PipeTier {
+    pub level: u32,
+    pub color: [f32; 3],
+}
+
+/// Shape of the bird as pipe collisions see it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HitboxShape {
+    /// The bird's center against pipes grown by half the bird's size, which
+    /// makes its corners as solid as its middle.
+    #[default]
+    Rect,
+    /// A circle around the bird's body against the pipes themselves, which
+    /// lets it clip a corner with its beak or tail.
+    Circle,
+}
+
+/// How input keeps the bird up.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControlScheme {
+    /// Each press is a flap.
+    #[default]
+    Flap,
+    /// Holding lifts the bird gently, for anyone who finds timing taps hard.
+    Glide,
+}
+
+/// Point in a run after which the difficulty ramps up.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RampStart {
+    /// Seconds into the run.
+    Seconds(f32),
+    /// Points scored.
+    Score(u32),
+}
+
+impl RampStart {
+    pub fn reached(self, elapsed: f32, points: u32) -> bool {
+        match self {
+            Self::Seconds(seconds) => elapsed >= seconds,
+            Self::Score(score) => points >= score,
+        }
+    }
+}
+
+/// How the gap narrows and the scroll speeds up over the ramp, from the
+/// starting values to the hardest ones.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DifficultyCurve {
+    /// A step at every difficulty level.
+    #[default]
+    Stepped,
+    /// Evenly all the way.
+    Linear,
+    /// Gently at first and steeply toward the end.
+    EaseIn,
+    /// Steeply at first and gently toward the end.
+    EaseOut,
+}
+
+impl DifficultyCurve {
+    /// How hard it gets `progress` of the way through a ramp with `levels`
+    /// levels, from 0 for the starting values to 1 for the hardest. Both
+    /// are clamped to 0 to 1.
+    pub fn apply(self, progress: f32, levels: u32) -> f32 {
+        let progress = progress.clamp(0., 1.);
+        match self {
+            Self::Stepped => {
+                let levels = levels.max(1) as f32;
+                (progress * levels).floor() / levels
+            }
+            Self::Linear => progress,
+            Self::EaseIn => progress * progress,
+            Self::EaseOut => 1. - (1. - progress) * (1. - progress),
+        }
+    }
+}
+
+/// How the height of each gap is picked.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GapPattern {
+    /// Anywhere in the allowed range.
+    #[default]
+    Uniform,
+    /// Within a short distance of the previous gap.
+    Smooth,
+    /// Always centered.
+    Fixed,
+    /// Each gap a step higher or lower than the last, turning back at the
+    /// top and bottom.
+    Staircase,
+    /// Alternately high and low.
+    Zigzag,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            game_mode: None,
+            time_attack: false,
+            time_attack_duration: 60.,
+            daily_challenge: false,
+            reset_daily_challenge: false,
+            difficulty_warning: false,
+            gap_shrink_telegraph: false,
+            mirror_mode: false,
+            wrap_vertical: false,
+            dive: false,
+            dive_strength: 3000.,
+            // A third of the way across gives more time to react than the
+            // center of the screen does
+            bird_start: [-WINDOW_SIZE.x / 6., 0.],
+            spawn_invulnerability: 1.5,
+            max_health: 1,
+            hit_invulnerability: 1.,
+            sky_top_color: [0.6, 0.8, 0.9],
+            sky_bottom_color: [0.35, 0.55, 0.7],
+            gap_pattern: GapPattern::Uniform,
+            ramp_start: RampStart::Seconds(0.),
+            difficulty_curve: DifficultyCurve::Stepped,
+            telemetry: false,
+            pixel_perfect: false,
+            max_frame_delta: 0.25,
+            control_scheme: ControlScheme::Flap,
+            hitbox_shape: HitboxShape::Rect,
+            bird_scale: 1.,
+            squash_stretch: 0.,
+            beat_pulse: false,
+            music_bpm: 120.,
+            music_loop_length: 0.,
+            gap_guide: false,
+            perfect_line: false,
+            quick_restart: false,
+            // The first pipe scrolls in from the edge of the screen instead of
+            // being right in front of the bird
+            first_pipe_distance: WINDOW_SIZE.x / 2. + WINDOW_SIZE.x / 6.,
+            tutorial_lead_in: 0.,
+            gravity_flip_chance: 0.,
+            gravity_flip_duration: 4.,
+            camera_zoom: 1.,
+            stream_mode: false,
+            gauntlet: None,
+            kill_cam: false,
+            variable_flap: false,
+            pipe_tier_colors: false,
+            finish_line_pipes: 0,
+            autopilot: false,
+            pipe_pool_size: 5,
+            pipe_spacing_jitter: 0.,
+            bird_texture: String::from("bird.png"),
+            bird_frames: 1,
+            pipe_texture: String::from("pipe.png"),
+            pipe_cap_height: 5.,
+            transition_duration: 0.4,
+            reduced_motion: ReducedMotion::default(),
+            training_targets: false,
+            comeback: Comeback::default(),
+            mercy: Mercy::default(),
+            soft_ceiling: SoftCeiling::default(),
+            flock_size: 1,
+            practice_checkpoints: false,
+            best_replay: false,
+            particle_cap: 128,
+            gap_audio_cues: false,
+            milestones: vec![10, 25, 50, 100],
+            milestone_sound: None,
+            ambient_sounds: Vec::new(),
+            ambient_volume: 0.2,
+            pipe_whoosh_sound: None,
+            pipe_whoosh_volume: 0.3,
+            flap_sound: None,
+            point_sound: None,
+            hit_sound: None,
+            swoosh_sound: None,
+            sound_volume: 0.8,
+            music: None,
+            music_volume: 0.4,
+            idle_timeout: 0.,
+            velocity_gauge: false,
+            show_run_time: false,
+            show_best_score: false,
+            demo_pipes: false,
+            screenshot_dir: None,
+            parallax_layers: Vec::new(),
+            // Each tier is darker as well as redder than the last, so they
+            // still tell apart without the hue
+            pipe_tiers: vec![
+                PipeTier {
+                    level: 0,
+                    color: [1., 1., 1.],
+                },
+                PipeTier {
+                    level: 2,
+                    color: [1., 0.7, 0.4],
+                },
+                PipeTier {
+                    level: 4,
+                    color: [0.8, 0.35, 0.3],
+                },
+            ],
+        }
+    }
+}
+
+impl GameConfig {
+    /// Reads the config file from the data directory, falling back to the
+    /// defaults when it's missing or can't be parsed.
+    pub fn load() -> Self {
+        let path = data_dir().join(CONFIG_FILE_NAME);
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match ron::from_str(&contents) {
+            Ok(config) => config,
+            Err(error) => {
+                warn!("Ignoring invalid config file {}: {error}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn store(&self) {
+        let dir = data_dir();
+        let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => contents,
+            Err(error) => {
+                error!("Failed to serialize config: {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = write_atomically(&dir.join(CONFIG_FILE_NAME), &contents) {
+            error!("Failed to write config file: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [DifficultyCurve; 4] = [
+        DifficultyCurve::Stepped,
+        DifficultyCurve::Linear,
+        DifficultyCurve::EaseIn,
+        DifficultyCurve::EaseOut,
+    ];
+
+    #[test]
+    fn curves_run_from_start_to_hardest() {
+        for curve in CURVES {
+            assert_eq!(curve.apply(0., 5), 0., "{curve:?}");
+            assert_eq!(curve.apply(1., 5), 1., "{curve:?}");
+            assert_eq!(curve.apply(-1., 5), 0., "{curve:?}");
+            assert_eq!(curve.apply(2., 5), 1., "{curve:?}");
+        }
+    }
+
+    #[test]
+    fn curves_only_get_harder() {
+        for curve in CURVES {
+            let mut previous = 0.;
+            for step in 0..=100 {
+                let hardness = curve.apply(step as f32 / 100., 5);
+                assert!(hardness >= previous, "{curve:?} eased off at {step}%");
+                previous = hardness;
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Outcome of a daily challenge, keyed by the UTC day it was played on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DailyRecord {
+    pub day: u64,
+    pub score: u32,
+}
+
+/// Present when the run is today's daily challenge.
+#[derive(Resource)]
+pub struct DailyChallenge {
+    pub day: u64,
+}
+
+/// Days since the unix epoch in UTC, which doubles as the challenge seed.
+pub fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or(0)
+}
+
+/// Whether the challenge for `today` can still be played given the last
+/// recorded attempt.
+pub fn attempt_available(record: Option<&DailyRecord>, today: u64) -> bool {
+    record.is_none_or(|record| record.day != today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn available_without_a_record() {
+        assert!(attempt_available(None, 20_000));
+    }
+
+    #[test]
+    fn available_after_an_earlier_day() {
+        let record = DailyRecord {
+            day: 19_999,
+            score: 12,
+        };
+        assert!(attempt_available(Some(&record), 20_000));
+    }
+
+    #[test]
+    fn unavailable_once_played_today() {
+        let record = DailyRecord {
+            day: 20_000,
+            score: 12,
+        };
+        assert!(!attempt_available(Some(&record), 20_000));
+    }
+}
@@ -0,0 +1,206 @@
+//! The end of a run: the kill cam, the bird tumbling off screen and the
+//! coin-paid continue that can pick the run back up.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig, daily::DailyChallenge, modes::ModeRules, save::SaveData, Bird, DeathCause,
+    GameManager, GameState, GravityFlip, Health, Invulnerable, LastDeath, Lead, Obstacle, Squash,
+    FLAP_FORCE, GRAVITY,
+};
+
+const DEATH_ANIMATION_DURATION: f32 = 0.8;
+const DEATH_POP_VELOCITY: f32 = 300.;
+const DEATH_SPIN_SPEED: f32 = 15.;
+const KILL_CAM_DURATION: f32 = 0.5;
+/// How long the bird is squashed for as it crashes.
+const SQUASH_DURATION: f32 = 0.15;
+
+pub const CONTINUE_COST: u32 = 25;
+
+/// What happens between the bird dying and the game over screen, and
+/// continuing from there.
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunContinue>()
+            .add_systems(OnEnter(GameState::Dying), start_death_animation)
+            .add_systems(Update, animate_death.run_if(in_state(GameState::Dying)))
+            .add_systems(
+                Update,
+                continue_after_game_over.run_if(in_state(GameState::GameOver)),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct DeathAnimation {
+    pub timer: Timer,
+}
+
+/// Where the bird and the pipe it hit were on the frame it died, held on
+/// screen with their hitboxes before the death animation plays.
+#[derive(Resource)]
+pub struct KillCam {
+    pub timer: Timer,
+    pub bird_translation: Vec3,
+    pub pipe_translation: Vec3,
+    pub pipe_height: f32,
+}
+
+impl KillCam {
+    pub fn showing(&self) -> bool {
+        !self.timer.finished()
+    }
+}
+
+/// The one coin-paid continue each run gets.
+#[derive(Resource, Default)]
+pub struct RunContinue {
+    pub used: bool,
+    /// Set when the bird dies in a way that may be continued from.
+    pub allowed: bool,
+    pub death_translation: Vec3,
+}
+
+impl RunContinue {
+    pub fn available(&self, coins: u32) -> bool {
+        self.allowed && !self.used && coins >= CONTINUE_COST
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_death_animation(
+    mut commands: Commands,
+    mut bird_query: Query<(Entity, &mut Bird, &Transform, &mut Visibility), With<Lead>>,
+    pipe_query: Query<(&Obstacle, &Transform)>,
+    mut run_continue: ResMut<RunContinue>,
+    last_death: Res<LastDeath>,
+    daily: Option<Res<DailyChallenge>>,
+    config: Res<GameConfig>,
+    rules: Res<ModeRules>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    commands.insert_resource(DeathAnimation {
+        timer: Timer::from_seconds(DEATH_ANIMATION_DURATION, TimerMode::Once),
+    });
+    commands.remove_resource::<KillCam>();
+    if let Ok((entity, mut bird, transform, mut visibility)) = bird_query.get_single_mut() {
+        // Copied before anything moves, so the freeze-frame shows exactly
+        // what the collision check saw
+        let hit_pipe = match last_death.0 {
+            Some(DeathCause::Pipe { pipe }) => pipe_query.get(pipe).ok(),
+            _ => None,
+        };
+        if let Some((obstacle, pipe_transform)) =
+            hit_pipe.filter(|_| config.kill_cam && !config.quick_restart)
+        {
+            commands.insert_resource(KillCam {
+                timer: Timer::from_seconds(KILL_CAM_DURATION, TimerMode::Once),
+                bird_translation: transform.translation,
+                pipe_translation: pipe_transform.translation,
+                pipe_height: obstacle.height,
+            });
+        }
+
+        // Hitting the floor can still end a run mid-blink, and the bird
+        // always tumbles down
+        commands
+            .entity(entity)
+            .remove::<(Invulnerable, GravityFlip)>()
+            .insert(Squash {
+                timer: Timer::from_seconds(SQUASH_DURATION, TimerMode::Once),
+            });
+        *visibility = Visibility::Inherited;
+        bird.velocity = DEATH_POP_VELOCITY;
+        run_continue.death_translation = transform.translation;
+    }
+    // The daily challenge is a single attempt, so it can't be extended
+    run_continue.allowed = !run_continue.used && daily.is_none() && rules.continues;
+
+    // Quick restarts skip the animation, the score is still recorded on the
+    // way through the game over state
+    if config.quick_restart {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Spends coins to pick the run back up where the bird died, with a moment
+/// of invulnerability to get clear of whatever it hit. The run keeps
+/// counting toward the high score afterwards, since the coins had to be
+/// earned by playing.
+#[allow(clippy::too_many_arguments)]
+fn continue_after_game_over(
+    mut commands: Commands,
+    mut bird_query: Query<(Entity, &mut Bird, &mut Transform), With<Lead>>,
+    game_manager: Res<GameManager>,
+    rules: Res<ModeRules>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut run_continue: ResMut<RunContinue>,
+    mut save: ResMut<SaveData>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyC) || !run_continue.available(save.coins) {
+        return;
+    }
+    let Ok((entity, mut bird, mut transform)) = bird_query.get_single_mut() else {
+        return;
+    };
+
+    save.coins -= CONTINUE_COST;
+    save.store();
+    run_continue.used = true;
+    run_continue.allowed = false;
+
+    let half_height = game_manager.window_dimentions.y / 2.;
+    transform.translation = Vec3::new(
+        game_manager.bird_start.x,
+        run_continue
+            .death_translation
+            .y
+            .clamp(-half_height / 2., half_height / 2.),
+        game_manager.bird_start.z,
+    );
+    transform.rotation = Quat::IDENTITY;
+    bird.velocity = FLAP_FORCE;
+    bird.flap_hold = None;
+    commands
+        .entity(entity)
+        .insert((
+            Invulnerable::new(rules.spawn_invulnerability),
+            Health(rules.max_health),
+        ))
+        .remove::<Squash>();
+
+    next_state.set(GameState::Playing);
+}
+
+/// Lets the bird fall and spin with the rest of the world frozen, then moves
+/// on to the game over screen.
+fn animate_death(
+    time: Res<Time>,
+    game_manager: Res<GameManager>,
+    mut death_animation: ResMut<DeathAnimation>,
+    kill_cam: Option<ResMut<KillCam>>,
+    mut bird_query: Query<(&mut Bird, &mut Transform), With<Lead>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Nothing moves until the kill cam is done
+    if let Some(mut kill_cam) = kill_cam {
+        if kill_cam.showing() {
+            kill_cam.timer.tick(time.delta());
+            return;
+        }
+    }
+
+    if let Ok((mut bird, mut transform)) = bird_query.get_single_mut() {
+        bird.velocity -= time.delta_secs() * GRAVITY;
+        transform.translation.y += bird.velocity * time.delta_secs();
+        transform.rotate_z(DEATH_SPIN_SPEED * time.delta_secs() * game_manager.facing);
+    }
+
+    if death_animation.timer.tick(time.delta()).finished() {
+        next_state.set(GameState::GameOver);
+    }
+}
@@ -0,0 +1,309 @@
+//! Tuning overlay toggled with F2: a world-space grid, with the gaps and
+//! spacing of the pipes as they're actually laid out measured on top of it.
+//! None of it exists or runs while it's off.
+//!
+//! F3 separately lists live values from the run, and keeps updating them
+//! while the game is paused so they can be read at leisure.
+
+use bevy::prelude::*;
+
+use crate::{
+    autopilot::next_pair, gap_center, Bird, Difficulty, GameManager, Lead, Obstacle, PipeQueue,
+    Score, OBSTACLE_SPACING, OBSTACLE_WIDTH, PIXEL_RATIO,
+};
+
+/// Eight sprite pixels per cell.
+const GRID_SPACING: f32 = 8. * PIXEL_RATIO;
+const GRID_COLOR: Color = Color::srgba(1., 1., 1., 0.15);
+const AXIS_COLOR: Color = Color::srgba(1., 1., 1., 0.4);
+const GAP_COLOR: Color = Color::srgb(0.3, 1., 0.4);
+const SPACING_COLOR: Color = Color::srgb(0.3, 0.7, 1.);
+
+/// Both overlays and the keys toggling them.
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (toggle_tuning_overlay, toggle_inspect_overlay))
+            .add_systems(
+                Update,
+                update_inspect_text.run_if(resource_exists::<InspectOverlay>),
+            )
+            .add_systems(
+                Update,
+                (
+                    draw_tuning_grid,
+                    draw_tuning_measurements,
+                    update_tuning_text,
+                )
+                    .run_if(resource_exists::<TuningOverlay>),
+            );
+    }
+}
+
+/// Present while the overlay is shown.
+#[derive(Resource)]
+pub struct TuningOverlay;
+
+#[derive(Component)]
+pub struct TuningText;
+
+/// Present while the live values are listed.
+#[derive(Resource)]
+pub struct InspectOverlay;
+
+#[derive(Component)]
+pub struct InspectText;
+
+pub fn toggle_tuning_overlay(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    overlay: Option<Res<TuningOverlay>>,
+    text_query: Query<Entity, With<TuningText>>,
+) {
+    if !keys.just_pressed(KeyCode::F2) {
+        return;
+    }
+
+    if overlay.is_some() {
+        commands.remove_resource::<TuningOverlay>();
+        for entity in text_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    } else {
+        commands.insert_resource(TuningOverlay);
+        commands.spawn((
+            Text::new(""),
+            TextFont {
+                font_size: 16.,
+                ..Default::default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.),
+                left: Val::Px(8.),
+                ..Default::default()
+            },
+            TuningText,
+        ));
+    }
+}
+
+pub fn draw_tuning_grid(mut gizmos: Gizmos, game_manager: Res<GameManager>) {
+    let half_size = game_manager.window_dimentions / 2.;
+    let columns = (half_size.x / GRID_SPACING) as i32;
+    let rows = (half_size.y / GRID_SPACING) as i32;
+
+    for column in -columns..=columns {
+        let x = column as f32 * GRID_SPACING;
+        let color = if column == 0 { AXIS_COLOR } else { GRID_COLOR };
+        gizmos.line_2d(Vec2::new(x, -half_size.y), Vec2::new(x, half_size.y), color);
+    }
+    for row in -rows..=rows {
+        let y = row as f32 * GRID_SPACING;
+        let color = if row == 0 { AXIS_COLOR } else { GRID_COLOR };
+        gizmos.line_2d(Vec2::new(-half_size.x, y), Vec2::new(half_size.x, y), color);
+    }
+}
+
+/// Inner end of a pipe, the one facing the gap.
+fn gap_end((obstacle, transform): (&Obstacle, &Transform)) -> f32 {
+    transform.translation.y - obstacle.pipe_direction * obstacle.height / 2.
+}
+
+/// Height between the inner ends of a pair's pipes.
+fn measured_gap(top: (&Obstacle, &Transform), bottom: (&Obstacle, &Transform)) -> f32 {
+    gap_end(top) - gap_end(bottom)
+}
+
+/// Marks each gap from end to end, and the distance between each pair and
+/// the next along the top of the screen.
+pub fn draw_tuning_measurements(
+    mut gizmos: Gizmos,
+    game_manager: Res<GameManager>,
+    pipe_queue: Res<PipeQueue>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+) {
+    let top_edge = game_manager.window_dimentions.y / 2. - GRID_SPACING;
+    let mut previous_x = None;
+    for &pair in pipe_queue.0.iter() {
+        let Ok([top, bottom]) = obstacle_query.get_many(pair) else {
+            continue;
+        };
+        let x = top.1.translation.x;
+        let gap_top = gap_end(top);
+        let gap_bottom = gap_end(bottom);
+        gizmos.line_2d(Vec2::new(x, gap_top), Vec2::new(x, gap_bottom), GAP_COLOR);
+        for y in [gap_top, gap_bottom] {
+            gizmos.line_2d(
+                Vec2::new(x - GRID_SPACING / 2., y),
+                Vec2::new(x + GRID_SPACING / 2., y),
+                GAP_COLOR,
+            );
+        }
+
+        if let Some(previous_x) = previous_x {
+            gizmos.line_2d(
+                Vec2::new(previous_x, top_edge),
+                Vec2::new(x, top_edge),
+                SPACING_COLOR,
+            );
+        }
+        previous_x = Some(x);
+    }
+}
+
+/// Lists the measured values next to what the config and difficulty say
+/// they should be.
+pub fn update_tuning_text(
+    game_manager: Res<GameManager>,
+    difficulty: Res<Difficulty>,
+    pipe_queue: Res<PipeQueue>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+    bird_query: Query<&Transform, With<Lead>>,
+    mut text_query: Query<&mut Text, With<TuningText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    let mut pairs = pipe_queue
+        .0
+        .iter()
+        .filter_map(|&pair| obstacle_query.get_many(pair).ok());
+    let first = pairs.next();
+    let second = pairs.next();
+
+    let expected_gap = game_manager.effective_gap_size(difficulty.gap_size()) * 2. * PIXEL_RATIO;
+    let gap = first.map_or(String::from("-"), |[top, bottom]| {
+        format!("{:.0}", measured_gap(top, bottom))
+    });
+    let spacing =
+        first
+            .zip(second)
+            .map_or(String::from("-"), |([(_, first), _], [(_, second), _])| {
+                format!("{:.0}", (second.translation.x - first.translation.x).abs())
+            });
+    let bird = bird_query.get_single().map_or(String::from("-"), |bird| {
+        format!("{:.0}, {:.0}", bird.translation.x, bird.translation.y)
+    });
+
+    text.0 = format!(
+        "Bird: {bird}\nNext gap: {gap} (new pipes {expected_gap:.0})\nSpacing: {spacing} ({:.0})\nScroll speed: {:.0}\nLevel: {}",
+        OBSTACLE_SPACING * PIXEL_RATIO,
+        difficulty.scroll_speed(),
+        difficulty.level(),
+    );
+}
+
+pub fn toggle_inspect_overlay(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    overlay: Option<Res<InspectOverlay>>,
+    text_query: Query<Entity, With<InspectText>>,
+) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    if overlay.is_some() {
+        commands.remove_resource::<InspectOverlay>();
+        for entity in text_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    } else {
+        commands.insert_resource(InspectOverlay);
+        commands.spawn((
+            Text::new(""),
+            TextFont {
+                font_size: 16.,
+                ..Default::default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.),
+                right: Val::Px(8.),
+                ..Default::default()
+            },
+            InspectText,
+        ));
+    }
+}
+
+/// One `label: value` row of the inspect overlay.
+pub fn inspect_line(label: &str, value: Option<String>) -> String {
+    format!("{label}: {}", value.as_deref().unwrap_or("-"))
+}
+
+pub fn format_units(value: f32) -> String {
+    format!("{value:.0}")
+}
+
+/// Everything the inspect overlay lists, `None` for values there's no bird
+/// or pipe to read from.
+pub struct InspectValues {
+    pub velocity: Option<f32>,
+    pub height: Option<f32>,
+    pub next_gap_center: Option<f32>,
+    pub next_pipe_distance: Option<f32>,
+    pub level: u32,
+    pub scroll_speed: f32,
+    pub points: u32,
+    pub pipes_passed: u32,
+}
+
+pub fn format_inspect_values(values: &InspectValues) -> String {
+    [
+        inspect_line("Velocity", values.velocity.map(format_units)),
+        inspect_line("Height", values.height.map(format_units)),
+        inspect_line("Next gap center", values.next_gap_center.map(format_units)),
+        inspect_line("Next pipe in", values.next_pipe_distance.map(format_units)),
+        inspect_line(
+            "Difficulty",
+            Some(format!(
+                "level {} at {}",
+                values.level,
+                format_units(values.scroll_speed)
+            )),
+        ),
+        inspect_line(
+            "Score",
+            Some(format!("{} ({} pipes)", values.points, values.pipes_passed)),
+        ),
+    ]
+    .join("\n")
+}
+
+pub fn update_inspect_text(
+    game_manager: Res<GameManager>,
+    difficulty: Res<Difficulty>,
+    score: Res<Score>,
+    pipe_queue: Res<PipeQueue>,
+    bird_query: Query<(&Bird, &Transform), With<Lead>>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+    mut text_query: Query<&mut Text, With<InspectText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    let bird = bird_query.get_single().ok();
+    let next_pair = next_pair(&pipe_queue, &score, &obstacle_query);
+    // To the front edge of the pipe, which is where the bird can first hit it
+    let next_pipe_distance = bird.zip(next_pair).map(|((_, bird), [top, _])| {
+        (top.translation.x - bird.translation.x) * game_manager.facing
+            - OBSTACLE_WIDTH * PIXEL_RATIO / 2.
+    });
+
+    text.0 = format_inspect_values(&InspectValues {
+        velocity: bird.map(|(bird, _)| bird.velocity),
+        height: bird.map(|(_, transform)| transform.translation.y),
+        next_gap_center: next_pair
+            .map(|[top, bottom]| gap_center(top.translation, bottom.translation)),
+        next_pipe_distance,
+        level: difficulty.level(),
+        scroll_speed: difficulty.scroll_speed(),
+        points: score.points,
+        pipes_passed: score.pipes_passed,
+    });
+}
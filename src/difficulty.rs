@@ -0,0 +1,397 @@
+//! How hard a run plays: the ramp of narrower gaps and faster pipes, and
+//! the comeback gaps and mercy given after a string of low scores.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::{DifficultyCurve, GameConfig, Mercy, MercyEffect},
+    daily::DailyChallenge,
+    modes::ModeRules,
+    GameManager, RunTime, Score, OBSTACLE_GAP_SIZE, OBSTACLE_SCROLL_SPEED,
+};
+
+const DIFFICULTY_LEVEL_DURATION: f32 = 15.;
+pub(crate) const DIFFICULTY_MAX_LEVEL: u32 = 5;
+const DIFFICULTY_WARNING_TIME: f32 = 3.;
+pub(crate) const GAP_SHRINK_PER_LEVEL: f32 = 1.5;
+const SCROLL_SPEED_PER_LEVEL: f32 = 15.;
+
+/// Steps up every `DIFFICULTY_LEVEL_DURATION` seconds survived once the
+/// configured `ramp_start` is reached, narrowing the gap of newly recycled
+/// pipes and speeding up the scroll.
+#[derive(Resource, Clone)]
+pub struct Difficulty {
+    /// Seconds since the ramp started.
+    pub ramp_elapsed: f32,
+    /// Seconds of progress skipped by starting at a practice level.
+    pub head_start: f32,
+    /// Comeback widening of every gap, in sprite pixels.
+    pub gap_bonus: f32,
+    /// World units the pipes have scrolled by.
+    pub distance: f32,
+    pub curve: DifficultyCurve,
+    /// The run was given mercy after a string of quick deaths.
+    pub mercy: bool,
+    /// Seconds of invulnerability mercy gives at the start, 0 without it.
+    pub mercy_invulnerability: f32,
+    /// The game mode's widening of every gap, in sprite pixels. Unlike
+    /// `gap_bonus` it doesn't stop the run being recorded.
+    pub mode_gap_bonus: f32,
+    /// The game mode's multiplier on the scroll speed.
+    pub speed_scale: f32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::starting_at(0)
+    }
+}
+
+impl Difficulty {
+    /// A run that plays as if it had already survived up to `level`.
+    pub fn starting_at(level: u32) -> Self {
+        Self {
+            ramp_elapsed: 0.,
+            head_start: level.min(DIFFICULTY_MAX_LEVEL) as f32 * DIFFICULTY_LEVEL_DURATION,
+            gap_bonus: 0.,
+            distance: 0.,
+            curve: DifficultyCurve::default(),
+            mercy: false,
+            mercy_invulnerability: 0.,
+            mode_gap_bonus: 0.,
+            speed_scale: 1.,
+        }
+    }
+
+    pub fn with_gap_bonus(self, gap_bonus: f32) -> Self {
+        Self { gap_bonus, ..self }
+    }
+
+    pub fn with_curve(self, curve: DifficultyCurve) -> Self {
+        Self { curve, ..self }
+    }
+
+    /// Plays by the gap, speed and curve of the game mode with `rules`.
+    pub fn with_mode_rules(self, rules: &ModeRules) -> Self {
+        Self {
+            mode_gap_bonus: rules.gap_bonus,
+            speed_scale: rules.speed_scale,
+            ..self.with_curve(rules.difficulty_curve)
+        }
+    }
+
+    /// Gives the run the help `mercy` is set up to give.
+    pub fn with_mercy(self, mercy: &Mercy) -> Self {
+        let (gap_bonus, mercy_invulnerability) = match mercy.effect {
+            MercyEffect::Invulnerability => (0., mercy.invulnerability.max(0.)),
+            MercyEffect::WiderGap => (mercy.gap_bonus.max(0.), 0.),
+        };
+        Self {
+            gap_bonus: self.gap_bonus + gap_bonus,
+            mercy: true,
+            mercy_invulnerability,
+            ..self
+        }
+    }
+
+    /// Runs with comeback gaps or mercy don't count toward records either.
+    pub fn is_assisted(&self) -> bool {
+        self.gap_bonus > 0. || self.mercy
+    }
+
+    /// Practice runs don't count toward records.
+    pub fn is_practice(&self) -> bool {
+        self.head_start > 0.
+    }
+
+    pub fn level(&self) -> u32 {
+        (((self.ramp_elapsed + self.head_start) / DIFFICULTY_LEVEL_DURATION) as u32)
+            .min(DIFFICULTY_MAX_LEVEL)
+    }
+
+    /// Levels' worth of narrowing and speeding up the curve has reached,
+    /// the same as `level` for the stepped curve.
+    fn ramp(&self) -> f32 {
+        // Going by the level itself keeps it exactly in step with it
+        if self.curve == DifficultyCurve::Stepped {
+            return self.level() as f32;
+        }
+        let duration = DIFFICULTY_MAX_LEVEL as f32 * DIFFICULTY_LEVEL_DURATION;
+        let progress = (self.ramp_elapsed + self.head_start) / duration;
+        self.curve.apply(progress, DIFFICULTY_MAX_LEVEL) * DIFFICULTY_MAX_LEVEL as f32
+    }
+
+    pub fn gap_size(&self) -> f32 {
+        OBSTACLE_GAP_SIZE - self.ramp() * GAP_SHRINK_PER_LEVEL
+            + self.gap_bonus
+            + self.mode_gap_bonus
+    }
+
+    pub fn scroll_speed(&self) -> f32 {
+        (OBSTACLE_SCROLL_SPEED + self.ramp() * SCROLL_SPEED_PER_LEVEL) * self.speed_scale
+    }
+
+    /// Whether the next level is close enough that pipes recycled now should
+    /// warn the player about it.
+    pub fn level_up_imminent(&self) -> bool {
+        self.level() < DIFFICULTY_MAX_LEVEL
+            && DIFFICULTY_LEVEL_DURATION
+                - (self.ramp_elapsed + self.head_start) % DIFFICULTY_LEVEL_DURATION
+                <= DIFFICULTY_WARNING_TIME
+    }
+}
+
+/// What happened earlier in this sitting. None of it is saved.
+#[derive(Resource, Default)]
+pub struct SessionStats {
+    /// Runs in a row that ended below the comeback low score.
+    pub low_score_deaths: u32,
+    /// Real time of each low-scoring death since mercy was last given or a
+    /// run went better.
+    pub recent_deaths: Vec<f32>,
+    /// The next run gets mercy.
+    pub mercy_due: bool,
+}
+
+impl SessionStats {
+    /// Counts a run that ended on `points`, starting over once one reaches
+    /// `low_score`. A continued run was already counted when it first ended.
+    pub fn count_run(&mut self, points: u32, low_score: u32, continued: bool) {
+        if points >= low_score {
+            self.low_score_deaths = 0;
+        } else if !continued {
+            self.low_score_deaths += 1;
+        }
+    }
+
+    /// Counts a death at real time `now` on `points` toward mercy, making it
+    /// due once enough low-scoring ones come within its window. Giving it
+    /// starts the count over, so it takes as many again for the next.
+    pub fn count_mercy_death(&mut self, now: f32, points: u32, mercy: &Mercy) {
+        if !mercy.enabled || points >= mercy.low_score {
+            self.recent_deaths.clear();
+            return;
+        }
+        self.recent_deaths
+            .retain(|&time| now - time <= mercy.window);
+        self.recent_deaths.push(now);
+        if self.recent_deaths.len() as u32 >= mercy.deaths.max(1) {
+            self.recent_deaths.clear();
+            self.mercy_due = true;
+        }
+    }
+
+    /// Difficulty the next run starts at, with any mercy that's due used up
+    /// on it. The daily challenge gets no comeback help or mercy, so it plays
+    /// the same for everyone.
+    pub fn next_run_difficulty(
+        &mut self,
+        config: &GameConfig,
+        rules: &ModeRules,
+        start_level: u32,
+        daily: Option<&DailyChallenge>,
+    ) -> Difficulty {
+        if daily.is_some() {
+            return Difficulty::starting_at(start_level).with_mode_rules(rules);
+        }
+        let difficulty = Difficulty::starting_at(start_level)
+            .with_gap_bonus(config.comeback.gap_bonus_after(self.low_score_deaths))
+            .with_mode_rules(rules);
+        if std::mem::take(&mut self.mercy_due) {
+            difficulty.with_mercy(&config.mercy)
+        } else {
+            difficulty
+        }
+    }
+}
+
+/// Moves the ramp along, once the run has got past the tutorial stretch and
+/// to the configured `ramp_start`.
+pub fn update_difficulty(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    rules: Res<ModeRules>,
+    game_manager: Res<GameManager>,
+    score: Res<Score>,
+    run_time: Res<RunTime>,
+    mut difficulty: ResMut<Difficulty>,
+) {
+    difficulty.distance += time.delta_secs() * difficulty.scroll_speed();
+    if difficulty.distance >= game_manager.tutorial_lead_in
+        && rules.difficulty_ramps
+        && config.ramp_start.reached(run_time.0, score.points)
+    {
+        difficulty.ramp_elapsed += time.delta_secs();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::{config::RampStart, modes::GameMode};
+
+    /// A world with what `update_difficulty` reads, for a run under `config`.
+    fn difficulty_world(config: GameConfig) -> World {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(GameMode::Normal.rules(&config));
+        world.insert_resource(GameManager::new(
+            &config,
+            Handle::default(),
+            Vec2::new(512., 512.),
+        ));
+        world.insert_resource(config);
+        world.insert_resource(Score::default());
+        world.insert_resource(RunTime::default());
+        world.insert_resource(Difficulty::default());
+        world
+    }
+
+    /// Runs `update_difficulty` for `seconds` of the run, a second at a time.
+    fn play(world: &mut World, seconds: u32) {
+        for _ in 0..seconds {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(1));
+            world.resource_mut::<RunTime>().0 += 1.;
+            world.run_system_once(update_difficulty).unwrap();
+        }
+    }
+
+    fn mercy(effect: MercyEffect) -> Mercy {
+        Mercy {
+            enabled: true,
+            effect,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn quick_low_deaths_earn_mercy_once() {
+        let config = GameConfig {
+            mercy: mercy(MercyEffect::WiderGap),
+            ..default()
+        };
+        let rules = GameMode::Normal.rules(&config);
+        let mut session = SessionStats::default();
+        for now in [0., 10., 20.] {
+            assert!(!session.mercy_due);
+            session.count_mercy_death(now, 0, &config.mercy);
+        }
+        assert!(session.mercy_due);
+
+        let difficulty = session.next_run_difficulty(&config, &rules, 0, None);
+        assert!(difficulty.mercy && difficulty.is_assisted());
+        assert_eq!(
+            difficulty.gap_size(),
+            OBSTACLE_GAP_SIZE + config.mercy.gap_bonus
+        );
+        assert!(!session.next_run_difficulty(&config, &rules, 0, None).mercy);
+    }
+
+    #[test]
+    fn invulnerability_mercy_keeps_the_gap() {
+        let difficulty = Difficulty::default().with_mercy(&mercy(MercyEffect::Invulnerability));
+        assert_eq!(difficulty.gap_size(), OBSTACLE_GAP_SIZE);
+        assert_eq!(difficulty.mercy_invulnerability, 3.);
+        assert!(difficulty.is_assisted());
+    }
+
+    #[test]
+    fn spread_out_or_better_runs_earn_no_mercy() {
+        let mercy = mercy(MercyEffect::WiderGap);
+        let mut session = SessionStats::default();
+        for now in [0., mercy.window, mercy.window * 2.5] {
+            session.count_mercy_death(now, 0, &mercy);
+        }
+        assert!(!session.mercy_due);
+
+        let mut session = SessionStats::default();
+        session.count_mercy_death(0., 0, &mercy);
+        session.count_mercy_death(1., 0, &mercy);
+        session.count_mercy_death(2., mercy.low_score, &mercy);
+        session.count_mercy_death(3., 0, &mercy);
+        assert!(!session.mercy_due);
+
+        let mut session = SessionStats::default();
+        for now in [0., 1., 2.] {
+            session.count_mercy_death(now, 0, &Mercy::default());
+        }
+        assert!(!session.mercy_due);
+    }
+
+    #[test]
+    fn daily_runs_get_no_mercy() {
+        let config = GameConfig {
+            mercy: mercy(MercyEffect::WiderGap),
+            ..default()
+        };
+        let rules = GameMode::Daily.rules(&config);
+        let mut session = SessionStats {
+            mercy_due: true,
+            ..default()
+        };
+        let daily = DailyChallenge { day: 1 };
+        assert!(
+            !session
+                .next_run_difficulty(&config, &rules, 0, Some(&daily))
+                .mercy
+        );
+    }
+
+    #[test]
+    fn curves_start_and_end_at_the_same_difficulty() {
+        let hardest = Difficulty::starting_at(DIFFICULTY_MAX_LEVEL);
+        for curve in [
+            DifficultyCurve::Stepped,
+            DifficultyCurve::Linear,
+            DifficultyCurve::EaseIn,
+            DifficultyCurve::EaseOut,
+        ] {
+            let start = Difficulty::default().with_curve(curve);
+            assert_eq!(start.gap_size(), OBSTACLE_GAP_SIZE, "{curve:?}");
+            assert_eq!(start.scroll_speed(), OBSTACLE_SCROLL_SPEED, "{curve:?}");
+
+            let end = hardest.clone().with_curve(curve);
+            let shrink = DIFFICULTY_MAX_LEVEL as f32 * GAP_SHRINK_PER_LEVEL;
+            let speedup = DIFFICULTY_MAX_LEVEL as f32 * SCROLL_SPEED_PER_LEVEL;
+            assert_eq!(end.gap_size(), OBSTACLE_GAP_SIZE - shrink, "{curve:?}");
+            assert_eq!(
+                end.scroll_speed(),
+                OBSTACLE_SCROLL_SPEED + speedup,
+                "{curve:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn gap_holds_until_the_ramp_starts() {
+        let mut world = difficulty_world(GameConfig {
+            ramp_start: RampStart::Seconds(20.),
+            ..default()
+        });
+        play(&mut world, 20);
+        assert_eq!(world.resource::<Difficulty>().gap_size(), OBSTACLE_GAP_SIZE);
+
+        play(&mut world, DIFFICULTY_LEVEL_DURATION as u32);
+        assert!(world.resource::<Difficulty>().gap_size() < OBSTACLE_GAP_SIZE);
+    }
+
+    #[test]
+    fn gap_holds_until_the_ramp_start_score() {
+        let mut world = difficulty_world(GameConfig {
+            ramp_start: RampStart::Score(5),
+            ..default()
+        });
+        play(&mut world, DIFFICULTY_LEVEL_DURATION as u32 * 2);
+        assert_eq!(world.resource::<Difficulty>().gap_size(), OBSTACLE_GAP_SIZE);
+
+        world.resource_mut::<Score>().points = 5;
+        play(&mut world, DIFFICULTY_LEVEL_DURATION as u32);
+        assert!(world.resource::<Difficulty>().gap_size() < OBSTACLE_GAP_SIZE);
+    }
+}
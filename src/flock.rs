@@ -0,0 +1,82 @@
+//! Flock mode: extra birds fly alongside the player's and flap whenever it
+//! does. Each one starts a little higher or lower and falls a little faster
+//! or slower, so the flock spreads out as the run goes on.
+//!
+//! Flockmates are birds like any other, flown by `update_bird` with the same
+//! controls. The player's bird starts as the `Lead`, and the run only ends
+//! once every bird is down.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig, layers::FLOCKMATE_Z, modes::ModeRules, Bird, GameManager, Health,
+    PIXEL_RATIO,
+};
+
+/// Most birds a flock can have, the lead included.
+const MAX_FLOCK_SIZE: u32 = 8;
+/// Height between neighbouring flockmates at the start of a run.
+const FLOCK_SPACING: f32 = 6. * PIXEL_RATIO;
+/// How much stronger gravity pulls on each flockmate further up the flock.
+const FLOCK_GRAVITY_SPREAD: f32 = 0.04;
+pub(crate) const FLOCKMATE_ALPHA: f32 = 0.7;
+
+/// On every bird in the flock but the player's own.
+#[derive(Component)]
+pub struct Flockmate {
+    /// Gravity on this bird relative to the usual.
+    pub gravity_scale: f32,
+    /// Height above the lead's start it starts each run at.
+    pub start_offset: f32,
+}
+
+impl Flockmate {
+    /// Where this flockmate starts a run, given where the player's bird does.
+    pub fn start(&self, bird_start: Vec3) -> Vec3 {
+        (bird_start + Vec3::Y * self.start_offset).with_z(FLOCKMATE_Z)
+    }
+}
+
+/// Place in the flock of flockmate `index`, alternating above and below the
+/// lead: 1, -1, 2, -2 and so on.
+pub fn flock_rank(index: u32) -> f32 {
+    let rank = (index / 2 + 1) as f32;
+    if index.is_multiple_of(2) {
+        rank
+    } else {
+        -rank
+    }
+}
+
+pub fn spawn_flock(
+    commands: &mut Commands,
+    game_manager: &GameManager,
+    config: &GameConfig,
+    rules: &ModeRules,
+    image: &Handle<Image>,
+) {
+    let flockmates = config.flock_size.clamp(1, MAX_FLOCK_SIZE) - 1;
+    for index in 0..flockmates {
+        let rank = flock_rank(index);
+        let flockmate = Flockmate {
+            gravity_scale: 1. + rank * FLOCK_GRAVITY_SPREAD,
+            start_offset: rank * FLOCK_SPACING,
+        };
+        commands.spawn((
+            Sprite {
+                image: image.clone(),
+                flip_x: config.mirror_mode,
+                color: Color::WHITE.with_alpha(FLOCKMATE_ALPHA),
+                ..Default::default()
+            },
+            Transform::from_translation(flockmate.start(game_manager.bird_start))
+                .with_scale(Vec3::splat(PIXEL_RATIO * game_manager.bird_scale)),
+            Bird {
+                velocity: 0.,
+                flap_hold: None,
+            },
+            Health(rules.max_health),
+            flockmate,
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+//! Telegraphed gap shrinking: when the difficulty ramp narrows the gap, the
+//! pairs already on screen close up to the new size over a moment, rather
+//! than only the pairs recycled afterwards coming in narrower. The pipes
+//! themselves are moved, so collisions always go by the gap as it's drawn.
+//!
+//! Pairs the bird is about to fly into keep the gap they have, so nothing
+//! ever closes in on it.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig, difficulty::GAP_SHRINK_PER_LEVEL, gap_center, get_centered_pipe_position,
+    Difficulty, GameManager, Lead, Obstacle, PipeQueue, OBSTACLE_HEIGHT, OBSTACLE_WIDTH,
+    PIXEL_RATIO,
+};
+
+/// Seconds a gap takes to close up by one difficulty level's worth.
+const GAP_NARROWING_DURATION: f32 = 0.6;
+/// Pairs whose front edge is closer to the bird than this are left alone.
+const PROTECTED_DISTANCE: f32 = 40. * PIXEL_RATIO;
+
+/// Gap size, in sprite pixels, of a pair with its pipes at `top_y` and
+/// `bottom_y`. The inverse of how the pipes are placed.
+pub fn pair_gap_size(top_y: f32, bottom_y: f32) -> f32 {
+    (top_y - bottom_y) / (2. * PIXEL_RATIO) - OBSTACLE_HEIGHT / 2.
+}
+
+/// `current` gap moved at most `max_step` towards a narrower `target`.
+/// Gaps only ever close up here, a wider target leaves them as they are.
+pub fn narrowed_gap(current: f32, target: f32, max_step: f32) -> f32 {
+    if current > target {
+        (current - max_step).max(target)
+    } else {
+        current
+    }
+}
+
+/// Runs right after the pipes have moved and before the bird checks for
+/// collisions with them.
+pub fn narrow_visible_gaps(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    game_manager: Res<GameManager>,
+    difficulty: Res<Difficulty>,
+    pipe_queue: Res<PipeQueue>,
+    bird_query: Query<&Transform, (With<Lead>, Without<Obstacle>)>,
+    mut obstacle_query: Query<(&Obstacle, &mut Transform, &Visibility)>,
+) {
+    if !config.gap_shrink_telegraph {
+        return;
+    }
+    let Ok(bird) = bird_query.get_single() else {
+        return;
+    };
+    let target = game_manager.effective_gap_size(difficulty.gap_size());
+    let max_step = time.delta_secs() * GAP_SHRINK_PER_LEVEL / GAP_NARROWING_DURATION;
+
+    for &pair in pipe_queue.0.iter() {
+        let Ok([(top, mut top_transform, visibility), (bottom, mut bottom_transform, _)]) =
+            obstacle_query.get_many_mut(pair)
+        else {
+            continue;
+        };
+        // Pairs hidden past the finish line have nothing to show
+        if visibility == Visibility::Hidden {
+            continue;
+        }
+        let distance = (top_transform.translation.x - bird.translation.x) * game_manager.facing
+            - OBSTACLE_WIDTH * PIXEL_RATIO / 2.;
+        if distance < PROTECTED_DISTANCE {
+            continue;
+        }
+
+        let current = pair_gap_size(top_transform.translation.y, bottom_transform.translation.y);
+        let gap_size = narrowed_gap(current, target, max_step);
+        if gap_size == current {
+            continue;
+        }
+        let center = gap_center(top_transform.translation, bottom_transform.translation);
+        top_transform.translation.y =
+            get_centered_pipe_position(gap_size) * top.pipe_direction + center;
+        bottom_transform.translation.y =
+            get_centered_pipe_position(gap_size) * bottom.pipe_direction + center;
+    }
+}
@@ -0,0 +1,122 @@
+//! Hand-made pipe layouts, read from a pattern file instead of rolling every
+//! gap at random.
+//!
+//! The file sits in the data directory under the name set by the `gauntlet`
+//! config entry. It lists the height of each gap, in sprite pixels above the
+//! middle of the screen, in the order the pipes come:
+//!
+//! ```ron
+//! (
+//!     offsets: [0., 20., -15., 30.],
+//!     looping: true,
+//! )
+//! ```
+//!
+//! A looping pattern starts over after its last gap, otherwise the usual
+//! random pipes take over once it runs out.
+
+use std::fs;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{save::data_dir, OBSTACLE_VERTICAL_OFFSET, PIXEL_RATIO};
+
+#[derive(Deserialize)]
+struct PatternFile {
+    offsets: Vec<f32>,
+    #[serde(default)]
+    looping: bool,
+}
+
+/// Where the gaps of new pipes come from.
+#[derive(Resource, Default, Clone, Debug)]
+pub enum PatternSource {
+    #[default]
+    Random,
+    Scripted(ScriptedPattern),
+}
+
+#[derive(Clone, Debug)]
+pub struct ScriptedPattern {
+    /// Gap offsets in world units.
+    pub offsets: Vec<f32>,
+    pub looping: bool,
+    /// Index of the offset the next pair gets.
+    pub next: usize,
+}
+
+impl PatternSource {
+    /// Reads the pattern file named `file_name`, falling back to random gaps
+    /// when there's none or it can't be used.
+    pub fn load(file_name: Option<&str>) -> Self {
+        let Some(file_name) = file_name else {
+            return Self::Random;
+        };
+        let path = data_dir().join(file_name);
+        let pattern = match fs::read_to_string(&path) {
+            Ok(contents) => {
+                ron::from_str::<PatternFile>(&contents).map_err(|error| error.to_string())
+            }
+            Err(error) => Err(error.to_string()),
+        };
+
+        match pattern {
+            Ok(pattern) if pattern.offsets.is_empty() => {
+                warn!("Ignoring empty pattern file {}", path.display());
+                Self::Random
+            }
+            Ok(pattern) => Self::Scripted(ScriptedPattern {
+                offsets: validate_offsets(&pattern.offsets),
+                looping: pattern.looping,
+                next: 0,
+            }),
+            Err(error) => {
+                warn!("Ignoring pattern file {}: {error}", path.display());
+                Self::Random
+            }
+        }
+    }
+
+    /// Goes back to the first gap of the pattern for a new run.
+    pub fn restart(&mut self) {
+        if let Self::Scripted(pattern) = self {
+            pattern.next = 0;
+        }
+    }
+
+    /// Offset for the next pair, `None` when it should be picked at random.
+    pub fn next_offset(&mut self) -> Option<f32> {
+        let Self::Scripted(pattern) = self else {
+            return None;
+        };
+        if pattern.next >= pattern.offsets.len() {
+            if !pattern.looping {
+                return None;
+            }
+            pattern.next = 0;
+        }
+
+        let offset = pattern.offsets.get(pattern.next).copied();
+        pattern.next += 1;
+        offset
+    }
+}
+
+/// Converts offsets from the file to world units, pulling any that would
+/// put a gap out of reach back to the furthest random gaps can go. Entries
+/// that aren't numbers end up in the middle.
+pub fn validate_offsets(offsets: &[f32]) -> Vec<f32> {
+    offsets
+        .iter()
+        .enumerate()
+        .map(|(index, &offset)| {
+            let valid = if offset.is_finite() { offset } else { 0. };
+            let clamped = valid.clamp(-OBSTACLE_VERTICAL_OFFSET, OBSTACLE_VERTICAL_OFFSET);
+            if clamped != offset {
+                warn!("Gap {index} of the pattern is out of bounds at {offset}, using {clamped}");
+            }
+            clamped * PIXEL_RATIO
+        })
+        .collect()
+}
@@ -0,0 +1,211 @@
+//! Whole runs played with no window, renderer or player, for checking
+//! gameplay changes end to end. The seed, the frame time and every flap are
+//! fixed up front, so the same script always ends the same way.
+
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    state::app::StatesPlugin,
+    time::{TimeSystem, TimeUpdateStrategy},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    autopilot,
+    config::GameConfig,
+    gauntlet::PatternSource,
+    input::FlapInput,
+    lay_out_pipes,
+    modes::{GameMode, ModeRules},
+    spawn_bird, spawn_pipe_pool, Bird, BirdPlugin, DeathCause, Difficulty, GameManager, GameRng,
+    GameState, Invulnerable, LastDeath, ObstaclePlugin, PipeQueue, RunPlugin, Score, ScoringPlugin,
+    WINDOW_SIZE,
+};
+
+/// Frame time of a scripted run, a steady 60 frames a second.
+const SCRIPTED_FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// A run to play: the seed its pipes are rolled from, the frames the bird
+/// flaps on, counting from 0, and when to give up if it's still alive.
+pub struct ScriptedRun {
+    pub config: GameConfig,
+    pub seed: u64,
+    pub flap_frames: Vec<u32>,
+    pub max_frames: u32,
+}
+
+/// What the player did on one frame of a run, and how long the frame took.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScriptedFrame {
+    /// Seconds since the previous frame.
+    pub delta: f32,
+    pub flap: bool,
+    /// The flap controls were down, for variable flaps and gliding.
+    pub held: bool,
+    pub dive: bool,
+}
+
+impl ScriptedRun {
+    /// Every frame of the run at the steady scripted frame rate, holding
+    /// the flap only on the frames it's pressed.
+    pub fn frames(&self) -> Vec<ScriptedFrame> {
+        (0..self.max_frames)
+            .map(|frame| {
+                let flap = self.flap_frames.contains(&frame);
+                ScriptedFrame {
+                    delta: SCRIPTED_FRAME_TIME.as_secs_f32(),
+                    flap,
+                    held: flap,
+                    dive: false,
+                }
+            })
+            .collect()
+    }
+}
+
+/// How a scripted run ended.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RunOutcome {
+    pub points: u32,
+    pub pipes_passed: u32,
+    /// `None` when the bird was still flying after `max_frames`, or won.
+    pub death: Option<DeathCause>,
+    pub state: GameState,
+    /// Frames played before the run ended.
+    pub frames: u32,
+}
+
+/// The frames still to come, played back one at a time. Past the end the
+/// run carries on at the steady scripted frame rate with nothing pressed.
+#[derive(Resource)]
+struct InputScript {
+    frames: Vec<ScriptedFrame>,
+    frame: usize,
+}
+
+/// Runs before the clock is advanced so each frame lasts as long as the
+/// script says.
+fn play_input_script(
+    mut script: ResMut<InputScript>,
+    mut flap_input: ResMut<FlapInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+) {
+    let frame = script
+        .frames
+        .get(script.frame)
+        .copied()
+        .unwrap_or(ScriptedFrame {
+            delta: SCRIPTED_FRAME_TIME.as_secs_f32(),
+            ..Default::default()
+        });
+    *time_update = TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(frame.delta));
+    flap_input.just_pressed = frame.flap;
+    flap_input.held = frame.held;
+    if frame.dive {
+        keys.press(KeyCode::ArrowDown);
+    } else {
+        keys.release(KeyCode::ArrowDown);
+    }
+    script.frame += 1;
+}
+
+/// Size of the world the scripted run is laid out in.
+#[derive(Resource)]
+struct ScriptedWindowSize(Vec2);
+
+fn setup_headless_level(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    rules: Res<ModeRules>,
+    window_size: Res<ScriptedWindowSize>,
+) {
+    let game_manager = GameManager::new(&config, Handle::default(), window_size.0);
+    spawn_bird(
+        &mut commands,
+        &game_manager,
+        &config,
+        &rules,
+        &Handle::default(),
+    );
+    spawn_pipe_pool(&mut commands, &game_manager, config.pipe_cap_height);
+    commands.insert_resource(game_manager);
+}
+
+/// Gives the bird the same spawn invulnerability a real run starts with.
+fn start_headless_run(
+    mut commands: Commands,
+    rules: Res<ModeRules>,
+    bird_query: Query<Entity, With<Bird>>,
+) {
+    for entity in bird_query.iter() {
+        commands
+            .entity(entity)
+            .insert(Invulnerable::new(rules.spawn_invulnerability));
+    }
+}
+
+/// An app already in the `Playing` state with `run` set up, ready to be
+/// stepped with `App::update`.
+pub fn build_headless_app(run: &ScriptedRun) -> App {
+    build_scripted_app(run.config.clone(), run.seed, WINDOW_SIZE, run.frames())
+}
+
+/// Like `build_headless_app`, playing back `frames` in a world
+/// `window_size` big, which is all it takes to play a recorded run again.
+pub fn build_scripted_app(
+    config: GameConfig,
+    seed: u64,
+    window_size: Vec2,
+    frames: Vec<ScriptedFrame>,
+) -> App {
+    let rules = GameMode::of(&config).rules(&config);
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(SCRIPTED_FRAME_TIME))
+        .insert_resource(config)
+        .insert_resource(GameRng(StdRng::seed_from_u64(seed)))
+        .insert_resource(ScriptedWindowSize(window_size))
+        .insert_resource(InputScript { frames, frame: 0 })
+        .insert_state(GameState::Playing)
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<FlapInput>()
+        .init_resource::<Assets<Image>>()
+        .insert_resource(Difficulty::default().with_mode_rules(&rules))
+        .insert_resource(rules)
+        .init_resource::<PatternSource>()
+        .init_resource::<PipeQueue>()
+        // After the config, which they read as they're added
+        .add_plugins((RunPlugin, ScoringPlugin, BirdPlugin, ObstaclePlugin))
+        .add_systems(
+            Startup,
+            (setup_headless_level, lay_out_pipes, start_headless_run).chain(),
+        )
+        .add_systems(First, play_input_script.before(TimeSystem))
+        .add_systems(PreUpdate, autopilot::drive_autopilot);
+    app
+}
+
+/// Plays `run` until the bird dies, wins or runs out of frames.
+pub fn play_scripted_run(run: &ScriptedRun) -> RunOutcome {
+    let mut app = build_headless_app(run);
+    let mut frames = 0;
+    while frames < run.max_frames
+        && *app.world().resource::<State<GameState>>().get() == GameState::Playing
+    {
+        app.update();
+        frames += 1;
+    }
+
+    let world = app.world();
+    let score = world.resource::<Score>();
+    RunOutcome {
+        points: score.points,
+        pipes_passed: score.pipes_passed,
+        death: world.resource::<LastDeath>().0,
+        state: *world.resource::<State<GameState>>().get(),
+        frames,
+    }
+}
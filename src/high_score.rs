@@ -0,0 +1,200 @@
+//! The best scores, kept in a file of their own next to the save file. A
+//! missing or unreadable file starts them over from 0 rather than keeping
+//! the game from starting.
+//!
+//! They used to be part of the save file, so the first launch without a
+//! high score file carries over whatever bests an older save has.
+
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::save::{data_dir, remove_file_if_present, write_atomically, SAVE_FILE_NAME};
+
+const HIGH_SCORE_FILE_NAME: &str = "high_score.ron";
+
+/// Best regular and time attack scores. The daily challenge keeps its own
+/// record in the save file.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct HighScore {
+    pub best: u32,
+    pub time_attack_best: u32,
+}
+
+/// The bests as older save files kept them.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SavedBests {
+    best_score: u32,
+    time_attack_best: u32,
+}
+
+impl HighScore {
+    /// Reads the high score file, or the bests of an older save file when
+    /// there isn't one yet.
+    pub fn load() -> Self {
+        let dir = data_dir();
+        if let Some(high_score) = Self::read(&dir.join(HIGH_SCORE_FILE_NAME)) {
+            return high_score;
+        }
+        let Some(high_score) = Self::read_old_save(&dir.join(SAVE_FILE_NAME)) else {
+            return Self::default();
+        };
+        // Written straight away, since the next save drops them from there
+        high_score.store();
+        high_score
+    }
+
+    /// The bests in the file at `path`, `None` when there's no file. A
+    /// corrupt one reads as no bests yet.
+    fn read(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        Some(ron::from_str(&contents).unwrap_or_else(|error| {
+            warn!(
+                "Ignoring corrupt high score file {}: {error}",
+                path.display()
+            );
+            Self::default()
+        }))
+    }
+
+    /// The bests kept in an older save file at `path`, when it has any.
+    fn read_old_save(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        let bests: SavedBests = ron::from_str(&contents).ok()?;
+        let high_score = Self {
+            best: bests.best_score,
+            time_attack_best: bests.time_attack_best,
+        };
+        (high_score != Self::default()).then_some(high_score)
+    }
+
+    /// Keeps `score` if it beats the stored best for its mode, returning
+    /// whether it did.
+    pub fn submit(&mut self, score: u32, time_attack: bool) -> bool {
+        let best = if time_attack {
+            &mut self.time_attack_best
+        } else {
+            &mut self.best
+        };
+
+        if score > *best {
+            *best = score;
+            return true;
+        }
+        false
+    }
+
+    /// The best for the mode, time attack or not.
+    pub fn best_for(&self, time_attack: bool) -> u32 {
+        if time_attack {
+            self.time_attack_best
+        } else {
+            self.best
+        }
+    }
+
+    pub fn store(&self) {
+        self.write(&data_dir().join(HIGH_SCORE_FILE_NAME));
+    }
+
+    fn write(&self, path: &Path) {
+        let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => contents,
+            Err(error) => {
+                error!("Failed to serialize high scores: {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = write_atomically(path, &contents) {
+            error!("Failed to write high score file: {error}");
+        }
+    }
+
+    /// Forgets the bests, file and all.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+        let path = data_dir().join(HIGH_SCORE_FILE_NAME);
+        if let Err(error) = remove_file_if_present(&path) {
+            error!(
+                "Failed to remove high score file {}: {error}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf};
+
+    use super::*;
+
+    /// An empty directory of its own for the test called `name`.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("bevy_flappy_bird_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn stored_scores_read_back() {
+        let dir = temp_dir("high_score_round_trip");
+        let path = dir.join(HIGH_SCORE_FILE_NAME);
+        let high_score = HighScore {
+            best: 42,
+            time_attack_best: 17,
+        };
+        high_score.write(&path);
+        assert_eq!(HighScore::read(&path), Some(high_score));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_or_corrupt_files_start_from_nothing() {
+        let dir = temp_dir("high_score_corrupt");
+        let path = dir.join(HIGH_SCORE_FILE_NAME);
+        assert_eq!(HighScore::read(&path), None);
+
+        fs::write(&path, "(best: \"lots\"").unwrap();
+        assert_eq!(HighScore::read(&path), Some(HighScore::default()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bests_carry_over_from_an_older_save() {
+        let dir = temp_dir("high_score_old_save");
+        let path = dir.join(SAVE_FILE_NAME);
+        fs::write(
+            &path,
+            "(best_score: 30, time_attack_best: 12, daily: None, coins: 5)",
+        )
+        .unwrap();
+        assert_eq!(
+            HighScore::read_old_save(&path),
+            Some(HighScore {
+                best: 30,
+                time_attack_best: 12,
+            })
+        );
+
+        fs::write(&path, "(coins: 5)").unwrap();
+        assert_eq!(HighScore::read_old_save(&path), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_a_higher_score_is_kept() {
+        let mut high_score = HighScore::default();
+        assert!(high_score.submit(10, false));
+        assert!(!high_score.submit(10, false));
+        assert!(!high_score.submit(4, false));
+        assert!(high_score.submit(4, true));
+        assert_eq!(high_score.best_for(false), 10);
+        assert_eq!(high_score.best_for(true), 4);
+    }
+}
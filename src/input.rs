@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{autopilot, GameState};
+
+/// Seconds after a touch during which mouse clicks are ignored, since some
+/// platforms report a tap as both.
+const TOUCH_MOUSE_SUPPRESSION: f32 = 0.5;
+/// Seconds within which the same menu step from another device is taken to
+/// be the same press, for devices that report it a frame apart.
+const MENU_INPUT_DEBOUNCE: f32 = 0.05;
+
+/// Reads the flap and menu controls from every device each frame, ahead of
+/// anything that acts on them, and lets the autopilot flap in the player's
+/// place.
+pub struct GameInputPlugin;
+
+impl Plugin for GameInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlapInput>()
+            .init_resource::<MenuInputDebounce>()
+            .add_event::<MenuInput>()
+            .add_systems(
+                PreUpdate,
+                (read_flap_input, read_menu_input).after(InputSystem),
+            )
+            .add_systems(
+                PreUpdate,
+                autopilot::drive_autopilot
+                    .after(read_flap_input)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Whether the player flapped this frame, from whichever input they use.
+#[derive(Resource, Default)]
+pub struct FlapInput {
+    pub just_pressed: bool,
+    /// Still down, for controls that act while held.
+    pub held: bool,
+    /// Real time of the last touch, used to drop emulated mouse clicks.
+    last_touch: Option<f32>,
+}
+
+/// Space, a left click or a tap all flap. However many fingers come down in
+/// a frame, or whatever else fires along with them, it's a single flap.
+pub fn read_flap_input(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut flap_input: ResMut<FlapInput>,
+) {
+    let now = time.elapsed_secs();
+    let touching = touches.iter().next().is_some() || touches.any_just_pressed();
+    if touching {
+        flap_input.last_touch = Some(now);
+    }
+    let mouse_allowed = flap_input
+        .last_touch
+        .is_none_or(|last_touch| now - last_touch > TOUCH_MOUSE_SUPPRESSION);
+
+    flap_input.just_pressed = keys.just_pressed(KeyCode::Space)
+        || touches.any_just_pressed()
+        || (mouse_allowed && mouse.just_pressed(MouseButton::Left));
+    flap_input.held = keys.pressed(KeyCode::Space)
+        || touching
+        || (mouse_allowed && mouse.pressed(MouseButton::Left));
+}
+
+/// Whether any key, mouse button or touch went down this frame.
+pub fn any_input_pressed(
+    keys: &ButtonInput<KeyCode>,
+    mouse: &ButtonInput<MouseButton>,
+    touches: &Touches,
+) -> bool {
+    keys.get_just_pressed().next().is_some()
+        || mouse.get_just_pressed().next().is_some()
+        || touches.any_just_pressed()
+}
+
+/// One step through a menu, whichever device it came from.
+#[derive(Event, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Confirm,
+}
+
+/// When each menu step was last let through.
+#[derive(Resource, Default)]
+pub struct MenuInputDebounce {
+    last_accepted: HashMap<MenuInput, f32>,
+}
+
+impl MenuInputDebounce {
+    /// Whether `input` at real time `now` is a new press rather than the
+    /// same one seen again from another device.
+    pub fn accept(&mut self, input: MenuInput, now: f32) -> bool {
+        let repeated = self
+            .last_accepted
+            .get(&input)
+            .is_some_and(|&last| now - last < MENU_INPUT_DEBOUNCE);
+        if !repeated {
+            self.last_accepted.insert(input, now);
+        }
+        !repeated
+    }
+}
+
+pub fn keyboard_menu_inputs(keys: &ButtonInput<KeyCode>) -> impl Iterator<Item = MenuInput> + '_ {
+    [
+        (KeyCode::ArrowUp, MenuInput::Up),
+        (KeyCode::ArrowDown, MenuInput::Down),
+        (KeyCode::Enter, MenuInput::Confirm),
+    ]
+    .into_iter()
+    .filter(|&(key, _)| keys.just_pressed(key))
+    .map(|(_, input)| input)
+}
+
+pub fn gamepad_menu_inputs(gamepad: &Gamepad) -> impl Iterator<Item = MenuInput> + '_ {
+    [
+        (GamepadButton::DPadUp, MenuInput::Up),
+        (GamepadButton::DPadDown, MenuInput::Down),
+        (GamepadButton::South, MenuInput::Confirm),
+    ]
+    .into_iter()
+    .filter(|&(button, _)| gamepad.just_pressed(button))
+    .map(|(_, input)| input)
+}
+
+/// Merges the menu presses of the keyboard and every gamepad into one
+/// stream of steps, so pressing the same thing on two of them at once only
+/// moves once.
+pub fn read_menu_input(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut debounce: ResMut<MenuInputDebounce>,
+    mut menu_inputs: EventWriter<MenuInput>,
+) {
+    let now = time.elapsed_secs();
+    let inputs = keyboard_menu_inputs(&keys).chain(gamepads.iter().flat_map(gamepad_menu_inputs));
+    for input in inputs {
+        if debounce.accept(input, now) {
+            menu_inputs.send(input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// The menu steps `read_menu_input` sends for this frame's presses.
+    fn menu_steps(keys: &[KeyCode], gamepads: &[&[GamepadButton]]) -> Vec<MenuInput> {
+        let mut world = World::new();
+        world.init_resource::<Time<Real>>();
+        world.init_resource::<MenuInputDebounce>();
+        world.init_resource::<Events<MenuInput>>();
+        let mut key_input = ButtonInput::<KeyCode>::default();
+        for &key in keys {
+            key_input.press(key);
+        }
+        world.insert_resource(key_input);
+        for &buttons in gamepads {
+            let mut gamepad = Gamepad::default();
+            for &button in buttons {
+                gamepad.digital_mut().press(button);
+            }
+            world.spawn(gamepad);
+        }
+
+        world.run_system_once(read_menu_input).unwrap();
+        world.resource_mut::<Events<MenuInput>>().drain().collect()
+    }
+
+    #[test]
+    fn keyboard_and_gamepad_together_step_once() {
+        let steps = menu_steps(&[KeyCode::ArrowDown], &[&[GamepadButton::DPadDown]]);
+        assert_eq!(steps, [MenuInput::Down]);
+    }
+
+    #[test]
+    fn two_gamepads_together_step_once() {
+        let steps = menu_steps(&[], &[&[GamepadButton::South], &[GamepadButton::South]]);
+        assert_eq!(steps, [MenuInput::Confirm]);
+    }
+
+    #[test]
+    fn different_steps_in_one_frame_all_count() {
+        let steps = menu_steps(&[KeyCode::ArrowUp], &[&[GamepadButton::South]]);
+        assert_eq!(steps, [MenuInput::Up, MenuInput::Confirm]);
+    }
+
+    #[test]
+    fn presses_apart_count_separately() {
+        let mut debounce = MenuInputDebounce::default();
+        assert!(debounce.accept(MenuInput::Down, 1.));
+        assert!(!debounce.accept(MenuInput::Down, 1. + MENU_INPUT_DEBOUNCE / 2.));
+        assert!(debounce.accept(MenuInput::Down, 1. + MENU_INPUT_DEBOUNCE * 2.));
+    }
+}
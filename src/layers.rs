@@ -0,0 +1,17 @@
+//! Depth of everything drawn in the world, from the back to the front. The
+//! HUD and menus are UI, which always covers all of it.
+
+pub const SKY_Z: f32 = -10.;
+/// Depth of the farthest parallax layer. Each nearer one is drawn a little
+/// in front of the last, and all of them behind the pipes.
+pub const PARALLAX_Z: f32 = -5.;
+pub const PARALLAX_Z_STEP: f32 = 0.1;
+pub const PIPE_Z: f32 = 0.;
+pub const FINISH_LINE_Z: f32 = 0.2;
+pub const TARGET_Z: f32 = 0.5;
+/// Behind the lead, so it stays in front where they overlap.
+pub const FLOCKMATE_Z: f32 = 0.9;
+pub const BIRD_Z: f32 = 1.;
+pub const PARTICLE_Z: f32 = 3.;
+/// Score popups and feedback, over the bird they rise from.
+pub const POPUP_Z: f32 = 5.;
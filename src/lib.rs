@@ -0,0 +1,321 @@
+pub mod ambient;
+pub mod animation;
+pub mod assets;
+pub mod attract;
+pub mod audio;
+pub mod audio_cues;
+pub mod autopilot;
+pub mod background;
+pub mod beat;
+pub mod bird;
+pub mod camera;
+pub mod checkpoint;
+pub mod config;
+pub mod daily;
+pub mod death;
+pub mod debug;
+pub mod difficulty;
+pub mod flock;
+pub mod gap_telegraph;
+pub mod gauntlet;
+pub mod headless;
+pub mod high_score;
+pub mod input;
+pub mod layers;
+pub mod menu;
+pub mod milestones;
+pub mod modes;
+pub mod monitor;
+pub mod obstacle;
+pub mod particles;
+pub mod replay;
+pub mod run;
+pub mod save;
+pub mod scoring;
+pub mod screenshot;
+pub mod targets;
+pub mod telemetry;
+pub mod transition;
+pub mod ui;
+pub mod whoosh;
+
+pub use bird::{
+    handle_bird_deaths, spawn_bird, squash_stretch, tutorial_gravity_scale, update_bird,
+    update_gravity_flip, update_invulnerability, variable_flap_force, Bird, BirdDied, BirdFlapped,
+    BirdPlugin, DeathCause, Downed, FlapBinding, Flight, GravityFlip, Health, Invulnerable,
+    LastDeath, Lead, Squash,
+};
+pub use death::{DeathAnimation, DeathPlugin, KillCam, RunContinue, CONTINUE_COST};
+pub use difficulty::{Difficulty, SessionStats};
+pub use obstacle::{
+    detect_pipe_passes, gap_center, generate_offset, get_centered_pipe_position, hit_pipe,
+    lay_out_pipes, pipe_color, pipe_half_size, pipe_overlaps_bird, pipe_overlaps_round_bird,
+    smooth_offset_range, spacing_offset, spawn_pipe_pool, staircase_offset, tier_color,
+    update_finish_line, update_obsacles, zigzag_offset, FinishLine, Obstacle, ObstaclePlugin,
+    PipeLayout, PipePassed, PipeQueue,
+};
+pub use run::{PracticeLevel, RunPlugin, RunSeed, RunSet, RunTime, TimeAttack};
+pub use scoring::{
+    run_is_record_eligible, slipstream_multiplier, update_score, NewBest, Score, ScoringPlugin,
+};
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use config::{GameConfig, GapPattern, HitboxShape, PipeTier, SoftCeiling};
+use modes::ModeRules;
+use rand::{rngs::StdRng, Rng};
+
+/// What the rest of the game is built on: its states, loading the assets
+/// and laying out the level, and writing the saves on the way out. Reads
+/// the config, so the app needs one before this is added.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        let practice_checkpoints = app.world().resource::<GameConfig>().practice_checkpoints;
+
+        app.init_state::<GameState>()
+            .enable_state_scoped_entities::<GameState>()
+            .add_systems(PreStartup, assets::load_game_assets)
+            .add_systems(Startup, (setup_level, lay_out_pipes).chain())
+            .add_systems(OnEnter(GameState::Loading), assets::spawn_loading_screen)
+            .add_systems(
+                Update,
+                assets::update_loading.run_if(in_state(GameState::Loading)),
+            )
+            .add_systems(Last, save::flush_saves_on_exit);
+
+        if practice_checkpoints {
+            app.add_systems(
+                Update,
+                (
+                    checkpoint::set_checkpoint,
+                    checkpoint::respawn_at_checkpoint.after(RunSet::Bird),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+        }
+    }
+}
+
+pub const WINDOW_SIZE: Vec2 = Vec2::new(512., 512.);
+
+const PIXEL_RATIO: f32 = 4.0;
+const GRAVITY: f32 = 2000.;
+const FLAP_FORCE: f32 = 500.;
+const VELOCITY_TO_ROTATION_RATIO: f32 = 7.5;
+/// Half the bird sprite's size, in sprite pixels.
+const BIRD_HALF_SIZE: Vec2 = Vec2::new(6., 4.);
+/// Radius of the bird's round hitbox, in sprite pixels. Fits the body, the
+/// beak and tail stick out past it.
+pub const BIRD_RADIUS: f32 = 4.;
+/// Smallest bird allowed, keeping the gap from closing up.
+const MIN_BIRD_SCALE: f32 = 0.5;
+/// Fastest the bird can fall, which only long drops ever reach.
+const TERMINAL_VELOCITY: f32 = 1000.;
+
+/// Fewest pipe pairs in the pool, enough to always cover the screen.
+const OBSTACLE_AMOUNT: u32 = 5;
+const MAX_PIPE_POOL_SIZE: u32 = 64;
+const OBSTACLE_WIDTH: f32 = 32.;
+const OBSTACLE_HEIGHT: f32 = 144.;
+/// Width of pipe.png, in sprite pixels. `OBSTACLE_WIDTH` also takes in the
+/// bird's own width.
+const PIPE_SPRITE_WIDTH: f32 = 18.;
+const OBSTACLE_VERTICAL_OFFSET: f32 = 30.;
+const OBSTACLE_GAP_SIZE: f32 = 15.;
+const OBSTACLE_SPACING: f32 = 60.;
+/// Most spacing jitter can add to or take from the distance between two
+/// pairs. At their closest, pairs still leave over half the usual room
+/// between them to react in.
+const MAX_PIPE_SPACING_JITTER: f32 = 12.;
+/// Closest pairs can be with spacing jitter, in sprite pixels.
+pub const MIN_PIPE_SPACING: f32 = OBSTACLE_SPACING - MAX_PIPE_SPACING_JITTER;
+const OBSTACLE_SCROLL_SPEED: f32 = 150.;
+
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    /// Waits for `GameAssets` so nothing pops in on the first frames.
+    #[default]
+    Loading,
+    Menu,
+    Playing,
+    Paused,
+    /// The bird tumbles off screen before the game over screen shows up.
+    Dying,
+    GameOver,
+    /// The bird made it past the finish line.
+    Victory,
+    /// Watching the last recorded run.
+    ReplayViewer,
+}
+
+#[derive(Resource)]
+pub struct GameManager {
+    pub pipe_image: Handle<Image>,
+    pub window_dimentions: Vec2,
+    /// 1 when the bird faces right and pipes scroll in from the right, -1 in
+    /// mirror mode.
+    pub facing: f32,
+    /// Where the bird spawns and respawns, already mirrored when needed.
+    pub bird_start: Vec3,
+    /// Seconds of invulnerability after taking a hit.
+    pub hit_invulnerability: f32,
+    /// Extra downward acceleration while Down is held, when diving is on.
+    pub dive_strength: Option<f32>,
+    pub soft_ceiling: Option<SoftCeiling>,
+    pub gap_pattern: GapPattern,
+    /// Size of the bird relative to its sprite, hitbox included.
+    pub bird_scale: f32,
+    /// How far ahead of the bird the first pipe of a run is placed, the
+    /// tutorial stretch included.
+    pub first_pipe_distance: f32,
+    /// Open sky at the start of every run flown with gentler gravity, in
+    /// world units. 0 without a tutorial.
+    pub tutorial_lead_in: f32,
+    /// Pipe tints by difficulty level, empty when pipes keep their colors.
+    pub pipe_tiers: Vec<PipeTier>,
+    /// Pairs of pipes before the finish line, `None` for endless runs.
+    pub finish_line: Option<u32>,
+    /// The bird flies itself.
+    pub autopilot: bool,
+    pub hitbox_shape: HitboxShape,
+    /// Size of the pipe pair pool, which also sets how far ahead pairs are
+    /// recycled to.
+    pub pipe_pairs: u32,
+    /// Most the distance between pairs varies by, in sprite pixels.
+    pub pipe_spacing_jitter: f32,
+}
+
+impl GameManager {
+    /// Settings for a play area of `window_dimentions` taken from `config`,
+    /// with out of range values pulled back to what the game can handle.
+    pub fn new(config: &GameConfig, pipe_image: Handle<Image>, window_dimentions: Vec2) -> Self {
+        let facing = if config.mirror_mode { -1. } else { 1. };
+        Self {
+            pipe_image,
+            window_dimentions,
+            facing,
+            bird_start: Vec3::new(
+                config.bird_start[0] * facing,
+                config.bird_start[1],
+                layers::BIRD_Z,
+            ),
+            hit_invulnerability: config.hit_invulnerability.max(0.),
+            dive_strength: config.dive.then_some(config.dive_strength),
+            soft_ceiling: config.soft_ceiling.enabled.then_some(config.soft_ceiling),
+            gap_pattern: config.gap_pattern,
+            bird_scale: config.bird_scale.max(MIN_BIRD_SCALE),
+            first_pipe_distance: config.first_pipe_distance.max(0.)
+                + config.tutorial_lead_in.max(0.),
+            tutorial_lead_in: config.tutorial_lead_in.max(0.),
+            pipe_tiers: if config.pipe_tier_colors {
+                config.pipe_tiers.clone()
+            } else {
+                Vec::new()
+            },
+            finish_line: (config.finish_line_pipes > 0).then_some(config.finish_line_pipes),
+            autopilot: config.autopilot,
+            hitbox_shape: config.hitbox_shape,
+            pipe_pairs: config
+                .pipe_pool_size
+                .clamp(OBSTACLE_AMOUNT, MAX_PIPE_POOL_SIZE),
+            pipe_spacing_jitter: config
+                .pipe_spacing_jitter
+                .clamp(0., MAX_PIPE_SPACING_JITTER),
+        }
+    }
+
+    /// How much further than usual the bird reaches from its center on each
+    /// side. Pipe extents already allow for a bird of the usual size, so
+    /// this is only what a rescaled one adds, and shrinks them when smaller.
+    pub fn bird_growth(&self) -> Vec2 {
+        BIRD_HALF_SIZE * PIXEL_RATIO * (self.bird_scale - 1.)
+    }
+
+    /// `gap_size` widened or narrowed to leave a rescaled bird the same room.
+    pub fn effective_gap_size(&self, gap_size: f32) -> f32 {
+        gap_size + BIRD_HALF_SIZE.y * (self.bird_scale - 1.)
+    }
+
+    /// Radius of the bird's round hitbox in world units.
+    pub fn bird_radius(&self) -> f32 {
+        BIRD_RADIUS * PIXEL_RATIO * self.bird_scale
+    }
+
+    /// A bigger bird is easier to track but its runs aren't recorded.
+    pub fn bird_enlarged(&self) -> bool {
+        self.bird_scale > 1.
+    }
+
+    /// Whether the pair numbered `pair_number` would come after the finish
+    /// line, where there are no pipes.
+    pub fn past_finish_line(&self, pair_number: u32) -> bool {
+        self.finish_line
+            .is_some_and(|finish_line| pair_number >= finish_line)
+    }
+
+    /// Tint of pipes placed at difficulty `level`.
+    pub fn tier_color(&self, level: u32) -> Color {
+        tier_color(&self.pipe_tiers, level)
+    }
+
+    /// How far a newly placed pair moves from its regular place, nothing
+    /// without spacing jitter. Rolling only with it on keeps seeded runs
+    /// without it laid out the same.
+    fn roll_spacing_offset(&self, rand: &mut impl Rng) -> f32 {
+        if self.pipe_spacing_jitter > 0. {
+            spacing_offset(rand.gen(), self.pipe_spacing_jitter)
+        } else {
+            0.
+        }
+    }
+}
+
+/// Drives every random pipe layout so seeded runs play out identically.
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+fn setup_level(
+    mut commands: Commands,
+    game_assets: Res<assets::GameAssets>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    config: Res<GameConfig>,
+    rules: Res<ModeRules>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    commands.insert_resource(ClearColor(Color::srgb(0.5, 0.7, 0.8)));
+
+    let pipe_image = game_assets.pipe.clone();
+    let window = window_query.get_single().unwrap();
+    // The pixel-perfect world is always drawn at the same resolution,
+    // however big the window is
+    let window_dimentions = if config.pixel_perfect {
+        camera::spawn_pixel_perfect_cameras(&mut commands, &mut images, config.camera_zoom);
+        WINDOW_SIZE
+    } else {
+        commands.spawn((
+            Camera2d,
+            camera::world_projection(config.camera_zoom),
+            camera::WorldCamera,
+        ));
+        Vec2::new(window.width(), window.height())
+    };
+
+    let game_manager = GameManager::new(&config, pipe_image, window_dimentions);
+    spawn_bird(
+        &mut commands,
+        &game_manager,
+        &config,
+        &rules,
+        &game_assets.bird,
+    );
+    flock::spawn_flock(
+        &mut commands,
+        &game_manager,
+        &config,
+        &rules,
+        &game_assets.bird,
+    );
+    spawn_pipe_pool(&mut commands, &game_manager, config.pipe_cap_height);
+    commands.insert_resource(game_manager);
+}
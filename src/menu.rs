@@ -0,0 +1,928 @@
+use bevy::{
+    app::AppExit,
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
+
+use crate::{
+    assets::GameAssets,
+    config::{ControlScheme, GameConfig},
+    daily::{self, DailyChallenge, DailyRecord},
+    difficulty::DIFFICULTY_MAX_LEVEL,
+    high_score::HighScore,
+    input::MenuInput,
+    modes::{self, GameMode},
+    replay::ReplaySlot,
+    save::{self, SaveData},
+    Difficulty, GameManager, GameState, Lead, PracticeLevel, RunSeed, SessionStats,
+};
+
+const ITEM_COLOR: Color = Color::WHITE;
+const SELECTED_ITEM_COLOR: Color = Color::srgb(1., 0.9, 0.3);
+const DISABLED_ITEM_COLOR: Color = Color::srgb(0.45, 0.45, 0.45);
+const SELECTED_DISABLED_ITEM_COLOR: Color = Color::srgb(0.6, 0.55, 0.35);
+
+const MAIN_MENU_ITEMS: [&str; 8] = [
+    "Play",
+    "Modes",
+    "Enter seed",
+    "Watch replay",
+    "Watch best",
+    "Settings",
+    "Leaderboard",
+    "Quit",
+];
+const SEED_MENU_ITEMS: [&str; 2] = ["Play", "Back"];
+/// Enough for any `u64`.
+const SEED_INPUT_MAX_LENGTH: usize = 20;
+/// Keeping the data comes first, so it's what's highlighted on arrival.
+const RESET_DATA_MENU_ITEMS: [&str; 2] = ["Keep my data", "Reset everything"];
+const PAUSE_MENU_ITEMS: [&str; 3] = ["Resume", "Main menu", "Quit"];
+
+const LOGO_HEIGHT: f32 = 96.;
+const MODE_ICON_SIZE: f32 = 20.;
+/// The bird idles on the menu, bobbing up and down and flapping now and
+/// then.
+const MENU_BIRD_BOB_HEIGHT: f32 = 12.;
+const MENU_BIRD_BOB_SPEED: f32 = 2.;
+const MENU_BIRD_FLAP_INTERVAL: f32 = 2.5;
+const MENU_BIRD_FLAP_DURATION: f32 = 0.4;
+const MENU_BIRD_FLAP_HEIGHT: f32 = 10.;
+const MENU_BIRD_FLAP_TILT: f32 = 25.;
+
+/// The menu screens and the pause menu. The game state has to be set up
+/// before this is added, since the menu screens only exist inside
+/// `GameState::Menu`.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<MenuScreen>()
+            .enable_state_scoped_entities::<MenuScreen>()
+            .init_resource::<MenuSelection>()
+            .add_systems(Update, animate_menu_bird.run_if(in_state(GameState::Menu)))
+            .add_systems(
+                Update,
+                (menu_navigation, highlight_menu_selection)
+                    .chain()
+                    .run_if(in_state(GameState::Menu).or(in_state(GameState::Paused))),
+            )
+            .add_systems(
+                OnEnter(GameState::Paused),
+                (reset_menu_selection, spawn_pause_menu),
+            )
+            .add_systems(
+                Update,
+                pause_menu_actions
+                    .after(menu_navigation)
+                    .run_if(in_state(GameState::Paused)),
+            )
+            .add_systems(
+                OnEnter(MenuScreen::Main),
+                (reset_menu_selection, spawn_main_menu),
+            )
+            .add_systems(
+                OnEnter(MenuScreen::Seed),
+                (reset_menu_selection, spawn_seed_menu),
+            )
+            .add_systems(
+                OnEnter(MenuScreen::Settings),
+                (reset_menu_selection, spawn_settings_menu),
+            )
+            .add_systems(
+                OnEnter(MenuScreen::Leaderboard),
+                (reset_menu_selection, spawn_leaderboard_menu),
+            )
+            .add_systems(
+                OnEnter(MenuScreen::Modes),
+                (reset_menu_selection, spawn_modes_menu),
+            )
+            .add_systems(
+                OnEnter(MenuScreen::ResetData),
+                (reset_menu_selection, spawn_reset_data_menu),
+            )
+            .add_systems(
+                OnEnter(MenuScreen::DailyResult),
+                (reset_menu_selection, spawn_daily_result_menu),
+            )
+            .add_systems(
+                Update,
+                return_to_menu
+                    .run_if(in_state(GameState::GameOver).or(in_state(GameState::Victory))),
+            )
+            .add_systems(
+                Update,
+                (
+                    main_menu_actions.run_if(in_state(MenuScreen::Main)),
+                    (seed_input, seed_menu_actions)
+                        .chain()
+                        .run_if(in_state(MenuScreen::Seed)),
+                    (settings_menu_actions, refresh_settings_labels)
+                        .chain()
+                        .run_if(in_state(MenuScreen::Settings)),
+                    leaderboard_menu_actions.run_if(in_state(MenuScreen::Leaderboard)),
+                    reset_data_menu_actions.run_if(in_state(MenuScreen::ResetData)),
+                    daily_result_menu_actions.run_if(in_state(MenuScreen::DailyResult)),
+                    (modes_menu_actions, describe_selected_mode)
+                        .run_if(in_state(MenuScreen::Modes)),
+                )
+                    .after(menu_navigation),
+            );
+    }
+}
+
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Menu)]
+pub enum MenuScreen {
+    #[default]
+    Main,
+    Seed,
+    Settings,
+    Leaderboard,
+    /// Asks before wiping all saved progress.
+    ResetData,
+    Modes,
+    /// Today's daily challenge result, once it's been played.
+    DailyResult,
+}
+
+/// Highlighted item on whichever menu screen is open.
+#[derive(Resource, Default)]
+pub struct MenuSelection {
+    pub index: usize,
+    /// Set on the frame the highlighted item is picked.
+    pub activated: bool,
+}
+
+#[derive(Component)]
+pub struct MenuItem {
+    pub index: usize,
+}
+
+/// A menu item that's shown but can't be picked right now.
+#[derive(Component)]
+pub struct DisabledItem;
+
+/// Describes the mode highlighted on the mode screen.
+#[derive(Component)]
+pub struct ModeDescription;
+
+/// Digits typed on the seed screen so far.
+#[derive(Component)]
+pub struct SeedInput;
+
+/// Moves the highlight with the arrow keys or d-pad, wrapping around at both
+/// ends, and picks it with Enter or the south button. Tapping or clicking an
+/// item picks it directly. Shared by every menu screen, which only have to
+/// spawn their `MenuItem`s.
+pub fn menu_navigation(
+    mut menu_inputs: EventReader<MenuInput>,
+    mut selection: ResMut<MenuSelection>,
+    items: Query<(&MenuItem, Ref<Interaction>)>,
+) {
+    selection.activated = false;
+    let count = items.iter().count();
+    if count == 0 {
+        menu_inputs.clear();
+        return;
+    }
+
+    // Interaction only changes once per press, whether it came from a
+    // finger or the mouse
+    if let Some((item, _)) = items
+        .iter()
+        .find(|(_, interaction)| interaction.is_changed() && **interaction == Interaction::Pressed)
+    {
+        selection.index = item.index;
+        selection.activated = true;
+        menu_inputs.clear();
+        return;
+    }
+
+    for input in menu_inputs.read() {
+        match input {
+            MenuInput::Up => selection.index = (selection.index + count - 1) % count,
+            MenuInput::Down => selection.index = (selection.index + 1) % count,
+            MenuInput::Confirm => selection.activated = true,
+        }
+    }
+}
+
+pub fn highlight_menu_selection(
+    selection: Res<MenuSelection>,
+    mut items: Query<(&MenuItem, &mut TextColor, Has<DisabledItem>)>,
+) {
+    for (item, mut color, disabled) in items.iter_mut() {
+        color.0 = match (item.index == selection.index, disabled) {
+            (true, false) => SELECTED_ITEM_COLOR,
+            (true, true) => SELECTED_DISABLED_ITEM_COLOR,
+            (false, false) => ITEM_COLOR,
+            (false, true) => DISABLED_ITEM_COLOR,
+        };
+    }
+}
+
+/// The item picked this frame, if any.
+pub fn activated_item(selection: &MenuSelection) -> Option<usize> {
+    selection.activated.then_some(selection.index)
+}
+
+pub fn reset_menu_selection(mut selection: ResMut<MenuSelection>) {
+    *selection = MenuSelection::default();
+}
+
+/// Spawns a centered column with a title, some informational lines and the
+/// selectable items, all despawned when `scope` is left.
+fn spawn_menu<S: States>(
+    commands: &mut Commands,
+    scope: S,
+    title: &str,
+    lines: &[String],
+    items: &[String],
+) {
+    spawn_menu_with_logo(commands, scope, title, None, lines, items);
+}
+
+/// Like `spawn_menu`, with `logo` shown in place of the title when there is
+/// one.
+fn spawn_menu_with_logo<S: States>(
+    commands: &mut Commands,
+    scope: S,
+    title: &str,
+    logo: Option<Handle<Image>>,
+    lines: &[String],
+    items: &[String],
+) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.),
+                ..Default::default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.4)),
+            StateScoped(scope),
+        ))
+        .with_children(|parent| {
+            match logo {
+                Some(logo) => {
+                    parent.spawn((
+                        ImageNode::new(logo),
+                        Node {
+                            height: Val::Px(LOGO_HEIGHT),
+                            ..Default::default()
+                        },
+                    ));
+                }
+                None => {
+                    parent.spawn((
+                        Text::new(title),
+                        TextFont {
+                            font_size: 48.,
+                            ..Default::default()
+                        },
+                    ));
+                }
+            }
+
+            for line in lines {
+                parent.spawn((
+                    Text::new(line.clone()),
+                    TextFont {
+                        font_size: 20.,
+                        ..Default::default()
+                    },
+                ));
+            }
+
+            for (index, label) in items.iter().enumerate() {
+                parent.spawn((
+                    Text::new(label.clone()),
+                    TextFont {
+                        font_size: 28.,
+                        ..Default::default()
+                    },
+                    TextColor(ITEM_COLOR),
+                    Interaction::default(),
+                    MenuItem { index },
+                ));
+            }
+        });
+}
+
+/// Today's result when the daily challenge is being played and today's
+/// attempt is already used up.
+fn finished_daily(save: &SaveData, daily: Option<&DailyChallenge>) -> Option<DailyRecord> {
+    let daily = daily?;
+    save.daily
+        .filter(|record| !daily::attempt_available(Some(record), daily.day))
+}
+
+/// Shows the logo when it loaded, the name in plain text otherwise, and
+/// today's daily challenge result once it's been played.
+pub fn spawn_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_assets: Res<GameAssets>,
+    save: Res<SaveData>,
+    daily: Option<Res<DailyChallenge>>,
+) {
+    let items = MAIN_MENU_ITEMS.map(String::from);
+    let logo = asset_server
+        .is_loaded_with_dependencies(&game_assets.logo)
+        .then(|| game_assets.logo.clone());
+    let lines: Vec<_> = finished_daily(&save, daily.as_deref())
+        .map(|record| format!("Today's daily challenge: {}", record.score))
+        .into_iter()
+        .collect();
+    spawn_menu_with_logo(
+        &mut commands,
+        MenuScreen::Main,
+        "Flappy bird",
+        logo,
+        &lines,
+        &items,
+    );
+}
+
+/// Keeps the bird bobbing around its start, hopping up with its nose raised
+/// every `MENU_BIRD_FLAP_INTERVAL`. Starting a run puts it back in place.
+pub fn animate_menu_bird(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    game_manager: Res<GameManager>,
+    mut bird_query: Query<&mut Transform, With<Lead>>,
+) {
+    let Ok(mut transform) = bird_query.get_single_mut() else {
+        return;
+    };
+    // Held level at its start otherwise
+    let (bob, flap) = if config.reduced_motion.pulsing_allowed() {
+        let elapsed = time.elapsed_secs();
+        (
+            (elapsed * MENU_BIRD_BOB_SPEED).sin() * MENU_BIRD_BOB_HEIGHT,
+            (1. - (elapsed % MENU_BIRD_FLAP_INTERVAL) / MENU_BIRD_FLAP_DURATION).max(0.),
+        )
+    } else {
+        (0., 0.)
+    };
+
+    transform.translation.y = game_manager.bird_start.y + bob + flap * MENU_BIRD_FLAP_HEIGHT;
+    transform.rotation =
+        Quat::from_rotation_z((flap * MENU_BIRD_FLAP_TILT).to_radians() * game_manager.facing);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn main_menu_actions(
+    selection: Res<MenuSelection>,
+    save: Res<SaveData>,
+    mut run_seed: ResMut<RunSeed>,
+    daily: Option<Res<DailyChallenge>>,
+    mut replay_slot: ResMut<ReplaySlot>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    match activated_item(&selection) {
+        // A finished daily challenge only shows its result, there's no run
+        // to start
+        Some(0) if finished_daily(&save, daily.as_deref()).is_some() => {
+            next_screen.set(MenuScreen::DailyResult)
+        }
+        Some(0) => {
+            run_seed.chosen = None;
+            next_game_state.set(GameState::Playing);
+        }
+        Some(1) => next_screen.set(MenuScreen::Modes),
+        Some(2) => next_screen.set(MenuScreen::Seed),
+        Some(3) if !ReplaySlot::Last.has_replay() => warn!("No replay recorded yet"),
+        Some(4) if !ReplaySlot::Best.has_replay() => warn!("No best run recorded yet"),
+        Some(index @ (3 | 4)) => {
+            *replay_slot = if index == 3 {
+                ReplaySlot::Last
+            } else {
+                ReplaySlot::Best
+            };
+            next_game_state.set(GameState::ReplayViewer);
+        }
+        Some(5) => next_screen.set(MenuScreen::Settings),
+        Some(6) => next_screen.set(MenuScreen::Leaderboard),
+        Some(7) => {
+            exit.send(AppExit::Success);
+        }
+        _ => {}
+    }
+}
+
+/// Lists every mode with its icon, dimming the ones that can't be played
+/// right now. The highlighted one is described underneath.
+pub fn spawn_modes_menu(mut commands: Commands, config: Res<GameConfig>, save: Res<SaveData>) {
+    let current = GameMode::of(&config);
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.),
+                ..Default::default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.4)),
+            StateScoped(MenuScreen::Modes),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Modes"),
+                TextFont {
+                    font_size: 48.,
+                    ..Default::default()
+                },
+            ));
+
+            for (index, mode) in GameMode::ALL.into_iter().enumerate() {
+                let available = mode.unavailable_reason(&config, &save).is_none();
+                let icon_color = if available {
+                    mode.icon_color()
+                } else {
+                    DISABLED_ITEM_COLOR
+                };
+                let label = if mode == current {
+                    format!("{} (current)", mode.name())
+                } else {
+                    String::from(mode.name())
+                };
+                parent
+                    .spawn(Node {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(10.),
+                        ..Default::default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Node {
+                                width: Val::Px(MODE_ICON_SIZE),
+                                height: Val::Px(MODE_ICON_SIZE),
+                                ..Default::default()
+                            },
+                            BackgroundColor(icon_color),
+                            BorderRadius::all(Val::Px(MODE_ICON_SIZE / 4.)),
+                        ));
+                        let mut item = row.spawn((
+                            Text::new(label),
+                            TextFont {
+                                font_size: 28.,
+                                ..Default::default()
+                            },
+                            TextColor(ITEM_COLOR),
+                            Interaction::default(),
+                            MenuItem { index },
+                        ));
+                        if !available {
+                            item.insert(DisabledItem);
+                        }
+                    });
+            }
+
+            parent.spawn((
+                Text::new("Back"),
+                TextFont {
+                    font_size: 28.,
+                    ..Default::default()
+                },
+                TextColor(ITEM_COLOR),
+                Interaction::default(),
+                MenuItem {
+                    index: GameMode::ALL.len(),
+                },
+            ));
+
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 20.,
+                    ..Default::default()
+                },
+                ModeDescription,
+            ));
+        });
+}
+
+pub fn describe_selected_mode(
+    selection: Res<MenuSelection>,
+    config: Res<GameConfig>,
+    save: Res<SaveData>,
+    mut query: Query<&mut Text, With<ModeDescription>>,
+) {
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+    let description = match GameMode::ALL.get(selection.index) {
+        Some(mode) => match mode.unavailable_reason(&config, &save) {
+            Some(reason) => format!("Unavailable: {reason}"),
+            None => String::from(mode.description()),
+        },
+        None => String::new(),
+    };
+    if text.0 != description {
+        text.0 = description;
+    }
+}
+
+/// Picking a mode switches to it and starts a run straight away.
+pub fn modes_menu_actions(
+    mut commands: Commands,
+    selection: Res<MenuSelection>,
+    save: Res<SaveData>,
+    mut config: ResMut<GameConfig>,
+    mut run_seed: ResMut<RunSeed>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    let Some(index) = activated_item(&selection) else {
+        return;
+    };
+    let Some(&mode) = GameMode::ALL.get(index) else {
+        next_screen.set(MenuScreen::Main);
+        return;
+    };
+    if let Some(reason) = mode.unavailable_reason(&config, &save) {
+        warn!("{} can't be played: {reason}", mode.name());
+        return;
+    }
+
+    modes::switch_mode(mode, &mut commands, &mut config);
+    run_seed.chosen = None;
+    next_game_state.set(GameState::Playing);
+}
+
+/// A typed seed, or `None` when it isn't a valid one.
+pub fn parse_seed(input: &str) -> Option<u64> {
+    input.trim().parse().ok()
+}
+
+pub fn spawn_seed_menu(mut commands: Commands) {
+    let items = SEED_MENU_ITEMS.map(String::from);
+    spawn_menu(
+        &mut commands,
+        MenuScreen::Seed,
+        "Enter seed",
+        &[String::from("Type the seed of a shared run")],
+        &items,
+    );
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 32.,
+            ..Default::default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.),
+            width: Val::Percent(100.),
+            ..Default::default()
+        },
+        SeedInput,
+        StateScoped(MenuScreen::Seed),
+    ));
+}
+
+pub fn seed_input(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut query: Query<&mut Text, With<SeedInput>>,
+) {
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Backspace => {
+                text.0.pop();
+            }
+            Key::Character(character) if text.0.len() < SEED_INPUT_MAX_LENGTH => {
+                text.0.extend(
+                    character
+                        .chars()
+                        .filter(|character| character.is_ascii_digit()),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Anything that doesn't parse as a seed plays a random layout instead.
+pub fn seed_menu_actions(
+    selection: Res<MenuSelection>,
+    input_query: Query<&Text, With<SeedInput>>,
+    mut run_seed: ResMut<RunSeed>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    match activated_item(&selection) {
+        Some(0) => {
+            let input = input_query.get_single().map_or("", |text| text.0.as_str());
+            run_seed.chosen = parse_seed(input);
+            if run_seed.chosen.is_none() {
+                warn!("Invalid seed {input:?}, playing a random one");
+            }
+            next_game_state.set(GameState::Playing);
+        }
+        Some(1) => next_screen.set(MenuScreen::Main),
+        _ => {}
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+/// Previews what the practice level starts the run with.
+fn practice_label(level: u32) -> String {
+    let difficulty = Difficulty::starting_at(level);
+    format!(
+        "Practice level: {level} (gap {}, speed {})",
+        difficulty.gap_size(),
+        difficulty.scroll_speed()
+    )
+}
+
+fn settings_labels(config: &GameConfig, practice_level: &PracticeLevel) -> [String; 8] {
+    [
+        format!("Time attack: {}", on_off(config.time_attack)),
+        format!("Difficulty warning: {}", on_off(config.difficulty_warning)),
+        format!("Gap guide: {}", on_off(config.gap_guide)),
+        format!("Perfect line: {}", on_off(config.perfect_line)),
+        format!("Controls: {:?}", config.control_scheme),
+        practice_label(practice_level.0),
+        String::from("Reset data"),
+        String::from("Back"),
+    ]
+}
+
+pub fn spawn_settings_menu(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    practice_level: Res<PracticeLevel>,
+) {
+    spawn_menu(
+        &mut commands,
+        MenuScreen::Settings,
+        "Settings",
+        &[],
+        &settings_labels(&config, &practice_level),
+    );
+}
+
+/// Toggles take effect right away and are written back to the config file.
+/// The practice level cycles through every level and is only kept until the
+/// game closes, so records aren't quietly turned off on the next launch.
+pub fn settings_menu_actions(
+    mut commands: Commands,
+    selection: Res<MenuSelection>,
+    mut config: ResMut<GameConfig>,
+    mut practice_level: ResMut<PracticeLevel>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    match activated_item(&selection) {
+        // Time attack is a mode of its own, turning it off goes back to normal
+        Some(0) => {
+            let mode = if config.time_attack {
+                GameMode::Normal
+            } else {
+                GameMode::TimeAttack
+            };
+            modes::switch_mode(mode, &mut commands, &mut config);
+        }
+        Some(1) => {
+            config.difficulty_warning = !config.difficulty_warning;
+            config.store();
+        }
+        Some(2) => {
+            config.gap_guide = !config.gap_guide;
+            config.store();
+        }
+        Some(3) => {
+            config.perfect_line = !config.perfect_line;
+            config.store();
+        }
+        Some(4) => {
+            config.control_scheme = match config.control_scheme {
+                ControlScheme::Flap => ControlScheme::Glide,
+                ControlScheme::Glide => ControlScheme::Flap,
+            };
+            config.store();
+        }
+        Some(5) => practice_level.0 = (practice_level.0 + 1) % (DIFFICULTY_MAX_LEVEL + 1),
+        Some(6) => next_screen.set(MenuScreen::ResetData),
+        Some(7) => next_screen.set(MenuScreen::Main),
+        _ => {}
+    }
+}
+
+pub fn refresh_settings_labels(
+    config: Res<GameConfig>,
+    practice_level: Res<PracticeLevel>,
+    mut items: Query<(&MenuItem, &mut Text)>,
+) {
+    if !config.is_changed() && !practice_level.is_changed() {
+        return;
+    }
+
+    let labels = settings_labels(&config, &practice_level);
+    for (item, mut text) in items.iter_mut() {
+        if let Some(label) = labels.get(item.index) {
+            text.0.clone_from(label);
+        }
+    }
+}
+
+pub fn spawn_leaderboard_menu(
+    mut commands: Commands,
+    save: Res<SaveData>,
+    high_score: Res<HighScore>,
+) {
+    let daily = match save.daily {
+        Some(record) => format!("Last daily challenge: {}", record.score),
+        None => String::from("Last daily challenge: -"),
+    };
+    let lines = [
+        format!("Best score: {}", high_score.best),
+        format!("Time attack best: {}", high_score.time_attack_best),
+        daily,
+    ];
+
+    spawn_menu(
+        &mut commands,
+        MenuScreen::Leaderboard,
+        "Leaderboard",
+        &lines,
+        &[String::from("Back")],
+    );
+}
+
+pub fn spawn_daily_result_menu(mut commands: Commands, save: Res<SaveData>) {
+    let score = save.daily.map_or(0, |record| record.score);
+    let lines = [
+        format!("Today's score: {score}"),
+        String::from("Come back tomorrow for a new one."),
+    ];
+    spawn_menu(
+        &mut commands,
+        MenuScreen::DailyResult,
+        "Daily challenge",
+        &lines,
+        &[String::from("Back")],
+    );
+}
+
+pub fn daily_result_menu_actions(
+    selection: Res<MenuSelection>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    if activated_item(&selection) == Some(0) {
+        next_screen.set(MenuScreen::Main);
+    }
+}
+
+pub fn spawn_reset_data_menu(mut commands: Commands) {
+    let lines = [
+        String::from("Scores, coins and replays"),
+        String::from("will be gone for good."),
+        String::from("Settings are kept."),
+    ];
+    let items = RESET_DATA_MENU_ITEMS.map(String::from);
+    spawn_menu(
+        &mut commands,
+        MenuScreen::ResetData,
+        "Reset data?",
+        &lines,
+        &items,
+    );
+}
+
+/// The files go straight away and everything in memory starts over, as if
+/// the game had been launched for the first time.
+pub fn reset_data_menu_actions(
+    selection: Res<MenuSelection>,
+    mut save: ResMut<SaveData>,
+    mut high_score: ResMut<HighScore>,
+    mut session: ResMut<SessionStats>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    match activated_item(&selection) {
+        Some(0) => next_screen.set(MenuScreen::Settings),
+        Some(1) => {
+            save::reset_all_data(&mut save, &mut high_score);
+            *session = SessionStats::default();
+            info!("Saved data reset");
+            next_screen.set(MenuScreen::Settings);
+        }
+        _ => {}
+    }
+}
+
+pub fn spawn_pause_menu(mut commands: Commands) {
+    let items = PAUSE_MENU_ITEMS.map(String::from);
+    spawn_menu(&mut commands, GameState::Paused, "Paused", &[], &items);
+}
+
+/// Going back to the main menu abandons the run, a fresh one is set up once
+/// the menu is left.
+pub fn pause_menu_actions(
+    selection: Res<MenuSelection>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    match activated_item(&selection) {
+        Some(0) => next_game_state.set(GameState::Playing),
+        Some(1) => next_game_state.set(GameState::Menu),
+        Some(2) => {
+            exit.send(AppExit::Success);
+        }
+        _ => {}
+    }
+}
+
+pub fn return_to_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_game_state.set(GameState::Menu);
+    }
+}
+
+pub fn leaderboard_menu_actions(
+    selection: Res<MenuSelection>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    if activated_item(&selection) == Some(0) {
+        next_screen.set(MenuScreen::Main);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    const TODAY: u64 = 20_000;
+
+    /// The main menu as it's spawned with `daily` as the last daily
+    /// challenge played, while today's is the mode.
+    fn main_menu_lines(daily: Option<DailyRecord>) -> Vec<String> {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .insert_resource(GameAssets {
+                bird: Handle::default(),
+                pipe: Handle::default(),
+                logo: Handle::default(),
+                milestone_sound: None,
+                ambient_sounds: Vec::new(),
+                pipe_whoosh_sound: None,
+            })
+            .insert_resource(SaveData { daily, ..default() })
+            .insert_resource(DailyChallenge { day: TODAY });
+        app.world_mut().run_system_once(spawn_main_menu).unwrap();
+
+        let world = app.world_mut();
+        let mut texts = world.query_filtered::<&Text, Without<MenuItem>>();
+        texts.iter(world).map(|text| text.0.clone()).collect()
+    }
+
+    #[test]
+    fn main_menu_shows_a_finished_daily() {
+        let lines = main_menu_lines(Some(DailyRecord {
+            day: TODAY,
+            score: 42,
+        }));
+        assert!(lines.contains(&String::from("Today's daily challenge: 42")));
+    }
+
+    #[test]
+    fn main_menu_leaves_out_an_earlier_daily() {
+        let lines = main_menu_lines(Some(DailyRecord {
+            day: TODAY - 1,
+            score: 42,
+        }));
+        assert!(!lines.iter().any(|line| line.contains("daily")));
+    }
+}
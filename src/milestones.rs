@@ -0,0 +1,139 @@
+//! A short celebration whenever the score reaches one of the milestones in
+//! the config: a banner, a burst of confetti and the milestone sound, when
+//! one is set. Each milestone is celebrated once a run, the first time the
+//! score gets to it.
+
+use bevy::prelude::*;
+
+use crate::{
+    assets::GameAssets,
+    config::GameConfig,
+    particles::{ParticleRequest, PARTICLE_GRAVITY},
+    Lead, Score, PIXEL_RATIO,
+};
+
+const BANNER_LIFETIME: f32 = 1.2;
+/// The banner stays fully visible for this much of its life before fading.
+const BANNER_HOLD: f32 = 0.6;
+const BANNER_COLOR: Color = Color::srgb(1., 0.9, 0.3);
+
+const CONFETTI_COUNT: u32 = 24;
+/// Confetti is thrown upwards within this many radians either side of
+/// straight up.
+const CONFETTI_SPREAD: f32 = 1.;
+const CONFETTI_SPEED: f32 = PARTICLE_GRAVITY * 0.6;
+const CONFETTI_LIFETIME: f32 = 1.;
+const CONFETTI_SIZE: f32 = PIXEL_RATIO;
+const CONFETTI_COLORS: [Color; 4] = [
+    Color::srgb(1., 0.3, 0.3),
+    Color::srgb(0.3, 0.8, 1.),
+    Color::srgb(1., 0.9, 0.3),
+    Color::srgb(0.4, 1., 0.5),
+];
+
+#[derive(Component)]
+pub struct MilestoneBanner {
+    /// Seconds left before it has faded out.
+    pub lifetime: f32,
+}
+
+/// The highest milestone in `milestones` that `points` has reached and that
+/// is past `celebrated`, the last one already celebrated. Passing several
+/// in one go only celebrates the highest.
+pub fn next_milestone(milestones: &[u32], celebrated: u32, points: u32) -> Option<u32> {
+    milestones
+        .iter()
+        .copied()
+        .filter(|&milestone| milestone > celebrated && milestone <= points)
+        .max()
+}
+
+pub fn banner_text(milestone: u32) -> String {
+    format!("Nice! {milestone}!")
+}
+
+/// Confetti thrown up and out from `center`, alternating colors.
+pub fn confetti_burst(center: Vec2) -> impl Iterator<Item = ParticleRequest> {
+    (0..CONFETTI_COUNT).map(move |index| {
+        let spread = index as f32 / (CONFETTI_COUNT - 1) as f32 * 2. - 1.;
+        // Alternating speeds keep it from landing in a neat arc
+        let speed = CONFETTI_SPEED * if index.is_multiple_of(3) { 0.7 } else { 1. };
+        ParticleRequest {
+            position: center,
+            velocity: Vec2::from_angle(std::f32::consts::FRAC_PI_2 - spread * CONFETTI_SPREAD)
+                * speed,
+            color: CONFETTI_COLORS[index as usize % CONFETTI_COLORS.len()],
+            size: CONFETTI_SIZE,
+            lifetime: CONFETTI_LIFETIME,
+        }
+    })
+}
+
+pub fn celebrate_milestones(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    config: Res<GameConfig>,
+    game_assets: Res<GameAssets>,
+    bird_query: Query<&Transform, With<Lead>>,
+    banner_query: Query<Entity, With<MilestoneBanner>>,
+    mut particle_requests: EventWriter<ParticleRequest>,
+) {
+    let Some(milestone) = next_milestone(&config.milestones, score.milestone, score.points) else {
+        return;
+    };
+    score.milestone = milestone;
+
+    for entity in banner_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_banner(&mut commands, &banner_text(milestone));
+
+    if let Some(sound) = &game_assets.milestone_sound {
+        commands.spawn((AudioPlayer::new(sound.clone()), PlaybackSettings::DESPAWN));
+    }
+    // Confetti flying about is exactly what reduced motion asks to leave out
+    if !config.reduced_motion.enabled {
+        if let Ok(transform) = bird_query.get_single() {
+            particle_requests.send_batch(confetti_burst(transform.translation.truncate()));
+        }
+    }
+}
+
+/// Shows `text` across the top of the screen for a moment, the way
+/// milestones are announced.
+pub fn spawn_banner(commands: &mut Commands, text: &str) {
+    commands.spawn((
+        Text::new(text),
+        TextFont {
+            font_size: 40.,
+            ..Default::default()
+        },
+        TextColor(BANNER_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(22.),
+            width: Val::Percent(100.),
+            ..Default::default()
+        },
+        MilestoneBanner {
+            lifetime: BANNER_LIFETIME,
+        },
+    ));
+}
+
+pub fn fade_milestone_banners(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut banner_query: Query<(Entity, &mut MilestoneBanner, &mut TextColor)>,
+) {
+    for (entity, mut banner, mut color) in banner_query.iter_mut() {
+        banner.lifetime -= time.delta_secs();
+        if banner.lifetime <= 0. {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let fade = (banner.lifetime / (BANNER_LIFETIME - BANNER_HOLD)).min(1.);
+        color.0 = BANNER_COLOR.with_alpha(fade);
+    }
+}
@@ -0,0 +1,332 @@
+//! Game modes, each a set of rules picked from the mode screen. Everything a
+//! mode changes about a run is worked out here into its `ModeRules`, which
+//! gameplay reads instead of the config entries they come from. The rest of
+//! the config applies to every mode alike.
+//!
+//! Time attack and the daily challenge keep their own config flags, which
+//! selecting a mode sets, so older config files still start in the mode
+//! they asked for. Past picking the mode, gameplay goes by the rules.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{DifficultyCurve, GameConfig},
+    daily::{self, DailyChallenge},
+    gauntlet::PatternSource,
+    save::SaveData,
+    TimeAttack,
+};
+
+/// How the active mode plays, taken from the config it was picked with.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct ModeRules {
+    /// Multiplies gravity, for the bird and its flockmates alike.
+    pub gravity_scale: f32,
+    /// Added to every gap, in sprite pixels.
+    pub gap_bonus: f32,
+    /// Multiplies the scroll speed.
+    pub speed_scale: f32,
+    pub difficulty_curve: DifficultyCurve,
+    /// The difficulty steps up as the run goes on.
+    pub difficulty_ramps: bool,
+    /// Leaving through the floor, or the ceiling with gravity flipped, ends
+    /// the run. Otherwise the bird wraps around to the other edge.
+    pub lethal_edges: bool,
+    /// Multiplies the points of every pipe passed, slipstream included.
+    pub score_multiplier: u32,
+    /// Hit points the bird starts every run with, at least 1.
+    pub max_health: u32,
+    /// Seconds of invulnerability given on every spawn.
+    pub spawn_invulnerability: f32,
+    /// Whether coins can buy a continue.
+    pub continues: bool,
+    /// Runs count toward the best scores.
+    pub record_eligible: bool,
+    /// Seconds to score in before the run ends, `None` for runs that only
+    /// end in a crash.
+    pub time_limit: Option<f32>,
+}
+
+/// Zen plays a little gentler all round than normal.
+const ZEN_GRAVITY_SCALE: f32 = 0.85;
+const ZEN_GAP_BONUS: f32 = 3.;
+const ZEN_SPEED_SCALE: f32 = 0.85;
+/// Hardcore's pipes come at the bird that much faster.
+const HARDCORE_SPEED_SCALE: f32 = 1.15;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Normal,
+    /// A single hit ends the run, with no grace at the start and no
+    /// continues, and the pipes scroll faster.
+    Hardcore,
+    /// The edges wrap around and the pipes never close in or speed up.
+    /// Gravity is gentler, the gaps wider and the pipes slower. Runs aren't
+    /// recorded.
+    Zen,
+    TimeAttack,
+    Daily,
+    /// Pipes laid out by the pattern file named in the `gauntlet` entry.
+    Gauntlet,
+}
+
+impl GameMode {
+    /// In the order the mode screen lists them.
+    pub const ALL: [Self; 6] = [
+        Self::Normal,
+        Self::Hardcore,
+        Self::Zen,
+        Self::TimeAttack,
+        Self::Daily,
+        Self::Gauntlet,
+    ];
+
+    /// The mode `config` plays in. Configs from before modes could be
+    /// picked go by which flags they have set.
+    pub fn of(config: &GameConfig) -> Self {
+        if let Some(mode) = config.game_mode {
+            return mode;
+        }
+        if config.daily_challenge {
+            Self::Daily
+        } else if config.time_attack {
+            Self::TimeAttack
+        } else if config.gauntlet.is_some() {
+            Self::Gauntlet
+        } else {
+            Self::Normal
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Hardcore => "Hardcore",
+            Self::Zen => "Zen",
+            Self::TimeAttack => "Time attack",
+            Self::Daily => "Daily challenge",
+            Self::Gauntlet => "Gauntlet",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Normal => "The usual endless run",
+            Self::Hardcore => "Faster, one hit and it's over, no continues",
+            Self::Zen => "Gentler, no edges, no speeding up, no records",
+            Self::TimeAttack => "Score all you can before time runs out",
+            Self::Daily => "Everyone gets the same pipes, one go a day",
+            Self::Gauntlet => "Hand-made pipes from your pattern file",
+        }
+    }
+
+    /// Color of the mode's icon on the mode screen.
+    pub fn icon_color(self) -> Color {
+        match self {
+            Self::Normal => Color::srgb(0.45, 0.8, 0.35),
+            Self::Hardcore => Color::srgb(0.85, 0.25, 0.2),
+            Self::Zen => Color::srgb(0.45, 0.7, 0.95),
+            Self::TimeAttack => Color::srgb(0.95, 0.75, 0.2),
+            Self::Daily => Color::srgb(0.75, 0.45, 0.9),
+            Self::Gauntlet => Color::srgb(0.6, 0.6, 0.6),
+        }
+    }
+
+    /// Why the mode can't be played right now, `None` when it can.
+    pub fn unavailable_reason(self, config: &GameConfig, save: &SaveData) -> Option<&'static str> {
+        match self {
+            Self::Daily if !daily::attempt_available(save.daily.as_ref(), daily::current_day()) => {
+                Some("Already played today")
+            }
+            Self::Gauntlet if config.gauntlet.is_none() => {
+                Some("Needs a pattern file set in the config")
+            }
+            _ => None,
+        }
+    }
+
+    /// Sets the config flags that go with the mode.
+    pub fn configure(self, config: &mut GameConfig) {
+        config.game_mode = Some(self);
+        config.time_attack = self == Self::TimeAttack;
+        config.daily_challenge = self == Self::Daily;
+    }
+
+    /// The rules of the mode, starting from the ones `config` sets for every
+    /// mode.
+    pub fn rules(self, config: &GameConfig) -> ModeRules {
+        let rules = ModeRules {
+            gravity_scale: 1.,
+            gap_bonus: 0.,
+            speed_scale: 1.,
+            difficulty_curve: config.difficulty_curve,
+            difficulty_ramps: true,
+            lethal_edges: !config.wrap_vertical,
+            score_multiplier: 1,
+            max_health: config.max_health.max(1),
+            spawn_invulnerability: config.spawn_invulnerability,
+            continues: true,
+            record_eligible: true,
+            time_limit: None,
+        };
+        match self {
+            Self::Normal | Self::Daily | Self::Gauntlet => rules,
+            Self::TimeAttack => ModeRules {
+                time_limit: Some(config.time_attack_duration),
+                ..rules
+            },
+            Self::Hardcore => ModeRules {
+                speed_scale: HARDCORE_SPEED_SCALE,
+                max_health: 1,
+                spawn_invulnerability: 0.,
+                continues: false,
+                ..rules
+            },
+            Self::Zen => ModeRules {
+                gravity_scale: ZEN_GRAVITY_SCALE,
+                gap_bonus: ZEN_GAP_BONUS,
+                speed_scale: ZEN_SPEED_SCALE,
+                difficulty_ramps: false,
+                lethal_edges: false,
+                record_eligible: false,
+                ..rules
+            },
+        }
+    }
+
+    /// Where the gaps of the mode's pipes come from.
+    pub fn pattern_source(self, config: &GameConfig) -> PatternSource {
+        match self {
+            Self::Gauntlet => PatternSource::load(config.gauntlet.as_deref()),
+            _ => PatternSource::Random,
+        }
+    }
+}
+
+/// Switches the runs from now on over to `mode`, and remembers it for the
+/// next launch.
+pub fn switch_mode(mode: GameMode, commands: &mut Commands, config: &mut GameConfig) {
+    mode.configure(config);
+    let rules = mode.rules(config);
+    commands.insert_resource(rules);
+
+    match rules.time_limit {
+        Some(remaining) => commands.insert_resource(TimeAttack { remaining }),
+        None => commands.remove_resource::<TimeAttack>(),
+    }
+    if mode == GameMode::Daily {
+        commands.insert_resource(DailyChallenge {
+            day: daily::current_day(),
+        });
+    } else {
+        commands.remove_resource::<DailyChallenge>();
+    }
+    commands.insert_resource(mode.pattern_source(config));
+    config.store();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            max_health: 3,
+            spawn_invulnerability: 2.,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn normal_keeps_the_config_rules() {
+        let rules = GameMode::Normal.rules(&config());
+        assert_eq!(rules.max_health, 3);
+        assert_eq!(rules.spawn_invulnerability, 2.);
+        assert!(rules.difficulty_ramps);
+        assert!(rules.lethal_edges);
+        assert!(rules.continues);
+        assert!(rules.record_eligible);
+        assert_eq!(rules.time_limit, None);
+        assert_eq!(rules.gravity_scale, 1.);
+        assert_eq!(rules.gap_bonus, 0.);
+        assert_eq!(rules.speed_scale, 1.);
+    }
+
+    #[test]
+    fn hardcore_allows_one_hit_and_no_continues() {
+        let rules = GameMode::Hardcore.rules(&config());
+        assert!(rules.speed_scale > 1.);
+        assert_eq!(rules.max_health, 1);
+        assert_eq!(rules.spawn_invulnerability, 0.);
+        assert!(!rules.continues);
+        assert!(rules.lethal_edges);
+        assert!(rules.record_eligible);
+    }
+
+    #[test]
+    fn zen_wraps_and_is_not_recorded() {
+        let rules = GameMode::Zen.rules(&config());
+        assert!(rules.gravity_scale < 1.);
+        assert!(rules.gap_bonus > 0.);
+        assert!(rules.speed_scale < 1.);
+        assert!(!rules.difficulty_ramps);
+        assert!(!rules.lethal_edges);
+        assert!(!rules.record_eligible);
+        assert!(rules.continues);
+    }
+
+    #[test]
+    fn time_attack_plays_like_normal_against_the_clock() {
+        let config = config();
+        assert_eq!(
+            GameMode::TimeAttack.rules(&config),
+            ModeRules {
+                time_limit: Some(config.time_attack_duration),
+                ..GameMode::Normal.rules(&config)
+            }
+        );
+        assert!(GameMode::TimeAttack.rules(&config).record_eligible);
+    }
+
+    #[test]
+    fn daily_plays_like_normal() {
+        assert_eq!(
+            GameMode::Daily.rules(&config()),
+            GameMode::Normal.rules(&config())
+        );
+        assert!(GameMode::Daily.rules(&config()).record_eligible);
+    }
+
+    #[test]
+    fn gauntlet_plays_like_normal() {
+        assert_eq!(
+            GameMode::Gauntlet.rules(&config()),
+            GameMode::Normal.rules(&config())
+        );
+        assert!(GameMode::Gauntlet.rules(&config()).record_eligible);
+    }
+
+    #[test]
+    fn at_least_one_hit_point() {
+        let config = GameConfig {
+            max_health: 0,
+            ..config()
+        };
+        for mode in GameMode::ALL {
+            assert_eq!(mode.rules(&config).max_health, 1, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn configured_mode_is_read_back() {
+        for mode in GameMode::ALL {
+            let mut config = config();
+            mode.configure(&mut config);
+            assert_eq!(GameMode::of(&config), mode);
+            assert_eq!(config.time_attack, mode == GameMode::TimeAttack);
+            assert_eq!(config.daily_challenge, mode == GameMode::Daily);
+        }
+    }
+}
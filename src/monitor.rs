@@ -0,0 +1,38 @@
+use bevy::{
+    prelude::*,
+    window::{Monitor, PrimaryWindow, WindowPosition},
+};
+
+/// Recenters the window on the primary monitor when monitors come or go and
+/// its center no longer lies on any of them. A window the player moved
+/// somewhere still visible is left alone.
+pub fn keep_window_on_screen(
+    added_monitors: Query<(), Added<Monitor>>,
+    mut removed_monitors: RemovedComponents<Monitor>,
+    monitor_query: Query<&Monitor>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let removed = removed_monitors.read().count() > 0;
+    if added_monitors.is_empty() && !removed {
+        return;
+    }
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+    // Until it's been moved the window is still centered by the platform
+    let WindowPosition::At(position) = window.position else {
+        return;
+    };
+
+    let center = position + window.physical_size().as_ivec2() / 2;
+    let on_screen = monitor_query.iter().any(|monitor| {
+        IRect::from_corners(
+            monitor.physical_position,
+            monitor.physical_position + monitor.physical_size().as_ivec2(),
+        )
+        .contains(center)
+    });
+    if !on_screen {
+        window.position = WindowPosition::Centered(MonitorSelection::Primary);
+    }
+}
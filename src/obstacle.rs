@@ -0,0 +1,943 @@
+//! The pipes: a pool of pairs scrolled past the bird and recycled ahead of
+//! it, the gaps they're laid out with and what counts as flying into one.
+//! `detect_pipe_passes` tells of every pair the bird gets past with
+//! `PipePassed`.
+
+use std::collections::VecDeque;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::Rng;
+
+use crate::{
+    bird::{Bird, Lead},
+    config::{GameConfig, GapPattern, HitboxShape, PipeTier},
+    gap_telegraph, gauntlet, layers, Difficulty, GameManager, GameRng, GameState, RunSet, Score,
+    MAX_PIPE_SPACING_JITTER, OBSTACLE_GAP_SIZE, OBSTACLE_HEIGHT, OBSTACLE_SPACING,
+    OBSTACLE_VERTICAL_OFFSET, OBSTACLE_WIDTH, PIPE_SPRITE_WIDTH, PIXEL_RATIO,
+};
+
+const FINISH_LINE_WIDTH: f32 = 4. * PIXEL_RATIO;
+const FINISH_LINE_COLOR: Color = Color::srgba(1., 1., 1., 0.8);
+/// Demo pipes behind the menu scroll this much slower than in a run.
+const DEMO_PIPE_SPEED_SCALE: f32 = 0.4;
+/// Furthest a gap moves from the previous one with `GapPattern::Smooth`.
+const SMOOTH_GAP_MAX_DELTA: f32 = 10.;
+/// Height between neighbouring gaps with `GapPattern::Staircase`, in sprite
+/// pixels.
+const STAIRCASE_STEP: f32 = 8.;
+/// How far toward the edge of their range zigzag gaps go.
+const ZIGZAG_AMPLITUDE: f32 = 0.7;
+
+/// The warning only darkens the pipes so it reads the same whatever hue
+/// they're drawn in.
+const DIFFICULTY_WARNING_BRIGHTNESS: f32 = 0.7;
+/// Tint of the pipes around a gap that flips gravity.
+const GRAVITY_FLIP_PIPE_COLOR: Color = Color::srgb(0.8, 0.5, 1.);
+
+/// The pipes: scrolling and scoring them in their place in `RunSet`, and the
+/// systems that only change how they look. Reads the config, so the app
+/// needs one before this is added.
+pub struct ObstaclePlugin;
+
+impl Plugin for ObstaclePlugin {
+    fn build(&self, app: &mut App) {
+        let demo_pipes = app.world().resource::<GameConfig>().demo_pipes;
+
+        app.add_event::<PipePassed>()
+            .add_systems(
+                Update,
+                (
+                    update_obsacles,
+                    gap_telegraph::narrow_visible_gaps,
+                    update_finish_line,
+                    detect_pipe_passes,
+                )
+                    .chain()
+                    .in_set(RunSet::Pipes),
+            )
+            .add_systems(Update, fit_pipe_sprites);
+
+        // Only the scrolling and recycling run, nothing there collides or
+        // scores. Starting a run lays the pipes out afresh.
+        if demo_pipes {
+            app.add_systems(Update, update_obsacles.run_if(in_state(GameState::Menu)));
+        }
+    }
+}
+
+/// Offset from its regular place, in world units, of a pair rolling `roll`
+/// between 0 and 1 with spacing jitter `jitter`. Each pair only moves up to
+/// half the jitter either way, so neighbours end up between
+/// `OBSTACLE_SPACING - jitter` and `OBSTACLE_SPACING + jitter` apart and the
+/// pairs never drift off their regular places over a run.
+pub fn spacing_offset(roll: f32, jitter: f32) -> f32 {
+    let jitter = jitter.clamp(0., MAX_PIPE_SPACING_JITTER);
+    (roll.clamp(0., 1.) - 0.5) * jitter * PIXEL_RATIO
+}
+
+/// Color of the highest of `tiers` that `level` has reached, white for none.
+pub fn tier_color(tiers: &[PipeTier], level: u32) -> Color {
+    tiers
+        .iter()
+        .filter(|tier| tier.level <= level)
+        .max_by_key(|tier| tier.level)
+        .map_or(Color::WHITE, |tier| Color::srgb_from_array(tier.color))
+}
+
+/// Pipe pairs as `[top, bottom]`, ordered from the one nearest the bird to
+/// the furthest ahead so collision and scoring only look at the front.
+#[derive(Resource, Default, Clone)]
+pub struct PipeQueue(pub VecDeque<[Entity; 2]>);
+
+/// The pipe pairs and the finish line are spawned once and moved back into
+/// place for every run, rather than being despawned and spawned again. The
+/// queue always holds the whole pool.
+#[derive(SystemParam)]
+#[allow(clippy::type_complexity)]
+pub struct PipeLayout<'w, 's> {
+    pipe_queue: Res<'w, PipeQueue>,
+    pattern_source: ResMut<'w, gauntlet::PatternSource>,
+    pipe_query: Query<
+        'w,
+        's,
+        (
+            &'static mut Obstacle,
+            &'static mut Transform,
+            &'static mut Sprite,
+            &'static mut Visibility,
+        ),
+    >,
+    finish_line_query: Query<
+        'w,
+        's,
+        (&'static mut Transform, &'static mut Visibility),
+        (With<FinishLine>, Without<Obstacle>, Without<Bird>),
+    >,
+}
+
+impl PipeLayout<'_, '_> {
+    /// Lines the pairs up in queue order from the first pipe of a run with
+    /// gaps of `gap_size`, tinted for difficulty `level`. Pairs that would
+    /// come after the finish line are hidden.
+    pub fn lay_out(
+        &mut self,
+        rand: &mut impl Rng,
+        game_manager: &GameManager,
+        gap_size: f32,
+        level: u32,
+    ) {
+        self.pattern_source.restart();
+        let color = game_manager.tier_color(level);
+        // The newest offset first
+        let mut offsets = [0.; 2];
+        for (pair_number, &pair) in (0..).zip(self.pipe_queue.0.iter()) {
+            let shown = !game_manager.past_finish_line(pair_number);
+            if shown {
+                let offset = next_gap_offset(
+                    rand,
+                    &mut self.pattern_source,
+                    game_manager.window_dimentions.y,
+                    gap_size,
+                    game_manager.gap_pattern,
+                    offsets,
+                );
+                offsets = [offset, offsets[0]];
+            }
+            let y_offset = offsets[0];
+            let spacing_offset = if shown {
+                game_manager.roll_spacing_offset(rand)
+            } else {
+                0.
+            };
+
+            let x_pos =
+                pair_x_position(game_manager, pair_number) + spacing_offset * game_manager.facing;
+            let mut pipes = self.pipe_query.iter_many_mut(pair);
+            while let Some((mut obstacle, mut transform, mut sprite, mut visibility)) =
+                pipes.fetch_next()
+            {
+                transform.translation = Vec3::new(
+                    x_pos,
+                    get_centered_pipe_position(gap_size) * obstacle.pipe_direction + y_offset,
+                    layers::PIPE_Z,
+                );
+                sprite.color = color;
+                obstacle.pair_number = pair_number;
+                obstacle.flips_gravity = false;
+                obstacle.spacing_offset = spacing_offset;
+                *visibility = pipe_visibility(shown);
+            }
+        }
+
+        // Stands where the pair after the last one would have been
+        for (mut transform, mut visibility) in self.finish_line_query.iter_mut() {
+            if let Some(finish_line) = game_manager.finish_line {
+                transform.translation.x = pair_x_position(game_manager, finish_line);
+            }
+            *visibility = pipe_visibility(game_manager.finish_line.is_some());
+        }
+    }
+}
+
+fn pipe_visibility(shown: bool) -> Visibility {
+    if shown {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+/// Sent by `detect_pipe_passes` once for each pair of pipes the bird gets
+/// past, which `update_score` scores.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PipePassed {
+    pub pair: [Entity; 2],
+    pub pair_number: u32,
+}
+
+/// Where the pipes end in a run with a finish line, scrolling in with them.
+#[derive(Component)]
+pub struct FinishLine;
+
+#[derive(Component, Clone)]
+pub struct Obstacle {
+    pub pipe_direction: f32,
+    /// Place of the pipe's pair in the run, counting up from the first pair
+    /// and carried on each time it's recycled. Both pipes share it.
+    pub pair_number: u32,
+    /// Flying through this pair's gap flips gravity.
+    pub flips_gravity: bool,
+    /// Length of the pipe in world units. Its sprite is sized to this and
+    /// collisions use it, so the two always match.
+    pub height: f32,
+    /// How far the pair was moved from its regular place by spacing jitter,
+    /// in world units toward the bird's back.
+    pub spacing_offset: f32,
+}
+
+/// Lays the pooled pipes out for a run at the starting difficulty, so they
+/// already stand in place behind the menu.
+pub fn lay_out_pipes(
+    mut pipe_layout: PipeLayout,
+    game_manager: Res<GameManager>,
+    mut rng: ResMut<GameRng>,
+) {
+    let gap_size = game_manager.effective_gap_size(OBSTACLE_GAP_SIZE);
+    pipe_layout.lay_out(&mut rng.0, &game_manager, gap_size, 0);
+}
+
+/// The pipe the bird overlaps at `position`, if any.
+pub fn hit_pipe(
+    position: Vec3,
+    pipe_queue: &PipeQueue,
+    obstacle_query: &Query<(&Obstacle, &Transform)>,
+    game_manager: &GameManager,
+) -> Option<Entity> {
+    let bird_growth = game_manager.bird_growth();
+    // Only the pairs at the front of the queue can be level with the bird;
+    // everything behind them is further ahead.
+    for pair in pipe_queue.0.iter() {
+        let Ok((_, top_transform)) = obstacle_query.get(pair[0]) else {
+            continue;
+        };
+        if (top_transform.translation.x - position.x) * game_manager.facing
+            > OBSTACLE_WIDTH * PIXEL_RATIO / 2. + bird_growth.x
+        {
+            break;
+        }
+
+        let hit = pair.iter().copied().find(|&pipe| {
+            obstacle_query
+                .get(pipe)
+                .is_ok_and(|(obstacle, pipe_transform)| {
+                    bird_hits_pipe(
+                        game_manager,
+                        pipe_transform.translation,
+                        obstacle.height,
+                        position,
+                    )
+                })
+        });
+        if hit.is_some() {
+            return hit;
+        }
+    }
+    None
+}
+
+/// Tells of every pair of pipes that's gone past the bird since the last
+/// frame.
+pub fn detect_pipe_passes(
+    score: Res<Score>,
+    game_manager: Res<GameManager>,
+    pipe_queue: Res<PipeQueue>,
+    bird_query: Query<&Transform, With<Lead>>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+    mut passes: EventWriter<PipePassed>,
+) {
+    let Ok(bird_transform) = bird_query.get_single() else {
+        return;
+    };
+
+    // Only the top pipe of each pair is counted so a pair scores once
+    for pair in pipe_queue.0.iter() {
+        let Ok((obstacle, transform)) = obstacle_query.get(pair[0]) else {
+            continue;
+        };
+        if score.has_scored(obstacle.pair_number) {
+            continue;
+        }
+        if transform.translation.x * game_manager.facing + OBSTACLE_WIDTH * PIXEL_RATIO / 2.
+            >= bird_transform.translation.x * game_manager.facing
+        {
+            break;
+        }
+        passes.send(PipePassed {
+            pair: *pair,
+            pair_number: obstacle.pair_number,
+        });
+    }
+}
+
+/// Whether the bird's center is inside the rectangle of a pipe `pipe_height`
+/// long, grown by `bird_growth` on each side for a bird bigger than the
+/// usual size.
+pub fn pipe_overlaps_bird(
+    pipe_translation: Vec3,
+    pipe_height: f32,
+    bird_translation: Vec3,
+    bird_growth: Vec2,
+) -> bool {
+    (pipe_translation.y - bird_translation.y).abs() < pipe_height / 2. + bird_growth.y
+        && (pipe_translation.x - bird_translation.x).abs()
+            < OBSTACLE_WIDTH * PIXEL_RATIO / 2. + bird_growth.x
+}
+
+/// Half the size of a pipe `pipe_height` long as drawn, without the room
+/// `pipe_overlaps_bird` adds for the bird.
+pub fn pipe_half_size(pipe_height: f32) -> Vec2 {
+    Vec2::new(PIPE_SPRITE_WIDTH / 2. * PIXEL_RATIO, pipe_height / 2.)
+}
+
+/// Whether a round bird of `bird_radius` overlaps the pipe, going by the
+/// point on the pipe closest to the bird's center.
+pub fn pipe_overlaps_round_bird(
+    pipe_translation: Vec3,
+    pipe_height: f32,
+    bird_translation: Vec3,
+    bird_radius: f32,
+) -> bool {
+    let half_size = pipe_half_size(pipe_height);
+    let offset = (bird_translation - pipe_translation).truncate();
+    let closest = offset.clamp(-half_size, half_size);
+    offset.distance_squared(closest) < bird_radius * bird_radius
+}
+
+/// Whether the bird at `bird_translation` overlaps the pipe, with whichever
+/// hitbox shape is configured.
+fn bird_hits_pipe(
+    game_manager: &GameManager,
+    pipe_translation: Vec3,
+    pipe_height: f32,
+    bird_translation: Vec3,
+) -> bool {
+    match game_manager.hitbox_shape {
+        HitboxShape::Rect => pipe_overlaps_bird(
+            pipe_translation,
+            pipe_height,
+            bird_translation,
+            game_manager.bird_growth(),
+        ),
+        HitboxShape::Circle => pipe_overlaps_round_bird(
+            pipe_translation,
+            pipe_height,
+            bird_translation,
+            game_manager.bird_radius(),
+        ),
+    }
+}
+
+/// Height of the middle of the gap between a pair's pipes. They sit evenly
+/// around it, so this holds whatever the gap size was when they were placed.
+pub fn gap_center(top_translation: Vec3, bottom_translation: Vec3) -> f32 {
+    (top_translation.y + bottom_translation.y) / 2.
+}
+
+pub fn get_centered_pipe_position(gap_size: f32) -> f32 {
+    (OBSTACLE_HEIGHT / 2. + gap_size) * PIXEL_RATIO
+}
+
+/// `tier_color` tinted for a pair that flips gravity and darkened for the
+/// difficulty warning.
+pub fn pipe_color(tier_color: Color, warning: bool, flips_gravity: bool) -> Color {
+    let color = if flips_gravity {
+        GRAVITY_FLIP_PIPE_COLOR
+    } else {
+        tier_color
+    };
+    if warning {
+        color.with_luminance(color.luminance() * DIFFICULTY_WARNING_BRIGHTNESS)
+    } else {
+        color
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_obsacles(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    game_manager: Res<GameManager>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    mut obstacle_query: Query<(&mut Obstacle, &mut Transform, &mut Sprite, &mut Visibility)>,
+    mut pipe_queue: ResMut<PipeQueue>,
+    mut rng: ResMut<GameRng>,
+    mut pattern_source: ResMut<gauntlet::PatternSource>,
+) {
+    let warning = config.difficulty_warning && difficulty.level_up_imminent();
+    // Pairs recycled just before a level up already show its tier, they'll
+    // reach the bird after it
+    let tier_color = game_manager.tier_color(if difficulty.level_up_imminent() {
+        difficulty.level() + 1
+    } else {
+        difficulty.level()
+    });
+    let gap_size = game_manager.effective_gap_size(difficulty.gap_size());
+    let speed_scale = if *state.get() == GameState::Menu {
+        DEMO_PIPE_SPEED_SCALE
+    } else {
+        1.
+    };
+    for (_, mut transform, _, _) in obstacle_query.iter_mut() {
+        transform.translation.x -=
+            time.delta_secs() * difficulty.scroll_speed() * speed_scale * game_manager.facing;
+    }
+
+    // Pairs recycle once they leave through the edge behind the bird, moving
+    // from the front of the queue to the back.
+    while let Some(&pair) = pipe_queue.0.front() {
+        let Ok((top_obstacle, top_transform, _, _)) = obstacle_query.get(pair[0]) else {
+            break;
+        };
+        if top_transform.translation.x * game_manager.facing + OBSTACLE_WIDTH * PIXEL_RATIO / 2.
+            >= -game_manager.window_dimentions.x / 2.
+        {
+            break;
+        }
+
+        let pair_number = top_obstacle.pair_number + game_manager.pipe_pairs;
+        let recycle_distance =
+            game_manager.pipe_pairs as f32 * OBSTACLE_SPACING * PIXEL_RATIO * game_manager.facing;
+
+        // There are no pipes past the finish line, pairs that would go there
+        // only keep their place in the queue until the run is over
+        if game_manager.past_finish_line(pair_number) {
+            let mut pipes = obstacle_query.iter_many_mut(pair);
+            while let Some((mut obstacle, mut transform, _, mut visibility)) = pipes.fetch_next() {
+                transform.translation.x +=
+                    recycle_distance - obstacle.spacing_offset * game_manager.facing;
+                obstacle.pair_number = pair_number;
+                obstacle.spacing_offset = 0.;
+                *visibility = Visibility::Hidden;
+            }
+            pipe_queue.0.rotate_left(1);
+            continue;
+        }
+
+        // The pair at the back is the last one placed
+        let queue_length = pipe_queue.0.len();
+        let previous_offsets = [1, 2].map(|from_back| {
+            queue_length
+                .checked_sub(from_back)
+                .and_then(|index| obstacle_query.get_many(pipe_queue.0[index]).ok())
+                .map_or(0., |[(_, top, _, _), (_, bottom, _, _)]| {
+                    gap_center(top.translation, bottom.translation)
+                })
+        });
+        let y_offset = next_gap_offset(
+            &mut rng.0,
+            &mut pattern_source,
+            game_manager.window_dimentions.y,
+            gap_size,
+            game_manager.gap_pattern,
+            previous_offsets,
+        );
+        // Only rolled for when enabled so seeded runs without flips keep
+        // the same pipes
+        let flips_gravity = config.gravity_flip_chance > 0.
+            && rng.0.gen_bool(config.gravity_flip_chance.min(1.) as f64);
+        let spacing_offset = game_manager.roll_spacing_offset(&mut rng.0);
+        let mut pipes = obstacle_query.iter_many_mut(pair);
+        while let Some((mut obstacle, mut transform, mut sprite, _)) = pipes.fetch_next() {
+            transform.translation.x +=
+                recycle_distance + (spacing_offset - obstacle.spacing_offset) * game_manager.facing;
+            obstacle.spacing_offset = spacing_offset;
+            transform.translation.y =
+                get_centered_pipe_position(gap_size) * obstacle.pipe_direction + y_offset;
+            sprite.color = pipe_color(tier_color, warning, flips_gravity);
+            obstacle.pair_number = pair_number;
+            obstacle.flips_gravity = flips_gravity;
+        }
+        pipe_queue.0.rotate_left(1);
+    }
+}
+
+/// Spawns the pool of pipe pairs and the finish line, hidden until
+/// `PipeLayout::lay_out` puts them in place.
+pub fn spawn_pipe_pool(commands: &mut Commands, game_manager: &GameManager, cap_height: f32) {
+    let mut pipe_queue = PipeQueue::default();
+    for _ in 0..game_manager.pipe_pairs {
+        let pair = [1., -1.].map(|pipe_direction| {
+            spawn_obstacle(
+                pipe_direction,
+                commands,
+                &game_manager.pipe_image,
+                cap_height,
+            )
+        });
+        pipe_queue.0.push_back(pair);
+    }
+    commands.insert_resource(pipe_queue);
+
+    commands.spawn((
+        Sprite {
+            color: FINISH_LINE_COLOR,
+            custom_size: Some(Vec2::new(
+                FINISH_LINE_WIDTH,
+                game_manager.window_dimentions.y,
+            )),
+            ..Default::default()
+        },
+        Transform::from_xyz(0., 0., layers::FINISH_LINE_Z),
+        Visibility::Hidden,
+        FinishLine,
+    ));
+}
+
+/// A pipe drawn as a cap `cap_height` pixels tall on the end of a body
+/// stretched to make up the rest of its length, upside down for the top
+/// pipe so the cap faces the gap. The sprite gets its size
+/// from `fit_pipe_sprites` once the image is in.
+fn spawn_obstacle(
+    pipe_direction: f32,
+    commands: &mut Commands,
+    image: &Handle<Image>,
+    cap_height: f32,
+) -> Entity {
+    commands
+        .spawn((
+            Sprite {
+                image: image.clone(),
+                image_mode: SpriteImageMode::Sliced(TextureSlicer {
+                    border: BorderRect {
+                        top: cap_height.max(0.),
+                        ..BorderRect::ZERO
+                    },
+                    max_corner_scale: 1.,
+                    ..Default::default()
+                }),
+                // Flipping the sprite rather than scaling it by -1 keeps the
+                // cap slice the right way up relative to the gap
+                flip_y: pipe_direction > 0.,
+                ..Default::default()
+            },
+            Transform::from_xyz(0., 0., layers::PIPE_Z).with_scale(Vec3::splat(PIXEL_RATIO)),
+            Visibility::Hidden,
+            Obstacle {
+                pipe_direction,
+                pair_number: 0,
+                flips_gravity: false,
+                height: OBSTACLE_HEIGHT * PIXEL_RATIO,
+                spacing_offset: 0.,
+            },
+        ))
+        .id()
+}
+
+/// Sizes each pipe's sprite to its length, keeping the image's own width.
+fn fit_pipe_sprites(images: Res<Assets<Image>>, mut pipe_query: Query<(&Obstacle, &mut Sprite)>) {
+    for (obstacle, mut sprite) in pipe_query.iter_mut() {
+        let Some(image) = images.get(&sprite.image) else {
+            continue;
+        };
+        let size = Vec2::new(image.width() as f32, obstacle.height / PIXEL_RATIO);
+        if sprite.custom_size != Some(size) {
+            sprite.custom_size = Some(size);
+        }
+    }
+}
+
+/// Where the pair numbered `pair_number` starts out. Pairs are recycled at
+/// the same spacing, which keeps the queue in order along the scroll
+/// direction.
+fn pair_x_position(game_manager: &GameManager, pair_number: u32) -> f32 {
+    game_manager.bird_start.x
+        + (game_manager.first_pipe_distance + OBSTACLE_SPACING * PIXEL_RATIO * pair_number as f32)
+            * game_manager.facing
+}
+
+/// Scrolls the finish line along with the pipes, ending the run in a win
+/// once the bird reaches it.
+pub fn update_finish_line(
+    time: Res<Time>,
+    game_manager: Res<GameManager>,
+    difficulty: Res<Difficulty>,
+    mut finish_line_query: Query<&mut Transform, (With<FinishLine>, Without<Lead>)>,
+    bird_query: Query<&Transform, With<Lead>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // Stays hidden out of the way in endless runs
+    if game_manager.finish_line.is_none() {
+        return;
+    }
+    let Ok(bird_transform) = bird_query.get_single() else {
+        return;
+    };
+    for mut transform in finish_line_query.iter_mut() {
+        transform.translation.x -=
+            time.delta_secs() * difficulty.scroll_speed() * game_manager.facing;
+        if transform.translation.x * game_manager.facing
+            <= bird_transform.translation.x * game_manager.facing
+        {
+            next_state.set(GameState::Victory);
+        }
+    }
+}
+
+/// Furthest the gap center may drift from the middle of the screen while the
+/// whole gap stays visible, capped by `OBSTACLE_VERTICAL_OFFSET`.
+fn max_gap_offset(window_height: f32, gap_size: f32) -> f32 {
+    let gap_half_height = gap_size * PIXEL_RATIO;
+    (window_height / 2. - gap_half_height).clamp(0., OBSTACLE_VERTICAL_OFFSET * PIXEL_RATIO)
+}
+
+/// Offset of the next gap from the middle of the screen, taken from the
+/// scripted pattern while there is one.
+fn next_gap_offset(
+    rand: &mut impl Rng,
+    pattern_source: &mut gauntlet::PatternSource,
+    window_height: f32,
+    gap_size: f32,
+    pattern: GapPattern,
+    previous_offsets: [f32; 2],
+) -> f32 {
+    match pattern_source.next_offset() {
+        // Checked again in case the window is too short for what the
+        // pattern file allowed
+        Some(offset) => {
+            let max_offset = max_gap_offset(window_height, gap_size);
+            offset.clamp(-max_offset, max_offset)
+        }
+        None => generate_offset(rand, window_height, gap_size, pattern, previous_offsets),
+    }
+}
+
+/// Offset of the next gap from the middle of the screen. `previous_offsets`
+/// are those of the two pairs placed just before it, newest first, which
+/// smooth patterns stay close to and authored ones carry on from. Both are
+/// 0 at the start of a run, where authored patterns roll which way to go.
+pub fn generate_offset(
+    rand: &mut impl Rng,
+    window_height: f32,
+    gap_size: f32,
+    pattern: GapPattern,
+    previous_offsets: [f32; 2],
+) -> f32 {
+    let max_offset = max_gap_offset(window_height, gap_size);
+    let [previous, before_previous] = previous_offsets;
+    match pattern {
+        GapPattern::Uniform => rand.gen_range(-max_offset..=max_offset),
+        GapPattern::Smooth => {
+            let (low, high) = smooth_offset_range(max_offset, previous);
+            rand.gen_range(low..=high)
+        }
+        GapPattern::Fixed => 0.,
+        GapPattern::Staircase => {
+            let direction = if previous == before_previous {
+                random_direction(rand)
+            } else {
+                (previous - before_previous).signum()
+            };
+            staircase_offset(previous, direction, max_offset).0
+        }
+        GapPattern::Zigzag => {
+            let side = if previous == 0. {
+                random_direction(rand)
+            } else {
+                -previous.signum()
+            };
+            zigzag_offset(side, max_offset)
+        }
+    }
+}
+
+fn random_direction(rand: &mut impl Rng) -> f32 {
+    if rand.gen_bool(0.5) {
+        1.
+    } else {
+        -1.
+    }
+}
+
+/// Offset a staircase puts after `previous` heading in `direction`, 1 up or
+/// -1 down, and which way it heads after that. A step that would leave the
+/// `max_offset` bounds turns back instead.
+pub fn staircase_offset(previous: f32, direction: f32, max_offset: f32) -> (f32, f32) {
+    let previous = previous.clamp(-max_offset, max_offset);
+    let step = STAIRCASE_STEP * PIXEL_RATIO * direction;
+    if (previous + step).abs() <= max_offset {
+        (previous + step, direction)
+    } else {
+        ((previous - step).clamp(-max_offset, max_offset), -direction)
+    }
+}
+
+/// Offset of a zigzag gap on `side`, 1 for high and -1 for low.
+pub fn zigzag_offset(side: f32, max_offset: f32) -> f32 {
+    side.signum() * ZIGZAG_AMPLITUDE * max_offset
+}
+
+/// Offsets the smooth pattern may pick after `previous_offset`, which is
+/// pulled back in range first in case the allowed drift shrank since.
+pub fn smooth_offset_range(max_offset: f32, previous_offset: f32) -> (f32, f32) {
+    let previous_offset = previous_offset.clamp(-max_offset, max_offset);
+    let max_delta = SMOOTH_GAP_MAX_DELTA * PIXEL_RATIO;
+    (
+        (previous_offset - max_delta).max(-max_offset),
+        (previous_offset + max_delta).min(max_offset),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::{BIRD_RADIUS, MIN_PIPE_SPACING};
+
+    const WINDOW_SIZE: Vec2 = Vec2::new(512., 512.);
+
+    const PATTERNS: [GapPattern; 5] = [
+        GapPattern::Uniform,
+        GapPattern::Smooth,
+        GapPattern::Fixed,
+        GapPattern::Staircase,
+        GapPattern::Zigzag,
+    ];
+
+    #[test]
+    fn generated_gaps_stay_on_screen() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for window_height in [256., 300., 512., 1024.] {
+            for gap_size in [10., 15., 25.] {
+                for pattern in PATTERNS {
+                    let mut previous_offsets = [0.; 2];
+                    for _ in 0..200 {
+                        let offset = generate_offset(
+                            &mut rng,
+                            window_height,
+                            gap_size,
+                            pattern,
+                            previous_offsets,
+                        );
+                        let gap_half_height = gap_size * PIXEL_RATIO;
+                        assert!(
+                            offset.abs() + gap_half_height <= window_height / 2. + 1e-3,
+                            "{pattern:?} put a {gap_size} gap at {offset} in a {window_height} window"
+                        );
+                        previous_offsets = [offset, previous_offsets[0]];
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gaps_taller_than_the_window_stay_centered() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for pattern in PATTERNS {
+            let offset = generate_offset(&mut rng, 160., 25., pattern, [0.; 2]);
+            assert_eq!(offset, 0., "{pattern:?}");
+        }
+    }
+
+    /// Gap offsets of `count` pairs in a row as the spawner places them.
+    fn offsets(rng: &mut StdRng, pattern: GapPattern, count: usize) -> Vec<f32> {
+        let mut previous_offsets = [0.; 2];
+        (0..count)
+            .map(|_| {
+                let offset = generate_offset(rng, WINDOW_SIZE.y, 15., pattern, previous_offsets);
+                previous_offsets = [offset, previous_offsets[0]];
+                offset
+            })
+            .collect()
+    }
+
+    #[test]
+    fn staircase_climbs_steadily_and_turns_at_the_edges() {
+        let max_offset = max_gap_offset(WINDOW_SIZE.y, 15.);
+        let step = STAIRCASE_STEP * PIXEL_RATIO;
+        for seed in 0..4 {
+            let offsets = offsets(&mut StdRng::seed_from_u64(seed), GapPattern::Staircase, 100);
+            let mut turns = 0;
+            for steps in offsets.windows(3) {
+                let (first, second) = (steps[1] - steps[0], steps[2] - steps[1]);
+                assert!(first.abs() <= step + 1e-3 && second.abs() <= step + 1e-3);
+                if first.signum() != second.signum() {
+                    // Only a step that would have left the screen turns back
+                    assert!(
+                        (steps[1] + first).abs() > max_offset - 1e-3,
+                        "turned at {} in {offsets:?}",
+                        steps[1]
+                    );
+                    turns += 1;
+                }
+            }
+            assert!(turns > 0, "never reached an edge in {offsets:?}");
+        }
+    }
+
+    #[test]
+    fn smooth_gaps_stay_close_to_the_previous_one() {
+        let max_delta = SMOOTH_GAP_MAX_DELTA * PIXEL_RATIO;
+        for seed in 0..4 {
+            let offsets = offsets(&mut StdRng::seed_from_u64(seed), GapPattern::Smooth, 500);
+            assert!(offsets[0].abs() <= max_delta + 1e-3);
+            for pair in offsets.windows(2) {
+                assert!(
+                    (pair[1] - pair[0]).abs() <= max_delta + 1e-3,
+                    "jumped from {} to {}",
+                    pair[0],
+                    pair[1]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_range_pulls_an_out_of_range_gap_back() {
+        let max_offset = max_gap_offset(WINDOW_SIZE.y, 15.);
+        let (low, high) = smooth_offset_range(max_offset, max_offset * 3.);
+        assert_eq!(high, max_offset);
+        assert_eq!(low, max_offset - SMOOTH_GAP_MAX_DELTA * PIXEL_RATIO);
+    }
+
+    #[test]
+    fn jittered_spacing_stays_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for jitter in [
+            1.,
+            6.,
+            MAX_PIPE_SPACING_JITTER,
+            MAX_PIPE_SPACING_JITTER * 4.,
+        ] {
+            let allowed = jitter.min(MAX_PIPE_SPACING_JITTER);
+            let (min, max) = (
+                (OBSTACLE_SPACING - allowed) * PIXEL_RATIO - 1e-3,
+                (OBSTACLE_SPACING + allowed) * PIXEL_RATIO + 1e-3,
+            );
+            let mut previous = spacing_offset(rng.gen(), jitter);
+            for _ in 0..10_000 {
+                let offset = spacing_offset(rng.gen(), jitter);
+                let spacing = OBSTACLE_SPACING * PIXEL_RATIO + offset - previous;
+                assert!(
+                    (min..=max).contains(&spacing),
+                    "{spacing} apart with {jitter} jitter"
+                );
+                assert!(spacing >= MIN_PIPE_SPACING * PIXEL_RATIO - 1e-3);
+                previous = offset;
+            }
+        }
+    }
+
+    #[test]
+    fn zigzag_alternates_sides() {
+        for seed in 0..4 {
+            let offsets = offsets(&mut StdRng::seed_from_u64(seed), GapPattern::Zigzag, 50);
+            assert!(offsets.iter().all(|offset| offset.abs() > 0.));
+            for pair in offsets.windows(2) {
+                assert_eq!(pair[0], -pair[1], "{offsets:?}");
+            }
+        }
+    }
+
+    /// The pipe `hit_pipe` finds for a bird at `bird` with pairs whose top
+    /// and bottom pipes sit at `pairs`, nearest first.
+    fn first_hit(bird: Vec3, pairs: &[[Vec3; 2]]) -> Option<Entity> {
+        let mut world = World::new();
+        let mut pipe_queue = PipeQueue::default();
+        for pair in pairs {
+            let [top, bottom] = [(pair[0], 1.), (pair[1], -1.)].map(|(translation, direction)| {
+                world
+                    .spawn((
+                        Obstacle {
+                            pipe_direction: direction,
+                            pair_number: 0,
+                            flips_gravity: false,
+                            height: OBSTACLE_HEIGHT * PIXEL_RATIO,
+                            spacing_offset: 0.,
+                        },
+                        Transform::from_translation(translation),
+                    ))
+                    .id()
+            });
+            pipe_queue.0.push_back([top, bottom]);
+        }
+        world.insert_resource(pipe_queue);
+        world.insert_resource(GameManager::new(
+            &GameConfig::default(),
+            Handle::default(),
+            WINDOW_SIZE,
+        ));
+        world
+            .run_system_once(
+                move |pipe_queue: Res<PipeQueue>,
+                      obstacle_query: Query<(&Obstacle, &Transform)>,
+                      game_manager: Res<GameManager>| {
+                    hit_pipe(bird, &pipe_queue, &obstacle_query, &game_manager)
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn round_bird_collides_with_the_pipe_as_drawn() {
+        let height = OBSTACLE_HEIGHT * PIXEL_RATIO;
+        let half_size = pipe_half_size(height);
+        assert_eq!(half_size.x, PIPE_SPRITE_WIDTH / 2. * PIXEL_RATIO);
+
+        let radius = BIRD_RADIUS * PIXEL_RATIO;
+        let hits = |offset: Vec2| {
+            let bird = (half_size + offset).extend(0.);
+            (
+                pipe_overlaps_bird(Vec3::ZERO, height, bird, Vec2::ZERO),
+                pipe_overlaps_round_bird(Vec3::ZERO, height, bird, radius),
+            )
+        };
+
+        // Straight beside the pipe both shapes reach it
+        assert_eq!(hits(Vec2::new(radius - 1., -height / 2.)), (true, true));
+        // Off the corner the circle only catches it within its radius, and
+        // the rectangle, which isn't grown up and down, never does
+        let diagonal = Vec2::ONE.normalize();
+        assert_eq!(hits(diagonal * (radius - 1.)), (false, true));
+        assert_eq!(hits(diagonal * (radius + 1.)), (false, false));
+        // Past the circle's reach beside the pipe only the rectangle, grown
+        // by the bird's half width, still counts a hit
+        assert_eq!(hits(Vec2::new(radius + 4., -1.)), (true, false));
+    }
+
+    #[test]
+    fn distant_pipe_level_with_the_bird_misses() {
+        let far = OBSTACLE_WIDTH * PIXEL_RATIO * 4.;
+        let pair = [Vec3::new(far, 0., 0.), Vec3::new(far, -WINDOW_SIZE.y, 0.)];
+        assert_eq!(first_hit(Vec3::ZERO, &[pair]), None);
+    }
+
+    #[test]
+    fn pipe_level_with_the_bird_hits() {
+        let pair = [Vec3::ZERO, Vec3::new(0., -WINDOW_SIZE.y, 0.)];
+        assert!(first_hit(Vec3::ZERO, &[pair]).is_some());
+    }
+}
@@ -0,0 +1,250 @@
+//! Short-lived sprites for effects. Every particle comes from a pool spawned
+//! once up front, as many as the `particle_cap` config entry allows, so
+//! however many effects ask for particles at once there are never more
+//! than that. Past the cap, the particles closest to fading out are taken
+//! over by new ones.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig, handle_bird_deaths, layers::PARTICLE_Z, Bird, BirdDied, PIXEL_RATIO,
+};
+
+/// Upper bound on the configured cap, to keep a typo from spawning millions.
+const MAX_PARTICLE_CAP: u32 = 4096;
+pub const PARTICLE_GRAVITY: f32 = 600.;
+
+const FEATHER_COUNT: u32 = 10;
+const FEATHER_SPEED: f32 = 220.;
+const FEATHER_LIFETIME: f32 = 0.7;
+const FEATHER_SIZE: f32 = 1.5 * PIXEL_RATIO;
+const FEATHER_COLOR: Color = Color::srgb(1., 0.95, 0.8);
+
+/// The particle pool, and the feathers a crashing bird loses. Effects
+/// anywhere else only have to send `ParticleRequest`s.
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ParticleRequest>()
+            .add_systems(Startup, spawn_particle_pool)
+            .add_systems(
+                Update,
+                (
+                    burst_feathers_on_death.after(handle_bird_deaths),
+                    emit_particles,
+                    update_particles,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Asks for one particle starting at `position`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ParticleRequest {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub color: Color,
+    /// Width and height in world units.
+    pub size: f32,
+    /// Seconds before it has faded out.
+    pub lifetime: f32,
+}
+
+#[derive(Component, Default)]
+pub struct Particle {
+    pub velocity: Vec2,
+    pub color: Color,
+    pub age: f32,
+    /// 0 while the particle is free to be used.
+    pub lifetime: f32,
+}
+
+impl Particle {
+    pub fn active(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    /// How far through its life the particle is, 1 for a free one.
+    pub fn progress(&self) -> f32 {
+        if self.active() {
+            self.age / self.lifetime
+        } else {
+            1.
+        }
+    }
+}
+
+/// The pooled particle entities.
+#[derive(Resource, Default)]
+pub struct ParticleSystem {
+    pub pool: Vec<Entity>,
+}
+
+/// Index of the particle a new request should take out of ones at
+/// `progresses`: a free one when there is one, otherwise the one furthest
+/// through its life. `None` when the pool is empty.
+pub fn pick_particle(progresses: impl IntoIterator<Item = f32>) -> Option<usize> {
+    progresses
+        .into_iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+pub fn spawn_particle_pool(mut commands: Commands, config: Res<GameConfig>) {
+    let pool = (0..config.particle_cap.min(MAX_PARTICLE_CAP))
+        .map(|_| {
+            commands
+                .spawn((
+                    Sprite::default(),
+                    Transform::from_xyz(0., 0., PARTICLE_Z),
+                    Visibility::Hidden,
+                    Particle::default(),
+                ))
+                .id()
+        })
+        .collect();
+    commands.insert_resource(ParticleSystem { pool });
+}
+
+/// Hands each request this frame a particle from the pool.
+pub fn emit_particles(
+    mut requests: EventReader<ParticleRequest>,
+    particle_system: Res<ParticleSystem>,
+    mut particle_query: Query<(&mut Particle, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    for request in requests.read() {
+        let progresses = particle_system.pool.iter().map(|&entity| {
+            particle_query
+                .get(entity)
+                .map_or(0., |(particle, ..)| particle.progress())
+        });
+        let Some(index) = pick_particle(progresses) else {
+            continue;
+        };
+        let Ok((mut particle, mut transform, mut sprite, mut visibility)) =
+            particle_query.get_mut(particle_system.pool[index])
+        else {
+            continue;
+        };
+
+        *particle = Particle {
+            velocity: request.velocity,
+            color: request.color,
+            age: 0.,
+            lifetime: request.lifetime.max(f32::EPSILON),
+        };
+        transform.translation = request.position.extend(PARTICLE_Z);
+        sprite.color = request.color;
+        sprite.custom_size = Some(Vec2::splat(request.size));
+        *visibility = Visibility::Inherited;
+    }
+}
+
+/// Moves and fades the live particles, hiding each once it's done.
+pub fn update_particles(
+    time: Res<Time>,
+    mut particle_query: Query<(&mut Particle, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    for (mut particle, mut transform, mut sprite, mut visibility) in particle_query.iter_mut() {
+        if !particle.active() {
+            continue;
+        }
+
+        particle.age += time.delta_secs();
+        particle.velocity.y -= PARTICLE_GRAVITY * time.delta_secs();
+        transform.translation += (particle.velocity * time.delta_secs()).extend(0.);
+        if particle.active() {
+            let alpha = particle.color.alpha() * (1. - particle.progress());
+            sprite.color = particle.color.with_alpha(alpha);
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+/// A ring of feathers blown out of `center`.
+pub fn feather_burst(center: Vec2) -> impl Iterator<Item = ParticleRequest> {
+    (0..FEATHER_COUNT).map(move |index| {
+        let angle = index as f32 / FEATHER_COUNT as f32 * TAU;
+        ParticleRequest {
+            position: center,
+            velocity: Vec2::from_angle(angle) * FEATHER_SPEED,
+            color: FEATHER_COLOR,
+            size: FEATHER_SIZE,
+            lifetime: FEATHER_LIFETIME,
+        }
+    })
+}
+
+/// Blows feathers out of every bird that crashes.
+pub fn burst_feathers_on_death(
+    config: Res<GameConfig>,
+    mut deaths: EventReader<BirdDied>,
+    bird_query: Query<&Transform, With<Bird>>,
+    mut requests: EventWriter<ParticleRequest>,
+) {
+    for death in deaths.read() {
+        if config.reduced_motion.enabled {
+            continue;
+        }
+        if let Ok(transform) = bird_query.get(death.bird) {
+            requests.send_batch(feather_burst(transform.translation.truncate()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn live_particles_never_exceed_the_cap() {
+        const CAP: u32 = 32;
+        let mut world = World::new();
+        world.insert_resource(GameConfig {
+            particle_cap: CAP,
+            ..default()
+        });
+        world.init_resource::<Time>();
+        world.init_resource::<Events<ParticleRequest>>();
+        world.run_system_once(spawn_particle_pool).unwrap();
+        let mut schedule = Schedule::default();
+        schedule.add_systems((emit_particles, update_particles).chain());
+
+        for frame in 0..120 {
+            // Fifty bursts' worth of particles every frame
+            let requests = (0..50).flat_map(|_| feather_burst(Vec2::new(frame as f32, 0.)));
+            world.send_event_batch(requests);
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(1. / 60.));
+            schedule.run(&mut world);
+            world.resource_mut::<Events<ParticleRequest>>().update();
+
+            let mut particle_query = world.query::<&Particle>();
+            assert_eq!(particle_query.iter(&world).count(), CAP as usize);
+            let live = particle_query
+                .iter(&world)
+                .filter(|particle| particle.active())
+                .count();
+            assert!(live <= CAP as usize, "{live} live on frame {frame}");
+            assert!(live > 0);
+        }
+    }
+
+    #[test]
+    fn free_particles_are_picked_before_live_ones() {
+        assert_eq!(pick_particle([0.3, 1., 0.9]), Some(1));
+        assert_eq!(pick_particle([0.3, 0.8, 0.5]), Some(1));
+        assert_eq!(pick_particle([]), None);
+    }
+}
@@ -0,0 +1,606 @@
+//! Every regular run is recorded as its seed and what was pressed on each
+//! frame, and the last one can be watched again from the menu. The viewer
+//! plays the recording back in a headless app of its own, the same way
+//! `headless` plays scripted runs, and shows that app's world in place of
+//! the live one.
+//!
+//! Seeking plays the recording forward to the frame asked for. Going back
+//! starts it over from the seed and plays it up to there, so wherever the
+//! timeline is moved to it shows exactly what happened on that frame.
+//!
+//! Only what `RunSet` simulates is played back, so runs that continued,
+//! started at a practice level, set a checkpoint or had comeback gaps or a
+//! flock aren't recorded. A replay is played with the current config, which has to match
+//! the one it was recorded with.
+//!
+//! With `best_replay` on, a run that sets a new best score is also kept as
+//! the best replay, next to the record it shows. Playing it back has to end
+//! on the same score, which is what makes the record checkable.
+
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint::Checkpoint,
+    config::GameConfig,
+    headless::{build_scripted_app, ScriptedFrame},
+    input::FlapInput,
+    menu,
+    save::{data_dir, remove_file_if_present, write_atomically},
+    scoring::record_score,
+    ui::format_run_time,
+    Bird, Difficulty, FinishLine, GameManager, GameState, GravityFlip, Invulnerable, Lead, NewBest,
+    Obstacle, PipeQueue, RunContinue, RunSeed, Score,
+};
+
+/// Bumped whenever a change to the game would play old recordings back
+/// differently. Replays from any other version are ignored.
+pub const REPLAY_VERSION: u32 = 1;
+/// Seconds each press of left or right moves the timeline by.
+const SEEK_STEP: f32 = 2.;
+
+/// Recording runs as they're played, keeping them once they're over, and
+/// the viewer that plays them back.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>()
+            .init_resource::<ReplaySlot>()
+            .add_systems(
+                Update,
+                record_replay_frame.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                store_replay.after(record_score),
+            )
+            .add_systems(
+                OnEnter(GameState::Victory),
+                store_replay.after(record_score),
+            )
+            .add_systems(OnEnter(GameState::ReplayViewer), open_replay_viewer)
+            .add_systems(OnExit(GameState::ReplayViewer), close_replay_viewer)
+            .add_systems(
+                Update,
+                (
+                    drive_replay,
+                    show_replay_frame,
+                    update_replay_timeline,
+                    menu::return_to_menu,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::ReplayViewer)),
+            );
+    }
+}
+const TIMELINE_HEIGHT: f32 = 8.;
+const TIMELINE_MARGIN: f32 = 24.;
+const TIMELINE_COLOR: Color = Color::srgba(1., 1., 1., 0.3);
+const TIMELINE_FILL_COLOR: Color = Color::srgb(1., 0.9, 0.3);
+
+/// Which of the stored replays to use.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplaySlot {
+    /// The last run that ended.
+    #[default]
+    Last,
+    /// The run that set the best score.
+    Best,
+}
+
+impl ReplaySlot {
+    fn path(self) -> PathBuf {
+        data_dir().join(match self {
+            ReplaySlot::Last => "replay.ron",
+            ReplaySlot::Best => "best_replay.ron",
+        })
+    }
+
+    /// Whether there is a replay stored here to watch.
+    pub fn has_replay(self) -> bool {
+        self.path().exists()
+    }
+
+    /// Drops the replay stored here, when there is one.
+    pub fn clear(self) {
+        let path = self.path();
+        if let Err(error) = remove_file_if_present(&path) {
+            error!("Failed to remove replay {}: {error}", path.display());
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Replay {
+    /// Replays written before versions were recorded read as 0.
+    #[serde(default)]
+    pub version: u32,
+    pub seed: u64,
+    /// Points the run ended on.
+    #[serde(default)]
+    pub points: u32,
+    /// `GameManager::window_dimentions` of the run, which the pipes are
+    /// laid out to.
+    pub window_size: [f32; 2],
+    pub frames: Vec<ScriptedFrame>,
+}
+
+impl Replay {
+    /// The replay stored in `slot`, `None` when there isn't one or it can't
+    /// be played back.
+    pub fn load(slot: ReplaySlot) -> Option<Self> {
+        let path = slot.path();
+        let contents = fs::read_to_string(&path).ok()?;
+        Self::parse(&contents)
+            .inspect_err(|error| warn!("Ignoring replay {}: {error}", path.display()))
+            .ok()
+    }
+
+    /// Reads a replay written by `store`, refusing one recorded by a version
+    /// of the game that would play it back differently.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let replay: Self = ron::from_str(contents).map_err(|error| error.to_string())?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "recorded with replay version {}, this is version {REPLAY_VERSION}",
+                replay.version
+            ));
+        }
+        Ok(replay)
+    }
+
+    pub fn store(&self, slot: ReplaySlot) {
+        let contents = match ron::to_string(self) {
+            Ok(contents) => contents,
+            Err(error) => {
+                error!("Failed to serialize replay: {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = write_atomically(&slot.path(), &contents) {
+            error!("Failed to write replay: {error}");
+        }
+    }
+
+    /// Seconds into the run at the end of the first `frames` frames.
+    pub fn time_at(&self, frames: usize) -> f32 {
+        self.frames
+            .iter()
+            .take(frames)
+            .map(|frame| frame.delta)
+            .sum()
+    }
+
+    /// Fewest frames played back that reach `seconds` into the run, all of
+    /// them for anything past its end.
+    pub fn frames_until(&self, seconds: f32) -> usize {
+        let mut elapsed = 0.;
+        for (index, frame) in self.frames.iter().enumerate() {
+            if elapsed >= seconds {
+                return index;
+            }
+            elapsed += frame.delta;
+        }
+        self.frames.len()
+    }
+
+    /// Plays the replay through with `config` and checks it ends on the
+    /// points it says it did.
+    pub fn verify(&self, config: &GameConfig) -> bool {
+        let mut app = build_scripted_app(
+            config.clone(),
+            self.seed,
+            Vec2::from_array(self.window_size),
+            self.frames.clone(),
+        );
+        for _ in 0..self.frames.len() {
+            app.update();
+        }
+        app.world().resource::<Score>().points == self.points
+    }
+}
+
+/// Frames of the run being played so far.
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    pub frames: Vec<ScriptedFrame>,
+}
+
+/// Runs on the same frames as `RunSet`, so the recording lines up with what
+/// it simulated.
+pub fn record_replay_frame(
+    time: Res<Time>,
+    flap_input: Res<FlapInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    recorder.frames.push(ScriptedFrame {
+        delta: time.delta_secs(),
+        flap: flap_input.just_pressed,
+        held: flap_input.held,
+        dive: keys.pressed(KeyCode::ArrowDown),
+    });
+}
+
+/// Keeps the run that just ended as the replay to watch, when it can be
+/// played back, and as the best replay when it set a new best score. A new
+/// best the replay can't show drops the old best replay, which no longer
+/// goes with the record.
+#[allow(clippy::too_many_arguments)]
+pub fn store_replay(
+    recorder: Res<ReplayRecorder>,
+    score: Res<Score>,
+    new_best: Option<Res<NewBest>>,
+    run_seed: Res<RunSeed>,
+    run_continue: Res<RunContinue>,
+    difficulty: Res<Difficulty>,
+    game_manager: Res<GameManager>,
+    config: Res<GameConfig>,
+    checkpoint: Option<Res<Checkpoint>>,
+) {
+    let keep_best = config.best_replay && new_best.is_some();
+    if run_continue.used
+        || difficulty.is_practice()
+        || checkpoint.is_some()
+        || difficulty.is_assisted()
+        || game_manager.autopilot
+        || config.flock_size > 1
+        || recorder.frames.is_empty()
+    {
+        if keep_best {
+            ReplaySlot::Best.clear();
+        }
+        return;
+    }
+
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        seed: run_seed.current,
+        points: score.points,
+        window_size: game_manager.window_dimentions.to_array(),
+        frames: recorder.frames.clone(),
+    };
+    replay.store(ReplaySlot::Last);
+    if keep_best {
+        replay.store(ReplaySlot::Best);
+    }
+}
+
+/// The replay being watched and the app it's played back in.
+pub struct ReplayViewer {
+    pub replay: Replay,
+    config: GameConfig,
+    app: App,
+    /// Frames played back so far, at least the first so the world is set up.
+    pub frame: usize,
+    pub playing: bool,
+}
+
+impl ReplayViewer {
+    pub fn new(replay: Replay, config: GameConfig) -> Self {
+        let app = Self::build_app(&replay, &config);
+        let mut viewer = Self {
+            replay,
+            config,
+            app,
+            frame: 0,
+            playing: true,
+        };
+        viewer.seek(1);
+        viewer
+    }
+
+    fn build_app(replay: &Replay, config: &GameConfig) -> App {
+        build_scripted_app(
+            config.clone(),
+            replay.seed,
+            Vec2::from_array(replay.window_size),
+            replay.frames.clone(),
+        )
+    }
+
+    /// Plays back up to `frame`, from the start again when it's behind the
+    /// current one.
+    pub fn seek(&mut self, frame: usize) {
+        let frame = frame.clamp(1, self.replay.frames.len().max(1));
+        if frame < self.frame {
+            self.app = Self::build_app(&self.replay, &self.config);
+            self.frame = 0;
+        }
+        while self.frame < frame {
+            self.app.update();
+            self.frame += 1;
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame >= self.replay.frames.len()
+    }
+}
+
+#[derive(Component)]
+pub struct ReplayTimelineText;
+
+#[derive(Component)]
+pub struct ReplayTimelineFill;
+
+/// Falls back to the menu when the replay can't be read. The best replay
+/// is checked against its record first.
+pub fn open_replay_viewer(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    slot: Res<ReplaySlot>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(replay) = Replay::load(*slot) else {
+        warn!("No replay to watch");
+        next_state.set(GameState::Menu);
+        return;
+    };
+    if *slot == ReplaySlot::Best && !replay.verify(&config) {
+        warn!(
+            "The best replay doesn't end on its {} points, the config may have changed since",
+            replay.points
+        );
+    }
+
+    // The viewer holds an app, which can't be sent between threads
+    let config = config.clone();
+    commands.queue(move |world: &mut World| {
+        world.insert_non_send_resource(ReplayViewer::new(replay, config));
+    });
+    spawn_replay_timeline(&mut commands);
+}
+
+fn spawn_replay_timeline(commands: &mut Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 20.,
+            ..Default::default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(TIMELINE_MARGIN + TIMELINE_HEIGHT + 8.),
+            width: Val::Percent(100.),
+            ..Default::default()
+        },
+        ReplayTimelineText,
+        StateScoped(GameState::ReplayViewer),
+    ));
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(TIMELINE_MARGIN),
+                left: Val::Px(TIMELINE_MARGIN),
+                right: Val::Px(TIMELINE_MARGIN),
+                height: Val::Px(TIMELINE_HEIGHT),
+                ..Default::default()
+            },
+            BackgroundColor(TIMELINE_COLOR),
+            StateScoped(GameState::ReplayViewer),
+        ))
+        .with_child((
+            Node {
+                width: Val::Percent(0.),
+                height: Val::Percent(100.),
+                ..Default::default()
+            },
+            BackgroundColor(TIMELINE_FILL_COLOR),
+            ReplayTimelineFill,
+        ));
+}
+
+pub fn close_replay_viewer(world: &mut World) {
+    world.remove_non_send_resource::<ReplayViewer>();
+}
+
+/// Space plays and pauses, left and right seek `SEEK_STEP` seconds back and
+/// forth. Playing goes one recorded frame per frame shown, and pauses at the
+/// end.
+pub fn drive_replay(keys: Res<ButtonInput<KeyCode>>, viewer: Option<NonSendMut<ReplayViewer>>) {
+    let Some(mut viewer) = viewer else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Space) {
+        if viewer.finished() {
+            viewer.seek(1);
+            viewer.playing = true;
+        } else {
+            viewer.playing = !viewer.playing;
+        }
+    }
+
+    let now = viewer.replay.time_at(viewer.frame);
+    let seek_to = if keys.just_pressed(KeyCode::ArrowLeft) {
+        Some(now - SEEK_STEP)
+    } else if keys.just_pressed(KeyCode::ArrowRight) {
+        Some(now + SEEK_STEP)
+    } else {
+        None
+    };
+    match seek_to {
+        Some(seconds) => {
+            let frame = viewer.replay.frames_until(seconds);
+            viewer.seek(frame);
+        }
+        None if viewer.playing => {
+            let frame = viewer.frame + 1;
+            viewer.seek(frame);
+        }
+        None => {}
+    }
+
+    if viewer.finished() {
+        viewer.playing = false;
+    }
+}
+
+/// Puts the live bird, pipes and score where the replay has them.
+#[allow(clippy::type_complexity)]
+pub fn show_replay_frame(
+    mut commands: Commands,
+    viewer: Option<NonSendMut<ReplayViewer>>,
+    mut score: ResMut<Score>,
+    mut bird_query: Query<
+        (Entity, &mut Transform, &mut Visibility),
+        (With<Lead>, Without<Obstacle>, Without<FinishLine>),
+    >,
+    mut obstacle_query: Query<
+        (&mut Obstacle, &mut Transform, &mut Visibility, &mut Sprite),
+        (Without<Bird>, Without<FinishLine>),
+    >,
+    mut finish_line_query: Query<(&mut Transform, &mut Visibility), With<FinishLine>>,
+    pipe_queue: Res<PipeQueue>,
+) {
+    let Some(mut viewer) = viewer else {
+        return;
+    };
+    let world = viewer.app.world_mut();
+
+    *score = world.resource::<Score>().clone();
+
+    let mut sim_birds = world.query_filtered::<(
+        &Transform,
+        &Visibility,
+        Option<&Invulnerable>,
+        Option<&GravityFlip>,
+    ), With<Lead>>();
+    if let (
+        Ok((sim_transform, sim_visibility, invulnerable, gravity_flip)),
+        Ok((entity, mut transform, mut visibility)),
+    ) = (sim_birds.get_single(world), bird_query.get_single_mut())
+    {
+        *transform = *sim_transform;
+        *visibility = *sim_visibility;
+        // Carried over for the sprite to fade and flip along with them
+        let mut bird = commands.entity(entity);
+        match invulnerable {
+            Some(invulnerable) => bird.insert(invulnerable.clone()),
+            None => bird.remove::<Invulnerable>(),
+        };
+        match gravity_flip {
+            Some(gravity_flip) => bird.insert(gravity_flip.clone()),
+            None => bird.remove::<GravityFlip>(),
+        };
+    }
+
+    let mut sim_finish_lines =
+        world.query_filtered::<(&Transform, &Visibility), With<FinishLine>>();
+    if let (Ok((sim_transform, sim_visibility)), Ok((mut transform, mut visibility))) = (
+        sim_finish_lines.get_single(world),
+        finish_line_query.get_single_mut(),
+    ) {
+        *transform = *sim_transform;
+        *visibility = *sim_visibility;
+    }
+
+    // Both pools are the same size, so pipes can be paired up by their
+    // place in the queues whatever order either is in
+    let sim_queue = world.resource::<PipeQueue>().0.clone();
+    let mut sim_pipes = world.query::<(&Obstacle, &Transform, &Visibility, &Sprite)>();
+    for (sim_pair, pair) in sim_queue.iter().zip(pipe_queue.0.iter()) {
+        for (&sim_entity, &entity) in sim_pair.iter().zip(pair) {
+            let (
+                Ok((sim_obstacle, sim_transform, sim_visibility, sim_sprite)),
+                Ok((mut obstacle, mut transform, mut visibility, mut sprite)),
+            ) = (
+                sim_pipes.get(world, sim_entity),
+                obstacle_query.get_mut(entity),
+            )
+            else {
+                continue;
+            };
+            *obstacle = sim_obstacle.clone();
+            // The live pipes keep their own scale, which the beat pulse
+            // looks after
+            transform.translation = sim_transform.translation;
+            *visibility = *sim_visibility;
+            sprite.color = sim_sprite.color;
+        }
+    }
+}
+
+pub fn update_replay_timeline(
+    viewer: Option<NonSend<ReplayViewer>>,
+    mut text_query: Query<&mut Text, With<ReplayTimelineText>>,
+    mut fill_query: Query<&mut Node, With<ReplayTimelineFill>>,
+) {
+    let Some(viewer) = viewer else {
+        return;
+    };
+
+    let now = viewer.replay.time_at(viewer.frame);
+    let duration = viewer.replay.time_at(viewer.replay.frames.len());
+    if let Ok(mut text) = text_query.get_single_mut() {
+        let status = if viewer.playing { "Playing" } else { "Paused" };
+        text.0 = format!(
+            "{} / {}  {status}\nLeft/Right to seek, Space to play or pause, Esc to leave",
+            format_run_time(now),
+            format_run_time(duration)
+        );
+    }
+    if let Ok(mut node) = fill_query.get_single_mut() {
+        let progress = if duration > 0. { now / duration } else { 1. };
+        node.width = Val::Percent(progress * 100.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(version: u32) -> Replay {
+        Replay {
+            version,
+            seed: 7,
+            points: 12,
+            window_size: [512., 512.],
+            frames: vec![
+                ScriptedFrame {
+                    delta: 1. / 60.,
+                    flap: true,
+                    held: true,
+                    dive: false,
+                },
+                ScriptedFrame {
+                    delta: 1. / 30.,
+                    ..default()
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn current_version_round_trips() {
+        let contents = ron::to_string(&replay(REPLAY_VERSION)).unwrap();
+        let parsed = Replay::parse(&contents).unwrap();
+        assert_eq!(parsed.version, REPLAY_VERSION);
+        assert_eq!(parsed.seed, 7);
+        assert_eq!(parsed.points, 12);
+        assert_eq!(parsed.window_size, [512., 512.]);
+        assert_eq!(parsed.frames, replay(REPLAY_VERSION).frames);
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        for version in [0, REPLAY_VERSION + 1] {
+            let contents = ron::to_string(&replay(version)).unwrap();
+            assert!(Replay::parse(&contents).is_err(), "version {version}");
+        }
+    }
+
+    #[test]
+    fn replays_from_before_versions_are_refused() {
+        let contents = "(seed: 7, window_size: (512., 512.), frames: [])";
+        assert!(Replay::parse(contents).is_err());
+    }
+}
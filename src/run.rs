@@ -0,0 +1,333 @@
+//! A run from start to finish: the order it's played in every frame, its
+//! clocks, starting it from the menu or again after it ends, and pausing.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    checkpoint::Checkpoint,
+    config::GameConfig,
+    daily::{self, DailyChallenge},
+    difficulty::update_difficulty,
+    flock::Flockmate,
+    input, milestones,
+    modes::ModeRules,
+    replay,
+    save::SaveData,
+    ui, Bird, Difficulty, Downed, GameManager, GameRng, GameState, GravityFlip, Health,
+    Invulnerable, LastDeath, Lead, NewBest, Obstacle, PipeLayout, RunContinue, Score, SessionStats,
+    Squash,
+};
+
+const MERCY_NOTE: &str = "Take a breather, this one's gentler";
+
+/// The steps a run moves forward by every frame while playing, in order.
+/// Each area puts its own systems in the set they belong to.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RunSet {
+    /// Clocks and timers, before anything reads them.
+    Timers,
+    /// Scrolling the pipes and counting the ones passed.
+    Pipes,
+    /// Points for the passes, before the bird can crash on the same frame.
+    Scoring,
+    /// Flying the birds into the pipes where they are now.
+    Bird,
+}
+
+/// Runs themselves, whether they're played or scripted: the order of
+/// `RunSet`, the run's clocks, starting runs and pausing them. Reads the
+/// mode's rules, so the app needs them before this is added.
+pub struct RunPlugin;
+
+impl Plugin for RunPlugin {
+    fn build(&self, app: &mut App) {
+        let rules = app.world().resource::<ModeRules>();
+        let time_attack = rules.time_limit.map(|remaining| TimeAttack { remaining });
+
+        app.init_resource::<Difficulty>()
+            .init_resource::<RunTime>()
+            .init_resource::<SessionStats>()
+            .init_resource::<PracticeLevel>()
+            .init_resource::<RunSeed>()
+            .configure_sets(
+                Update,
+                (RunSet::Timers, RunSet::Pipes, RunSet::Scoring, RunSet::Bird)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(PreStartup, apply_max_frame_delta)
+            .add_systems(
+                Update,
+                (update_run_time, update_difficulty)
+                    .chain()
+                    .in_set(RunSet::Timers),
+            )
+            .add_systems(OnExit(GameState::Menu), begin_run)
+            .add_systems(
+                Update,
+                restart_after_game_over
+                    .run_if(in_state(GameState::GameOver).or(in_state(GameState::Victory))),
+            )
+            .add_systems(
+                Update,
+                update_time_attack
+                    .after(RunSet::Timers)
+                    .run_if(in_state(GameState::Playing).and(resource_exists::<TimeAttack>)),
+            )
+            .add_systems(
+                Update,
+                toggle_pause.run_if(in_state(GameState::Playing).or(in_state(GameState::Paused))),
+            );
+
+        if let Some(time_attack) = time_attack {
+            app.insert_resource(time_attack);
+        }
+    }
+}
+
+/// Seed `GameRng` was reset with for the current run, shown after it so a
+/// layout can be shared.
+#[derive(Resource, Default)]
+pub struct RunSeed {
+    pub current: u64,
+    /// Entered from the menu to replay a shared layout, kept across retries.
+    pub chosen: Option<u64>,
+}
+
+impl RunSeed {
+    /// Picks the seed for a new run: the day's for the daily challenge, the
+    /// chosen one if there is one, otherwise a fresh random one.
+    pub fn next(&mut self, daily: Option<&DailyChallenge>) -> u64 {
+        self.current = daily
+            .map(|daily| daily.day)
+            .or(self.chosen)
+            .unwrap_or_else(rand::random);
+        self.current
+    }
+}
+
+/// Difficulty level picked in the menu for runs to start at.
+#[derive(Resource, Default)]
+pub struct PracticeLevel(pub u32);
+
+impl PracticeLevel {
+    /// The level the next run starts at, the daily challenge always being
+    /// played from the start.
+    pub fn for_run(&self, daily: Option<&DailyChallenge>) -> u32 {
+        if daily.is_some() {
+            0
+        } else {
+            self.0
+        }
+    }
+}
+
+/// Present only when the run is a time attack; counts down while playing.
+#[derive(Resource)]
+pub struct TimeAttack {
+    pub remaining: f32,
+}
+
+/// Seconds the current run has been played for, leaving out time spent
+/// paused. The clock the time limit, the ramp, scoring and telemetry all
+/// read.
+#[derive(Resource, Default)]
+pub struct RunTime(pub f32);
+
+/// Clamping the virtual clock covers every system reading `Time` at once.
+fn apply_max_frame_delta(mut time: ResMut<Time<Virtual>>, config: Res<GameConfig>) {
+    // Bevy refuses a zero maximum
+    time.set_max_delta(Duration::from_secs_f32(config.max_frame_delta.max(0.001)));
+}
+
+/// Puts the bird back at its start, briefly invulnerable in case a pipe ends
+/// up on top of it, and lays out a fresh set of pipes.
+#[allow(clippy::too_many_arguments)]
+fn reset_run(
+    commands: &mut Commands,
+    bird_query: &mut Query<
+        (Entity, &mut Bird, &mut Transform, Option<&Flockmate>),
+        Without<Obstacle>,
+    >,
+    pipe_layout: &mut PipeLayout,
+    game_manager: &GameManager,
+    rules: &ModeRules,
+    score: &mut Score,
+    rng: &mut impl Rng,
+    difficulty: Difficulty,
+) {
+    let invulnerability = rules
+        .spawn_invulnerability
+        .max(difficulty.mercy_invulnerability);
+    for (entity, mut bird, mut transform, flockmate) in bird_query.iter_mut() {
+        transform.translation = flockmate.map_or(game_manager.bird_start, |flockmate| {
+            flockmate.start(game_manager.bird_start)
+        });
+        transform.rotation = Quat::IDENTITY;
+
+        bird.velocity = 0.;
+        bird.flap_hold = None;
+        let mut bird = commands.entity(entity);
+        bird.insert((
+            Invulnerable::new(invulnerability),
+            Health(rules.max_health),
+            Visibility::Inherited,
+        ))
+        .remove::<(GravityFlip, Downed, Squash)>();
+        // The lead goes back to the player's bird, whoever ended the last run
+        match flockmate {
+            Some(_) => bird.remove::<Lead>(),
+            None => bird.insert(Lead),
+        };
+    }
+    *score = Score::default();
+    commands.insert_resource(RunContinue::default());
+    commands.insert_resource(LastDeath::default());
+    commands.insert_resource(RunTime::default());
+    commands.remove_resource::<Checkpoint>();
+    commands.remove_resource::<NewBest>();
+    commands.insert_resource(replay::ReplayRecorder::default());
+    commands.insert_resource(ui::AltitudeHistory::default());
+    commands.insert_resource(ui::BestScoreBeaten::default());
+    if difficulty.mercy {
+        milestones::spawn_banner(commands, MERCY_NOTE);
+    }
+
+    let gap_size = game_manager.effective_gap_size(difficulty.gap_size());
+    pipe_layout.lay_out(rng, game_manager, gap_size, difficulty.level());
+    commands.insert_resource(difficulty);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn restart_after_game_over(
+    mut commands: Commands,
+    mut bird_query: Query<
+        (Entity, &mut Bird, &mut Transform, Option<&Flockmate>),
+        Without<Obstacle>,
+    >,
+    mut pipe_layout: PipeLayout,
+    flap_input: Res<input::FlapInput>,
+    // Paired up to stay within the most parameters a system can take
+    (game_manager, rules): (Res<GameManager>, Res<ModeRules>),
+    config: Res<GameConfig>,
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+    mut run_seed: ResMut<RunSeed>,
+    practice_level: Res<PracticeLevel>,
+    mut session: ResMut<SessionStats>,
+    save: Res<SaveData>,
+    time_attack: Option<ResMut<TimeAttack>>,
+    daily: Option<Res<DailyChallenge>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !flap_input.just_pressed {
+        return;
+    }
+
+    if daily
+        .as_ref()
+        .is_some_and(|daily| !daily::attempt_available(save.daily.as_ref(), daily.day))
+    {
+        return;
+    }
+
+    rng.0 = StdRng::seed_from_u64(run_seed.next(daily.as_deref()));
+    reset_run(
+        &mut commands,
+        &mut bird_query,
+        &mut pipe_layout,
+        &game_manager,
+        &rules,
+        &mut score,
+        &mut rng.0,
+        session.next_run_difficulty(
+            &config,
+            &rules,
+            practice_level.for_run(daily.as_deref()),
+            daily.as_deref(),
+        ),
+    );
+
+    if let (Some(mut time_attack), Some(time_limit)) = (time_attack, rules.time_limit) {
+        time_attack.remaining = time_limit;
+    }
+
+    next_state.set(GameState::Playing);
+}
+
+/// Leaving the menu always starts a fresh run, whatever was configured there.
+#[allow(clippy::too_many_arguments)]
+fn begin_run(
+    mut commands: Commands,
+    mut bird_query: Query<
+        (Entity, &mut Bird, &mut Transform, Option<&Flockmate>),
+        Without<Obstacle>,
+    >,
+    mut pipe_layout: PipeLayout,
+    game_manager: Res<GameManager>,
+    config: Res<GameConfig>,
+    rules: Res<ModeRules>,
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+    mut run_seed: ResMut<RunSeed>,
+    practice_level: Res<PracticeLevel>,
+    mut session: ResMut<SessionStats>,
+    time_attack: Option<ResMut<TimeAttack>>,
+    daily: Option<Res<DailyChallenge>>,
+) {
+    rng.0 = StdRng::seed_from_u64(run_seed.next(daily.as_deref()));
+    reset_run(
+        &mut commands,
+        &mut bird_query,
+        &mut pipe_layout,
+        &game_manager,
+        &rules,
+        &mut score,
+        &mut rng.0,
+        session.next_run_difficulty(
+            &config,
+            &rules,
+            practice_level.for_run(daily.as_deref()),
+            daily.as_deref(),
+        ),
+    );
+
+    if let (Some(mut time_attack), Some(time_limit)) = (time_attack, rules.time_limit) {
+        time_attack.remaining = time_limit;
+    }
+}
+
+/// Counts the time limit down by the run's own clock.
+fn update_time_attack(
+    rules: Res<ModeRules>,
+    run_time: Res<RunTime>,
+    mut time_attack: ResMut<TimeAttack>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let time_limit = rules.time_limit.unwrap_or_default();
+    time_attack.remaining = (time_limit - run_time.0).max(0.);
+    if time_attack.remaining <= 0. {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) || keys.just_pressed(KeyCode::KeyP) {
+        match state.get() {
+            GameState::Playing => next_state.set(GameState::Paused),
+            _ => next_state.set(GameState::Playing),
+        }
+    }
+}
+
+/// Only runs while `Playing`, so time paused doesn't count.
+fn update_run_time(time: Res<Time>, mut run_time: ResMut<RunTime>) {
+    run_time.0 += time.delta_secs();
+}
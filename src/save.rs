@@ -0,0 +1,235 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, window::WindowCloseRequested};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameConfig, daily::DailyRecord, high_score::HighScore, replay::ReplaySlot,
+    scoring::SLIPSTREAM_MAX_MULTIPLIER, telemetry, MIN_PIPE_SPACING, PIXEL_RATIO,
+};
+
+pub(crate) const SAVE_FILE_NAME: &str = "save.ron";
+
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct SaveData {
+    pub daily: Option<DailyRecord>,
+    pub coins: u32,
+}
+
+impl SaveData {
+    /// Reads the save file, starting fresh when it's missing or corrupt.
+    pub fn load() -> Self {
+        let path = data_dir().join(SAVE_FILE_NAME);
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match ron::from_str(&contents) {
+            Ok(save) => save,
+            Err(error) => {
+                warn!("Ignoring corrupt save file {}: {error}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn store(&self) {
+        let dir = data_dir();
+        let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => contents,
+            Err(error) => {
+                error!("Failed to serialize save data: {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = write_atomically(&dir.join(SAVE_FILE_NAME), &contents) {
+            error!("Failed to write save file: {error}");
+        }
+    }
+}
+
+/// Wipes all progress kept between sessions, putting `save` and
+/// `high_score` back to a fresh start. Only the config is left alone. Files
+/// that were never written are skipped.
+pub fn reset_all_data(save: &mut SaveData, high_score: &mut HighScore) {
+    *save = SaveData::default();
+    high_score.reset();
+    let path = data_dir().join(SAVE_FILE_NAME);
+    if let Err(error) = remove_file_if_present(&path) {
+        error!("Failed to remove save file {}: {error}", path.display());
+    }
+    ReplaySlot::Last.clear();
+    ReplaySlot::Best.clear();
+    telemetry::delete_telemetry();
+}
+
+/// Deletes the file at `path`, which is fine to be missing already.
+pub fn remove_file_if_present(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Writes everything the game keeps between sessions, for when it's about
+/// to quit.
+pub fn save_all(save: &SaveData, high_score: &HighScore, config: &GameConfig) {
+    save.store();
+    high_score.store();
+    config.store();
+}
+
+/// Saves are written as soon as they change, this is a last write for
+/// whatever may still be pending when the app quits, be it from a menu or
+/// the window being closed. Closing the window is caught as it's asked for,
+/// in case the app is torn down before the exit that follows gets here.
+pub fn flush_saves_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    mut close_events: EventReader<WindowCloseRequested>,
+    save: Res<SaveData>,
+    high_score: Res<HighScore>,
+    config: Res<GameConfig>,
+) {
+    let exiting = exit_events.read().count() > 0;
+    let closing = close_events.read().count() > 0;
+    if exiting || closing {
+        save_all(&save, &high_score, &config);
+    }
+}
+
+/// Replaces the file at `path` with `contents`, creating its directory as
+/// needed. The contents go to a temporary file next to it that's renamed
+/// over it once fully written, so a crash part way through leaves the old
+/// file as it was rather than half of the new one.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    });
+    match result.and_then(|_| fs::rename(&temp_path, path)) {
+        Ok(()) => Ok(()),
+        Err(error) => {
+            // Nothing is left behind for the next write to trip over
+            let _ = fs::remove_file(&temp_path);
+            Err(error)
+        }
+    }
+}
+
+/// Whether `score` points could really have been earned in `duration`
+/// seconds with pipes scrolling at no more than `speed`, even if every pair
+/// got the full slipstream bonus and came as close together as spacing
+/// jitter allows. One pair of slack covers the pipe that starts closest to
+/// the bird.
+pub fn is_plausible_score(score: u32, duration: f32, speed: f32) -> bool {
+    let max_pairs =
+        (duration.max(0.) * speed / (MIN_PIPE_SPACING * PIXEL_RATIO)).floor() as u32 + 1;
+    score <= max_pairs * SLIPSTREAM_MAX_MULTIPLIER
+}
+
+/// Platform config directory the game keeps its files in.
+pub fn data_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("bevy_flappy_bird")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::OBSTACLE_SPACING;
+
+    const SPEED: f32 = 300.;
+
+    #[test]
+    fn score_within_the_run_is_plausible() {
+        let duration = 30.;
+        let pairs_passed = (duration * SPEED / (OBSTACLE_SPACING * PIXEL_RATIO)) as u32;
+        assert!(is_plausible_score(pairs_passed, duration, SPEED));
+        assert!(is_plausible_score(
+            pairs_passed * SLIPSTREAM_MAX_MULTIPLIER,
+            duration,
+            SPEED
+        ));
+        assert!(is_plausible_score(0, 0., SPEED));
+    }
+
+    #[test]
+    fn score_beyond_the_closest_spacing_is_rejected() {
+        let duration = 30.;
+        let max_pairs = (duration * SPEED / (MIN_PIPE_SPACING * PIXEL_RATIO)) as u32 + 1;
+        let max_score = max_pairs * SLIPSTREAM_MAX_MULTIPLIER;
+        assert!(is_plausible_score(max_score, duration, SPEED));
+        assert!(!is_plausible_score(max_score + 1, duration, SPEED));
+    }
+
+    #[test]
+    fn negative_duration_counts_as_none() {
+        assert!(is_plausible_score(SLIPSTREAM_MAX_MULTIPLIER, -5., SPEED));
+        assert!(!is_plausible_score(
+            SLIPSTREAM_MAX_MULTIPLIER + 1,
+            -5.,
+            SPEED
+        ));
+    }
+
+    #[test]
+    fn saves_with_bests_in_them_still_load() {
+        let save: SaveData =
+            ron::from_str("(best_score: 30, time_attack_best: 12, daily: None, coins: 5)").unwrap();
+        assert_eq!(save.coins, 5);
+    }
+
+    /// An empty directory of its own for the test called `name`.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("bevy_flappy_bird_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn atomic_write_replaces_the_file() {
+        let dir = temp_dir("atomic_write");
+        let path = dir.join("nested").join(SAVE_FILE_NAME);
+        write_atomically(&path, "old").unwrap();
+        write_atomically(&path, "new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let leftovers: Vec<_> = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name != SAVE_FILE_NAME)
+            .collect();
+        assert!(leftovers.is_empty(), "left behind {leftovers:?}");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_atomic_write_keeps_the_old_file() {
+        let dir = temp_dir("failed_atomic_write");
+        let path = dir.join(SAVE_FILE_NAME);
+        write_atomically(&path, "old").unwrap();
+        // A directory where the temporary file should go can't be written to
+        fs::create_dir(dir.join(format!("{SAVE_FILE_NAME}.tmp"))).unwrap();
+
+        assert!(write_atomically(&path, "new").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
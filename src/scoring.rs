@@ -0,0 +1,388 @@
+//! Points for the pipes the bird gets past, and what's kept of them once a
+//! run is over: the coins, the bests and the daily challenge record.
+
+use bevy::prelude::*;
+
+use crate::{
+    checkpoint::Checkpoint,
+    config::GameConfig,
+    daily::{self, DailyChallenge, DailyRecord},
+    high_score::HighScore,
+    modes::ModeRules,
+    save::{self, SaveData},
+    Bird, Difficulty, Downed, GameManager, GameState, GravityFlip, Lead, Obstacle, PipePassed,
+    RunContinue, RunSet, RunTime, SessionStats, TimeAttack, PIXEL_RATIO,
+};
+
+const COINS_PER_PIPE: u32 = 1;
+/// Passes closer together than this keep a slipstream streak going. Pairs
+/// arrive every 1.6s at the starting speed.
+const SLIPSTREAM_WINDOW: f32 = 1.7;
+const SLIPSTREAM_PASSES_PER_STEP: u32 = 3;
+pub(crate) const SLIPSTREAM_MAX_MULTIPLIER: u32 = 3;
+/// A pass with the bird's center closer than this to the end of a pipe, as
+/// it clears the pair, is a near miss.
+const NEAR_MISS_MARGIN: f32 = 3. * PIXEL_RATIO;
+
+/// The run's score, and recording it once the run ends.
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .add_systems(Update, update_score.in_set(RunSet::Scoring))
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (record_score, count_low_score_deaths),
+            )
+            .add_systems(OnEnter(GameState::Victory), record_score);
+    }
+}
+
+#[derive(Resource, Default, Clone)]
+pub struct Score {
+    /// Points including slipstream bonuses, which is what records keep.
+    pub points: u32,
+    pub pipes_passed: u32,
+    /// Passes in a row that each came within `SLIPSTREAM_WINDOW` of the
+    /// previous one.
+    pub streak: u32,
+    /// Run time of the latest pass.
+    pub last_pass: Option<f32>,
+    /// Coins earned since the score was last recorded.
+    pub coins: u32,
+    /// Every coin earned this run, recorded or not.
+    pub coins_earned: u32,
+    /// Most passes in a single slipstream streak.
+    pub best_combo: u32,
+    /// Pairs passed with the bird's center close to one of the pipes.
+    pub near_misses: u32,
+    /// `Obstacle::pair_number` of the newest pair scored. Tracking how far
+    /// the bird got rather than flagging pipes means anything that moves it
+    /// back can't have it score the same pairs twice, while restoring a
+    /// saved `Score` opens them up again.
+    pub last_scored_pair: Option<u32>,
+    /// Training targets hit, kept apart from `points` so records stay
+    /// comparable with and without targets.
+    pub precision_points: u32,
+    /// `Obstacle::pair_number` of the newest pair whose target was judged.
+    pub last_judged_target: Option<u32>,
+    /// Highest milestone celebrated, 0 before the first.
+    pub milestone: u32,
+}
+
+impl Score {
+    pub fn multiplier(&self) -> u32 {
+        slipstream_multiplier(self.streak)
+    }
+
+    /// Whether the pair numbered `pair_number` has already earned its points.
+    pub fn has_scored(&self, pair_number: u32) -> bool {
+        self.last_scored_pair
+            .is_some_and(|last_scored_pair| pair_number <= last_scored_pair)
+    }
+
+    pub fn target_judged(&self, pair_number: u32) -> bool {
+        self.last_judged_target
+            .is_some_and(|last_judged| pair_number <= last_judged)
+    }
+
+    /// Records whether the bird went through the target of the pair numbered
+    /// `pair_number`, returning false when it was already judged.
+    pub fn judge_target(&mut self, pair_number: u32, hit: bool) -> bool {
+        if self.target_judged(pair_number) {
+            return false;
+        }
+        self.last_judged_target = Some(pair_number);
+        if hit {
+            self.precision_points += 1;
+        }
+        true
+    }
+
+    /// Counts the pair numbered `pair_number` passed at run time `now` with
+    /// the slipstream bonus multiplied by `score_multiplier`, returning the
+    /// points it was worth, or nothing when it already scored.
+    pub fn pass_pipe(&mut self, pair_number: u32, now: f32, score_multiplier: u32) -> Option<u32> {
+        if self.has_scored(pair_number) {
+            return None;
+        }
+        self.last_scored_pair = Some(pair_number);
+
+        self.streak = match self.last_pass {
+            Some(last_pass) if now - last_pass <= SLIPSTREAM_WINDOW => self.streak + 1,
+            _ => 0,
+        };
+        self.last_pass = Some(now);
+        self.best_combo = self.best_combo.max(self.streak + 1);
+        self.pipes_passed += 1;
+        self.coins += COINS_PER_PIPE;
+        self.coins_earned += COINS_PER_PIPE;
+
+        let points = self.multiplier() * score_multiplier;
+        self.points += points;
+        Some(points)
+    }
+}
+
+/// Points per pipe for a streak of quick passes: one more for every
+/// `SLIPSTREAM_PASSES_PER_STEP` passes, up to `SLIPSTREAM_MAX_MULTIPLIER`.
+pub fn slipstream_multiplier(streak: u32) -> u32 {
+    (1 + streak / SLIPSTREAM_PASSES_PER_STEP).min(SLIPSTREAM_MAX_MULTIPLIER)
+}
+
+/// Present once the current run has beaten the best score.
+#[derive(Resource)]
+pub struct NewBest;
+
+/// Scores each pair of pipes passed, along with a near miss for squeezing
+/// by and the gravity flip of a flipping gap.
+#[allow(clippy::too_many_arguments)]
+pub fn update_score(
+    mut commands: Commands,
+    mut passes: EventReader<PipePassed>,
+    mut score: ResMut<Score>,
+    game_manager: Res<GameManager>,
+    config: Res<GameConfig>,
+    rules: Res<ModeRules>,
+    run_time: Res<RunTime>,
+    lead_query: Query<&Transform, With<Lead>>,
+    flying_query: Query<Entity, (With<Bird>, Without<Downed>)>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+) {
+    let Ok(bird_transform) = lead_query.get_single() else {
+        passes.clear();
+        return;
+    };
+
+    for pass in passes.read() {
+        if score
+            .pass_pipe(pass.pair_number, run_time.0, rules.score_multiplier)
+            .is_none()
+        {
+            continue;
+        }
+        let pair = &pass.pair;
+        if let Ok(pipes) = obstacle_query.get_many(*pair) {
+            let clearance = pipes
+                .iter()
+                .map(|(obstacle, transform)| {
+                    let gap_end =
+                        transform.translation.y - obstacle.pipe_direction * obstacle.height / 2.;
+                    (gap_end - bird_transform.translation.y).abs()
+                })
+                .fold(f32::INFINITY, f32::min)
+                - game_manager.bird_growth().y;
+            if clearance < NEAR_MISS_MARGIN {
+                score.near_misses += 1;
+            }
+        }
+        // Passing another flip while flipped starts the timer over. Every
+        // bird still flying passes it along with the lead
+        let flips_gravity = obstacle_query
+            .get(pair[0])
+            .is_ok_and(|(obstacle, _)| obstacle.flips_gravity);
+        if flips_gravity {
+            for bird_entity in flying_query.iter() {
+                commands
+                    .entity(bird_entity)
+                    .insert(GravityFlip::new(config.gravity_flip_duration));
+            }
+        }
+    }
+}
+
+/// Whether the run can set a best score. Practice, checkpoints, assists, a
+/// bigger bird, the autopilot and modes that aren't recorded all rule it
+/// out. `restored` is whether a checkpoint is in play.
+pub fn run_is_record_eligible(
+    difficulty: &Difficulty,
+    game_manager: &GameManager,
+    rules: &ModeRules,
+    restored: bool,
+) -> bool {
+    !difficulty.is_practice()
+        && !restored
+        && !difficulty.is_assisted()
+        && !game_manager.bird_enlarged()
+        && !game_manager.autopilot
+        && rules.record_eligible
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_score(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    difficulty: Res<Difficulty>,
+    run_time: Res<RunTime>,
+    game_manager: Res<GameManager>,
+    mut save: ResMut<SaveData>,
+    mut high_score: ResMut<HighScore>,
+    time_attack: Option<Res<TimeAttack>>,
+    daily: Option<Res<DailyChallenge>>,
+    checkpoint: Option<Res<Checkpoint>>,
+    rules: Res<ModeRules>,
+) {
+    // The scroll speed only ever ramps up during a run, so the current one
+    // bounds how fast pipes could have been passed.
+    if !save::is_plausible_score(
+        score.points / rules.score_multiplier.max(1),
+        run_time.0,
+        difficulty.scroll_speed(),
+    ) {
+        warn!(
+            "Rejecting implausible score of {} after {:.1}s",
+            score.points, run_time.0
+        );
+        return;
+    }
+
+    // Coins are banked as they're recorded so a continued run doesn't pay
+    // out the same pipes twice.
+    let mut changed = score.coins > 0;
+    save.coins += score.coins;
+    score.coins = 0;
+
+    // Daily attempts are kept apart from the regular bests, and only the
+    // first one of the day is recorded.
+    if let Some(daily) = daily {
+        if daily::attempt_available(save.daily.as_ref(), daily.day) {
+            save.daily = Some(DailyRecord {
+                day: daily.day,
+                score: score.points,
+            });
+            changed = true;
+        }
+    } else if run_is_record_eligible(&difficulty, &game_manager, &rules, checkpoint.is_some()) {
+        // Only the regular best has a replay kept with it
+        let new_best = high_score.submit(score.points, time_attack.is_some());
+        if new_best && time_attack.is_none() {
+            commands.insert_resource(NewBest);
+        }
+        if new_best {
+            high_score.store();
+        }
+    }
+
+    if changed {
+        save.store();
+    }
+}
+
+/// Keeps count of low-scoring runs for the comeback gaps and mercy.
+/// Autopilot runs say nothing about how the player is doing.
+fn count_low_score_deaths(
+    time: Res<Time<Real>>,
+    mut session: ResMut<SessionStats>,
+    score: Res<Score>,
+    config: Res<GameConfig>,
+    game_manager: Res<GameManager>,
+    run_continue: Res<RunContinue>,
+) {
+    if game_manager.autopilot {
+        return;
+    }
+    session.count_run(score.points, config.comeback.low_score, run_continue.used);
+    if !run_continue.used {
+        session.count_mercy_death(time.elapsed_secs(), score.points, &config.mercy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{modes::GameMode, WINDOW_SIZE};
+
+    #[test]
+    fn only_unaided_runs_in_recorded_modes_are_record_eligible() {
+        let config = GameConfig::default();
+        let mut game_manager = GameManager::new(&config, Handle::default(), WINDOW_SIZE);
+        let normal = GameMode::Normal.rules(&config);
+        let difficulty = Difficulty::default();
+        assert!(run_is_record_eligible(
+            &difficulty,
+            &game_manager,
+            &normal,
+            false
+        ));
+
+        assert!(!run_is_record_eligible(
+            &difficulty,
+            &game_manager,
+            &normal,
+            true
+        ));
+        assert!(!run_is_record_eligible(
+            &Difficulty::starting_at(2),
+            &game_manager,
+            &normal,
+            false
+        ));
+        assert!(!run_is_record_eligible(
+            &difficulty,
+            &game_manager,
+            &GameMode::Zen.rules(&config),
+            false
+        ));
+        game_manager.autopilot = true;
+        assert!(!run_is_record_eligible(
+            &difficulty,
+            &game_manager,
+            &normal,
+            false
+        ));
+    }
+
+    #[test]
+    fn rewound_pairs_only_score_again_from_a_restored_score() {
+        let mut score = Score::default();
+        assert_eq!(score.pass_pipe(0, 0., 1), Some(1));
+        let saved = score.clone();
+        assert_eq!(score.pass_pipe(1, 5., 1), Some(1));
+
+        // Moving back over pairs already passed scores nothing
+        assert_eq!(score.pass_pipe(1, 10., 1), None);
+        assert_eq!(score.pass_pipe(0, 10., 1), None);
+        assert_eq!(score.pipes_passed, 2);
+
+        // A restored score hasn't seen the pairs after it yet
+        score = saved;
+        assert_eq!(score.pass_pipe(0, 15., 1), None);
+        assert_eq!(score.pass_pipe(1, 15., 1), Some(1));
+        assert_eq!(score.pass_pipe(2, 20., 1), Some(1));
+        assert_eq!((score.points, score.pipes_passed), (3, 3));
+    }
+
+    #[test]
+    fn slipstream_steps_up_and_caps() {
+        assert_eq!(slipstream_multiplier(0), 1);
+        assert_eq!(slipstream_multiplier(SLIPSTREAM_PASSES_PER_STEP - 1), 1);
+        assert_eq!(slipstream_multiplier(SLIPSTREAM_PASSES_PER_STEP), 2);
+        assert_eq!(slipstream_multiplier(SLIPSTREAM_PASSES_PER_STEP * 2), 3);
+        assert_eq!(slipstream_multiplier(1000), SLIPSTREAM_MAX_MULTIPLIER);
+        for streak in 0..100 {
+            assert!(slipstream_multiplier(streak + 1) >= slipstream_multiplier(streak));
+        }
+    }
+
+    #[test]
+    fn quick_passes_build_a_streak_and_a_slow_one_ends_it() {
+        let mut score = Score::default();
+        let mut now = 0.;
+        for pair_number in 0..=SLIPSTREAM_PASSES_PER_STEP {
+            score.pass_pipe(pair_number, now, 1);
+            now += SLIPSTREAM_WINDOW / 2.;
+        }
+        assert_eq!(score.streak, SLIPSTREAM_PASSES_PER_STEP);
+        assert_eq!(score.points, SLIPSTREAM_PASSES_PER_STEP + 2);
+
+        let points = score.pass_pipe(
+            SLIPSTREAM_PASSES_PER_STEP + 1,
+            now + SLIPSTREAM_WINDOW * 2.,
+            1,
+        );
+        assert_eq!(points, Some(1));
+        assert_eq!(score.streak, 0);
+        assert_eq!(score.best_combo, SLIPSTREAM_PASSES_PER_STEP + 1);
+    }
+}
@@ -0,0 +1,89 @@
+//! F12 saves what's on screen to a PNG, named after the run's seed and
+//! score so it's clear later which run it came from. Overlays are captured
+//! only if they're showing.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    render::view::screenshot::{Screenshot, ScreenshotCaptured},
+    tasks::IoTaskPool,
+};
+
+use crate::{config::GameConfig, save::data_dir, RunSeed, Score};
+
+const SCREENSHOT_DIR_NAME: &str = "screenshots";
+
+/// Where screenshots go: the configured directory, or one in the data
+/// directory.
+pub fn screenshot_dir(config: &GameConfig) -> PathBuf {
+    config
+        .screenshot_dir
+        .as_ref()
+        .map_or_else(|| data_dir().join(SCREENSHOT_DIR_NAME), PathBuf::from)
+}
+
+/// The time taken keeps screenshots of the same run and score apart.
+pub fn screenshot_file_name(seed: u64, points: u32, taken_at: u64) -> String {
+    format!("flappy-seed{seed}-score{points}-{taken_at}.png")
+}
+
+pub fn take_screenshot(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Res<GameConfig>,
+    run_seed: Res<RunSeed>,
+    score: Res<Score>,
+) {
+    if !keys.just_pressed(KeyCode::F12) {
+        return;
+    }
+
+    let taken_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = screenshot_dir(&config).join(screenshot_file_name(
+        run_seed.current,
+        score.points,
+        taken_at,
+    ));
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(move |trigger: Trigger<ScreenshotCaptured>| save_screenshot(&trigger, &path));
+}
+
+/// Encodes and writes the image on the IO pool, so a big window doesn't
+/// hold up the frame.
+fn save_screenshot(captured: &ScreenshotCaptured, path: &Path) {
+    let image = match captured.0.clone().try_into_dynamic() {
+        Ok(image) => image,
+        Err(error) => {
+            error!("Failed to read screenshot: {error}");
+            return;
+        }
+    };
+    let path = path.to_path_buf();
+    IoTaskPool::get()
+        .spawn(async move {
+            // The alpha channel isn't opacity, leaving it in looks wrong
+            let result = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .map_err(|error| error.to_string())
+                .and_then(|_| {
+                    image
+                        .to_rgb8()
+                        .save(&path)
+                        .map_err(|error| error.to_string())
+                });
+            match result {
+                Ok(()) => info!("Saved screenshot to {}", path.display()),
+                Err(error) => error!("Failed to save screenshot {}: {error}", path.display()),
+            }
+        })
+        .detach();
+}
@@ -0,0 +1,170 @@
+//! Training targets: a small marker in the middle of each gap that scores a
+//! precision point when the bird flies right through it, to teach aiming
+//! for the center. They only ever look at the bird's position, so pipes
+//! collide exactly the same with or without them.
+
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig,
+    gap_center,
+    layers::{POPUP_Z, TARGET_Z},
+    setup_level,
+    ui::{ScorePopup, SCORE_POPUP_LIFETIME},
+    update_bird, GameManager, GameState, Lead, Obstacle, PipeQueue, RunSet, Score, PIXEL_RATIO,
+};
+
+/// In sprite pixels.
+const TARGET_RADIUS: f32 = 2.5;
+/// Misses within this many radii of the center still get told how close
+/// they were.
+const TARGET_NEAR_MISS: f32 = 3.;
+const TARGET_COLOR: Color = Color::srgba(1., 0.9, 0.3, 0.8);
+
+/// The targets, when the config turns them on.
+pub struct TargetsPlugin;
+
+impl Plugin for TargetsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().resource::<GameConfig>().training_targets {
+            return;
+        }
+
+        app.add_systems(Startup, spawn_targets.after(setup_level))
+            .add_systems(Update, place_targets.after(RunSet::Pipes))
+            .add_systems(
+                Update,
+                judge_targets
+                    .after(update_bird)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Marker in the gap of the pipe pair `pair`, as `[top, bottom]`.
+#[derive(Component)]
+pub struct Target {
+    pub pair: [Entity; 2],
+}
+
+/// One target for every pair in the pool, following its pair as it's
+/// recycled.
+pub fn spawn_targets(
+    mut commands: Commands,
+    pipe_queue: Res<PipeQueue>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mesh = meshes.add(Circle::new(TARGET_RADIUS * PIXEL_RATIO));
+    let material = materials.add(ColorMaterial::from_color(TARGET_COLOR));
+    for &pair in pipe_queue.0.iter() {
+        commands.spawn((
+            Mesh2d(mesh.clone()),
+            MeshMaterial2d(material.clone()),
+            Transform::from_xyz(0., 0., TARGET_Z),
+            Visibility::Hidden,
+            Target { pair },
+        ));
+    }
+}
+
+/// Keeps each target in the middle of its gap, shown along with its pipes
+/// until the bird has gone past it.
+pub fn place_targets(
+    score: Res<Score>,
+    obstacle_query: Query<(&Obstacle, &Transform, &Visibility), Without<Target>>,
+    mut target_query: Query<(&Target, &mut Transform, &mut Visibility)>,
+) {
+    for (target, mut transform, mut visibility) in target_query.iter_mut() {
+        let Ok([(obstacle, top, top_visibility), (_, bottom, _)]) =
+            obstacle_query.get_many(target.pair)
+        else {
+            continue;
+        };
+
+        let translation = Vec3::new(
+            top.translation.x,
+            gap_center(top.translation, bottom.translation),
+            TARGET_Z,
+        );
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+        let shown =
+            *top_visibility != Visibility::Hidden && !score.target_judged(obstacle.pair_number);
+        let target_visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != target_visibility {
+            *visibility = target_visibility;
+        }
+    }
+}
+
+/// What the bird is told after going past a target `distance` off its
+/// center, nothing for a clear miss.
+pub fn precision_feedback(distance: f32) -> Option<&'static str> {
+    let radius = TARGET_RADIUS * PIXEL_RATIO;
+    if distance < radius {
+        Some("Bullseye!")
+    } else if distance < radius * TARGET_NEAR_MISS {
+        Some("Close!")
+    } else {
+        None
+    }
+}
+
+/// Judges each target as the bird reaches it, in the order the pairs come.
+pub fn judge_targets(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    game_manager: Res<GameManager>,
+    bird_query: Query<&Transform, With<Lead>>,
+    obstacle_query: Query<&Obstacle>,
+    target_query: Query<(&Target, &Transform)>,
+) {
+    let Ok(bird_transform) = bird_query.get_single() else {
+        return;
+    };
+
+    let mut targets: Vec<_> = target_query
+        .iter()
+        .filter_map(|(target, transform)| {
+            let obstacle = obstacle_query.get(target.pair[0]).ok()?;
+            Some((obstacle.pair_number, transform.translation))
+        })
+        .collect();
+    targets.sort_by_key(|&(pair_number, _)| pair_number);
+
+    for (pair_number, translation) in targets {
+        if score.target_judged(pair_number) {
+            continue;
+        }
+        if (translation.x - bird_transform.translation.x) * game_manager.facing > 0. {
+            break;
+        }
+
+        let distance = (bird_transform.translation.y - translation.y).abs();
+        let feedback = precision_feedback(distance);
+        let hit = distance < TARGET_RADIUS * PIXEL_RATIO;
+        if !score.judge_target(pair_number, hit) {
+            continue;
+        }
+        if let Some(feedback) = feedback {
+            commands.spawn((
+                Text2d::new(feedback),
+                TextFont {
+                    font_size: 20.,
+                    ..Default::default()
+                },
+                TextColor(TARGET_COLOR),
+                Transform::from_translation(translation.with_z(POPUP_Z)),
+                ScorePopup {
+                    lifetime: SCORE_POPUP_LIFETIME,
+                },
+            ));
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig,
+    save::{data_dir, remove_file_if_present},
+    BirdDied, BirdFlapped, DeathCause, GameState, PipePassed, RunSeed, RunSet, RunTime, Score,
+};
+
+const TELEMETRY_FILE_NAME: &str = "telemetry.csv";
+const TELEMETRY_HEADER: &str = "seed,time,event,value\n";
+
+/// Writes every run's flaps, passes and crashes to the telemetry file, when
+/// the config turns it on. Runs left for the menu aren't written.
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().resource::<GameConfig>().telemetry {
+            return;
+        }
+
+        app.init_resource::<Telemetry>()
+            .add_systems(Update, record_gameplay_events.after(RunSet::Bird))
+            .add_systems(OnEnter(GameState::GameOver), flush_telemetry)
+            .add_systems(OnEnter(GameState::Victory), flush_telemetry)
+            .add_systems(OnEnter(GameState::Menu), discard_telemetry);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum TelemetryEvent {
+    Flap,
+    Pass { points: u32 },
+    Death(DeathCause),
+}
+
+/// Per-run events buffered for balancing, only present when the `telemetry`
+/// config flag is set so nothing is recorded otherwise.
+#[derive(Resource, Default)]
+pub struct Telemetry {
+    /// Events with the run time they happened at.
+    pub events: Vec<(f32, TelemetryEvent)>,
+}
+
+impl Telemetry {
+    pub fn record(&mut self, time: f32, event: TelemetryEvent) {
+        self.events.push((time, event));
+    }
+}
+
+/// Records the flaps, passes and crashes of the frame. Passes go down with
+/// the points the run had after them.
+pub fn record_gameplay_events(
+    mut telemetry: ResMut<Telemetry>,
+    run_time: Res<RunTime>,
+    score: Res<Score>,
+    mut flaps: EventReader<BirdFlapped>,
+    mut passes: EventReader<PipePassed>,
+    mut deaths: EventReader<BirdDied>,
+) {
+    let time = run_time.0;
+    for _ in flaps.read() {
+        telemetry.record(time, TelemetryEvent::Flap);
+    }
+    for _ in passes.read() {
+        telemetry.record(
+            time,
+            TelemetryEvent::Pass {
+                points: score.points,
+            },
+        );
+    }
+    for death in deaths.read() {
+        telemetry.record(time, TelemetryEvent::Death(death.cause));
+    }
+}
+
+/// Drops the events of a run abandoned from the pause menu.
+pub fn discard_telemetry(mut telemetry: ResMut<Telemetry>) {
+    telemetry.events.clear();
+}
+
+/// Deletes the telemetry file along with everything recorded in it.
+pub fn delete_telemetry() {
+    let path = data_dir().join(TELEMETRY_FILE_NAME);
+    if let Err(error) = remove_file_if_present(&path) {
+        error!(
+            "Failed to remove telemetry file {}: {error}",
+            path.display()
+        );
+    }
+}
+
+/// Appends the buffered events of the run that just ended, followed by its
+/// final score, to the CSV file in the data directory.
+pub fn flush_telemetry(
+    mut telemetry: ResMut<Telemetry>,
+    score: Res<Score>,
+    run_time: Res<RunTime>,
+    run_seed: Res<RunSeed>,
+) {
+    let seed = run_seed.current;
+    let mut rows = String::new();
+    for (time, event) in telemetry.events.drain(..) {
+        let (name, value) = match event {
+            TelemetryEvent::Flap => ("flap", String::new()),
+            TelemetryEvent::Pass { points } => ("pass", points.to_string()),
+            TelemetryEvent::Death(cause) => ("death", String::from(cause.name())),
+        };
+        rows += &format!("{seed},{time:.3},{name},{value}\n");
+    }
+    rows += &format!("{seed},{:.3},score,{}\n", run_time.0, score.points);
+
+    let dir = data_dir();
+    let path = dir.join(TELEMETRY_FILE_NAME);
+    let new_file = !path.exists();
+    let result = fs::create_dir_all(&dir).and_then(|_| {
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if new_file {
+            file.write_all(TELEMETRY_HEADER.as_bytes())?;
+        }
+        file.write_all(rows.as_bytes())
+    });
+    if let Err(error) = result {
+        error!("Failed to write telemetry file: {error}");
+    }
+}
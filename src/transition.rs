@@ -0,0 +1,113 @@
+//! Fades to black and back between the menu, a run and its result. The
+//! state change is held back until the screen is fully dark, and the world
+//! stands still for the whole fade.
+//!
+//! Retrying, continuing and unpausing stay instant: they already put the
+//! world back in place before changing state, and are meant to be quick.
+
+use bevy::prelude::*;
+
+use crate::{config::GameConfig, GameState};
+
+/// Full-window black quad drawn over everything, UI included.
+#[derive(Component)]
+pub struct FadeOverlay;
+
+/// A fade in progress from `from` to `to`. Once it exists nothing else can
+/// change the state until it's done.
+#[derive(Resource)]
+pub struct Transition {
+    pub from: GameState,
+    pub to: GameState,
+    pub timer: Timer,
+    /// Whether the state has been set to `to` yet, at the darkest point.
+    pub switched: bool,
+}
+
+/// Whether going from `from` to `to` fades.
+pub fn fades_between(from: GameState, to: GameState) -> bool {
+    from != to
+        && (matches!(
+            to,
+            GameState::Menu | GameState::GameOver | GameState::Victory | GameState::ReplayViewer
+        ) || (from == GameState::Menu && to == GameState::Playing))
+}
+
+/// Opacity of the overlay `progress` of the way through a fade, rising to
+/// fully black halfway and falling back.
+pub fn fade_alpha(progress: f32) -> f32 {
+    1. - (2. * progress.clamp(0., 1.) - 1.).abs()
+}
+
+pub fn spawn_fade_overlay(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            ..Default::default()
+        },
+        BackgroundColor(Color::NONE),
+        GlobalZIndex(i32::MAX),
+        FadeOverlay,
+    ));
+}
+
+/// Runs after everything else has asked for its state changes, so it can
+/// take over the ones that fade and drop any asked for mid-fade.
+#[allow(clippy::too_many_arguments)]
+pub fn run_transitions(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    transition: Option<ResMut<Transition>>,
+    mut overlay_query: Query<&mut BackgroundColor, With<FadeOverlay>>,
+) {
+    let alpha = match transition {
+        Some(mut transition) => {
+            next_state.reset();
+            transition.timer.tick(real_time.delta());
+            if !transition.switched && transition.timer.fraction() >= 0.5 {
+                next_state.set(transition.to);
+                transition.switched = true;
+            }
+            if transition.timer.finished() {
+                commands.remove_resource::<Transition>();
+                virtual_time.unpause();
+            }
+            fade_alpha(transition.timer.fraction())
+        }
+        None => {
+            let from = *state.get();
+            let to = match next_state.as_ref() {
+                NextState::Pending(to) => *to,
+                NextState::Unchanged => return,
+            };
+            // Quick restarts are there to skip straight past the result
+            let quick = config.quick_restart && from == GameState::Dying;
+            if config.transition_duration <= 0. || quick || !fades_between(from, to) {
+                return;
+            }
+
+            next_state.reset();
+            virtual_time.pause();
+            commands.insert_resource(Transition {
+                from,
+                to,
+                timer: Timer::from_seconds(config.transition_duration, TimerMode::Once),
+                switched: false,
+            });
+            0.
+        }
+    };
+
+    for mut color in overlay_query.iter_mut() {
+        let faded = Color::BLACK.with_alpha(alpha);
+        if color.0 != faded {
+            color.0 = faded;
+        }
+    }
+}
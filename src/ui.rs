@@ -0,0 +1,1054 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    autopilot::{aim_height, simulate_path},
+    checkpoint::Checkpoint,
+    config::{GameConfig, HitboxShape},
+    daily::DailyChallenge,
+    gap_center,
+    high_score::HighScore,
+    layers::POPUP_Z,
+    milestones,
+    modes::{GameMode, ModeRules},
+    particles, pipe_half_size, run_is_record_eligible,
+    save::SaveData,
+    transition, update_score, Bird, DeathCause, Difficulty, Downed, Flight, GameManager, GameState,
+    GravityFlip, Health, KillCam, LastDeath, Lead, Obstacle, PipeQueue, RunContinue, RunSeed,
+    RunTime, Score, TimeAttack, BIRD_HALF_SIZE, CONTINUE_COST, OBSTACLE_WIDTH, PIXEL_RATIO,
+    TERMINAL_VELOCITY,
+};
+
+const ALTITUDE_SAMPLE_INTERVAL: f32 = 1. / 30.;
+/// Five seconds worth of samples.
+const ALTITUDE_HISTORY_LENGTH: usize = 150;
+const ALTITUDE_GRAPH_SIZE: Vec2 = Vec2::new(320., 80.);
+const ALTITUDE_GRAPH_MARGIN: f32 = 32.;
+
+pub const SCORE_POPUP_LIFETIME: f32 = 0.8;
+const SCORE_POPUP_RISE_SPEED: f32 = 60.;
+const SCORE_POPUP_OFFSET: Vec2 = Vec2::new(0., 40.);
+/// Oldest popups make way past this many.
+const MAX_SCORE_POPUPS: usize = 4;
+
+const GAP_GUIDE_ALPHA: f32 = 0.35;
+/// Gap guides start fading at this distance from the bird and are gone by
+/// the next, so they help plan ahead but not during the pass itself.
+const GAP_GUIDE_FADE_START: f32 = 300.;
+const GAP_GUIDE_FADE_END: f32 = 120.;
+
+const PERFECT_LINE_ALPHA: f32 = 0.25;
+/// Gaps the perfect line is planned through, the next one included.
+const PERFECT_LINE_PIPES: usize = 3;
+
+const VELOCITY_GAUGE_SIZE: Vec2 = Vec2::new(12., 160.);
+const VELOCITY_GAUGE_RISING_COLOR: Color = Color::srgb(0.3, 0.9, 0.4);
+const VELOCITY_GAUGE_FALLING_COLOR: Color = Color::srgb(0.9, 0.3, 0.3);
+
+/// Fraction of the tutorial stretch at its end over which the hint fades.
+const TUTORIAL_HINT_FADE: f32 = 0.25;
+
+const BEST_SCORE_BEATEN_COLOR: Color = Color::srgb(1., 0.9, 0.3);
+
+const HEART_SIZE: f32 = 16.;
+const HEART_COLOR: Color = Color::srgb(0.9, 0.2, 0.3);
+const LOST_HEART_COLOR: Color = Color::srgba(0.2, 0.2, 0.2, 0.6);
+
+const RUN_SUMMARY_WIDTH: f32 = 200.;
+const RUN_SUMMARY_MARGIN: f32 = 16.;
+const RUN_SUMMARY_SLIDE_DURATION: f32 = 0.4;
+/// World units to a metre of distance flown: 16 sprite pixels, a little
+/// more than a bird.
+const DISTANCE_PER_METRE: f32 = 16. * PIXEL_RATIO;
+
+/// The HUD, the popups, banners and overlays drawn over a run, the summary
+/// after it and the fades between screens.
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AltitudeHistory>()
+            .init_resource::<BestScoreBeaten>()
+            .add_systems(Startup, (setup_hud, transition::spawn_fade_overlay))
+            .add_systems(PostUpdate, transition::run_transitions)
+            .add_systems(
+                Update,
+                milestones::celebrate_milestones
+                    .after(update_score)
+                    .before(particles::emit_particles)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, milestones::fade_milestone_banners)
+            .add_systems(Update, record_altitude.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                Update,
+                (spawn_score_popups, animate_score_popups)
+                    .run_if(in_state(GameState::Playing).or(in_state(GameState::Dying))),
+            )
+            // Restarting, continuing and starting from the menu all leave one
+            // of these, so no popup outlives its run
+            .add_systems(OnExit(GameState::GameOver), clear_score_popups)
+            .add_systems(OnExit(GameState::Victory), clear_score_popups)
+            .add_systems(OnExit(GameState::Menu), clear_score_popups)
+            .add_systems(
+                Update,
+                draw_altitude_graph.run_if(in_state(GameState::Paused)),
+            )
+            .add_systems(OnEnter(GameState::GameOver), spawn_run_summary)
+            .add_systems(
+                Update,
+                animate_run_summary.run_if(in_state(GameState::GameOver)),
+            )
+            .add_systems(
+                Update,
+                (draw_gap_guides, draw_perfect_line).run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                draw_kill_cam.run_if(in_state(GameState::Dying).and(resource_exists::<KillCam>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_score_text,
+                    update_timer_text,
+                    update_multiplier_text,
+                    update_best_score_text,
+                    update_velocity_gauge,
+                    update_tutorial_hint,
+                    update_hearts,
+                    update_overlay_text,
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Component)]
+pub struct TimerText;
+
+#[derive(Component)]
+pub struct MultiplierText;
+
+#[derive(Component)]
+pub struct BestScoreText;
+
+/// Whether the current run has gone past the stored best. Kept apart from
+/// the score, so it stays beaten even once the run's points have been
+/// recorded as the new best on the way to a continue.
+#[derive(Resource, Default)]
+pub struct BestScoreBeaten(pub bool);
+
+/// The filled part of the velocity gauge, growing up or down from its
+/// middle.
+#[derive(Component)]
+pub struct VelocityGaugeFill;
+
+/// One of the bird's hit points on the HUD, filled in while it has it.
+#[derive(Component)]
+pub struct Heart {
+    pub index: u32,
+}
+
+/// Breakdown of the finished run on the game over screen, sliding in from
+/// the left.
+#[derive(Component)]
+pub struct RunSummary {
+    pub timer: Timer,
+}
+
+/// How to play, shown over the tutorial stretch at the start of a run.
+#[derive(Component)]
+pub struct TutorialHint;
+
+/// Centered text used for the pause and result screens.
+#[derive(Component)]
+pub struct OverlayText;
+
+/// "+1" floating up from the bird when points are earned.
+#[derive(Component)]
+pub struct ScorePopup {
+    /// Seconds left before it has faded out.
+    pub lifetime: f32,
+}
+
+/// Recent bird altitudes sampled at a fixed rate while playing, shown as a
+/// sparkline on the pause screen.
+#[derive(Resource, Default)]
+pub struct AltitudeHistory {
+    pub samples: VecDeque<f32>,
+    pub since_last_sample: f32,
+}
+
+pub fn setup_hud(mut commands: Commands, config: Res<GameConfig>) {
+    commands.spawn((
+        Text::new("0"),
+        TextFont {
+            font_size: 48.,
+            ..Default::default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(16.),
+            width: Val::Percent(100.),
+            ..Default::default()
+        },
+        ScoreText,
+    ));
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 24.,
+            ..Default::default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(16.),
+            right: Val::Px(16.),
+            ..Default::default()
+        },
+        TimerText,
+    ));
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 24.,
+            ..Default::default()
+        },
+        TextColor(Color::srgb(1., 0.9, 0.3)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(72.),
+            width: Val::Percent(100.),
+            ..Default::default()
+        },
+        MultiplierText,
+    ));
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 32.,
+            ..Default::default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(35.),
+            width: Val::Percent(100.),
+            ..Default::default()
+        },
+        OverlayText,
+    ));
+
+    // With a single hit point there's nothing to keep track of
+    let hearts_shown = config.max_health > 1;
+    if config.show_best_score {
+        // Stacked under the hearts, which share the corner
+        let top = if hearts_shown {
+            16. + HEART_SIZE + 8.
+        } else {
+            16.
+        };
+        commands.spawn((
+            Text::new(""),
+            TextFont {
+                font_size: 24.,
+                ..Default::default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(top),
+                left: Val::Px(16.),
+                ..Default::default()
+            },
+            BestScoreText,
+        ));
+    }
+
+    if config.tutorial_lead_in > 0. {
+        commands.spawn((
+            Text::new("Tap or press Space to flap\nThe pipes are coming up ahead"),
+            TextFont {
+                font_size: 24.,
+                ..Default::default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Percent(20.),
+                width: Val::Percent(100.),
+                ..Default::default()
+            },
+            Visibility::Hidden,
+            TutorialHint,
+        ));
+    }
+
+    if config.velocity_gauge {
+        spawn_velocity_gauge(&mut commands);
+    }
+
+    if hearts_shown {
+        spawn_hearts(&mut commands, config.max_health);
+    }
+}
+
+fn spawn_hearts(commands: &mut Commands, max_health: u32) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(16.),
+            left: Val::Px(16.),
+            column_gap: Val::Px(6.),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for index in 0..max_health {
+                parent.spawn((
+                    Node {
+                        width: Val::Px(HEART_SIZE),
+                        height: Val::Px(HEART_SIZE),
+                        ..Default::default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(HEART_COLOR),
+                    Heart { index },
+                ));
+            }
+        });
+}
+
+/// Hearts past what the game mode starts the bird with are hidden, all of
+/// them when it only has the one.
+pub fn update_hearts(
+    bird_query: Query<&Health, With<Lead>>,
+    rules: Res<ModeRules>,
+    mut heart_query: Query<(&Heart, &mut BackgroundColor, &mut Visibility)>,
+) {
+    let Ok(health) = bird_query.get_single() else {
+        return;
+    };
+    for (heart, mut color, mut visibility) in heart_query.iter_mut() {
+        visibility.set_if_neq(if rules.max_health > 1 && heart.index < rules.max_health {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        let filled = if heart.index < health.0 {
+            HEART_COLOR
+        } else {
+            LOST_HEART_COLOR
+        };
+        color.set_if_neq(BackgroundColor(filled));
+    }
+}
+
+/// Opacity of the tutorial hint `distance` into a tutorial stretch
+/// `lead_in` long, fading out over the last part of it.
+pub fn tutorial_hint_alpha(distance: f32, lead_in: f32) -> f32 {
+    let remaining = (1. - distance / lead_in).max(0.);
+    (remaining / TUTORIAL_HINT_FADE).min(1.)
+}
+
+pub fn update_tutorial_hint(
+    state: Res<State<GameState>>,
+    difficulty: Res<Difficulty>,
+    game_manager: Res<GameManager>,
+    mut query: Query<(&mut Visibility, &mut TextColor), With<TutorialHint>>,
+) {
+    let alpha = if *state.get() == GameState::Playing {
+        tutorial_hint_alpha(difficulty.distance, game_manager.tutorial_lead_in)
+    } else {
+        0.
+    };
+    for (mut visibility, mut color) in query.iter_mut() {
+        let shown = if alpha > 0. {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        visibility.set_if_neq(shown);
+        color.0 = Color::WHITE.with_alpha(alpha);
+    }
+}
+
+fn spawn_velocity_gauge(commands: &mut Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(16.),
+                top: Val::Percent(50.),
+                margin: UiRect::top(Val::Px(-VELOCITY_GAUGE_SIZE.y / 2.)),
+                width: Val::Px(VELOCITY_GAUGE_SIZE.x),
+                height: Val::Px(VELOCITY_GAUGE_SIZE.y),
+                border: UiRect::all(Val::Px(2.)),
+                ..Default::default()
+            },
+            BorderColor(Color::WHITE),
+            BackgroundColor(Color::srgba(0., 0., 0., 0.3)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(0.),
+                    ..Default::default()
+                },
+                BackgroundColor(VELOCITY_GAUGE_RISING_COLOR),
+                VelocityGaugeFill,
+            ));
+        });
+}
+
+/// How full the velocity gauge is, from -1 falling at terminal velocity to
+/// 1 rising as fast, and 0 while the bird hangs still.
+pub fn velocity_gauge_fill(velocity: f32) -> f32 {
+    (velocity / TERMINAL_VELOCITY).clamp(-1., 1.)
+}
+
+pub fn update_velocity_gauge(
+    bird_query: Query<&Bird, With<Lead>>,
+    mut fill_query: Query<(&mut Node, &mut BackgroundColor), With<VelocityGaugeFill>>,
+) {
+    let Ok((mut node, mut color)) = fill_query.get_single_mut() else {
+        return;
+    };
+    let fill = bird_query
+        .get_single()
+        .map_or(0., |bird| velocity_gauge_fill(bird.velocity));
+
+    // Anchored to the middle, rising fills upwards from it and falling down
+    let (top, bottom, fill_color) = if fill >= 0. {
+        (Val::Auto, Val::Percent(50.), VELOCITY_GAUGE_RISING_COLOR)
+    } else {
+        (Val::Percent(50.), Val::Auto, VELOCITY_GAUGE_FALLING_COLOR)
+    };
+    let height = Val::Percent(fill.abs() * 50.);
+    if node.top != top || node.bottom != bottom || node.height != height {
+        node.top = top;
+        node.bottom = bottom;
+        node.height = height;
+    }
+    if color.0 != fill_color {
+        color.0 = fill_color;
+    }
+}
+
+pub fn update_score_text(score: Res<Score>, mut query: Query<&mut Text, With<ScoreText>>) {
+    if let Ok(mut text) = query.get_single_mut() {
+        text.0 = score.points.to_string();
+    }
+}
+
+pub fn best_score_label(best: u32, beaten: bool) -> String {
+    if beaten {
+        String::from("New best!")
+    } else {
+        format!("Best: {best}")
+    }
+}
+
+/// Compares against the best for the mode being played. The daily
+/// challenge has a record of its own, so there's nothing shown for it, and
+/// a run that can't be recorded never shows a new best.
+#[allow(clippy::too_many_arguments)]
+pub fn update_best_score_text(
+    score: Res<Score>,
+    high_score: Res<HighScore>,
+    difficulty: Res<Difficulty>,
+    game_manager: Res<GameManager>,
+    rules: Res<ModeRules>,
+    checkpoint: Option<Res<Checkpoint>>,
+    time_attack: Option<Res<TimeAttack>>,
+    daily: Option<Res<DailyChallenge>>,
+    mut beaten: ResMut<BestScoreBeaten>,
+    mut query: Query<(&mut Text, &mut TextColor), With<BestScoreText>>,
+) {
+    let Ok((mut text, mut color)) = query.get_single_mut() else {
+        return;
+    };
+    if daily.is_some() {
+        text.0.clear();
+        return;
+    }
+
+    let best = high_score.best_for(time_attack.is_some());
+    if score.points > best
+        && run_is_record_eligible(&difficulty, &game_manager, &rules, checkpoint.is_some())
+    {
+        beaten.0 = true;
+    }
+    text.0 = best_score_label(best, beaten.0);
+    color.0 = if beaten.0 {
+        BEST_SCORE_BEATEN_COLOR
+    } else {
+        Color::WHITE
+    };
+}
+
+pub fn update_multiplier_text(
+    score: Res<Score>,
+    mut query: Query<&mut Text, With<MultiplierText>>,
+) {
+    if let Ok(mut text) = query.get_single_mut() {
+        text.0 = match score.multiplier() {
+            1 => String::new(),
+            multiplier => format!("x{multiplier} slipstream"),
+        };
+    }
+}
+
+/// `seconds` as minutes, seconds and milliseconds, like 01:23.456.
+pub fn format_run_time(seconds: f32) -> String {
+    let millis = (seconds.max(0.) * 1000.) as u64;
+    format!(
+        "{:02}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Time left in a time attack, how many pipes remain before the finish line
+/// when there is one, the training targets hit and how long the run has
+/// lasted when that's turned on.
+pub fn update_timer_text(
+    time_attack: Option<Res<TimeAttack>>,
+    run_time: Res<RunTime>,
+    score: Res<Score>,
+    game_manager: Res<GameManager>,
+    config: Res<GameConfig>,
+    flying_query: Query<(), (With<Bird>, Without<Downed>)>,
+    mut query: Query<&mut Text, With<TimerText>>,
+) {
+    if let Ok(mut text) = query.get_single_mut() {
+        let timer = time_attack.map(|time_attack| format!("{:.1}", time_attack.remaining));
+        let progress = game_manager
+            .finish_line
+            .map(|finish_line| format!("{}/{finish_line}", score.pipes_passed));
+        let precision = config
+            .training_targets
+            .then(|| format!("Precision: {}", score.precision_points));
+        let flock =
+            (config.flock_size > 1).then(|| format!("Birds: {}", flying_query.iter().count()));
+        let run_time = config.show_run_time.then(|| format_run_time(run_time.0));
+        text.0 = [timer, progress, precision, flock, run_time]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_overlay_text(
+    state: Res<State<GameState>>,
+    score: Res<Score>,
+    save: Res<SaveData>,
+    high_score: Res<HighScore>,
+    time_attack: Option<Res<TimeAttack>>,
+    daily: Option<Res<DailyChallenge>>,
+    run_continue: Res<RunContinue>,
+    run_seed: Res<RunSeed>,
+    last_death: Res<LastDeath>,
+    difficulty: Res<Difficulty>,
+    game_manager: Res<GameManager>,
+    config: Res<GameConfig>,
+    rules: Res<ModeRules>,
+    checkpoint: Option<Res<Checkpoint>>,
+    mut query: Query<&mut Text, With<OverlayText>>,
+) {
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+
+    text.0 = match state.get() {
+        // The pause menu brings its own title
+        GameState::Loading
+        | GameState::Menu
+        | GameState::Playing
+        | GameState::Paused
+        | GameState::Dying
+        | GameState::ReplayViewer => String::new(),
+        GameState::GameOver if daily.is_some() => {
+            let score = save.daily.map_or(score.points, |record| record.score);
+            format!(
+                "Daily challenge complete\nScore: {score}\nCome back tomorrow!\nPress Esc for the menu"
+            )
+        }
+        GameState::GameOver if config.quick_restart => String::new(),
+        GameState::GameOver => {
+            let title = match (&time_attack, last_death.0) {
+                (Some(time_attack), _) if time_attack.remaining <= 0. => "Time's up!",
+                (_, Some(DeathCause::Pipe { .. })) => "You hit a pipe!",
+                (_, Some(DeathCause::Floor)) => "You hit the ground!",
+                (_, Some(DeathCause::Ceiling)) => "You hit the sky!",
+                (_, None) => "Game over",
+            };
+            let best = best_score_line(
+                &difficulty,
+                &game_manager,
+                checkpoint.is_some(),
+                time_attack.is_some(),
+                GameMode::of(&config),
+                &rules,
+                &high_score,
+            );
+            let continue_prompt = if run_continue.available(save.coins) {
+                format!("\nPress C to continue ({CONTINUE_COST} coins)")
+            } else {
+                String::new()
+            };
+            format!(
+                "{title}\nScore: {}\nBest: {best}\nCoins: {}\nSeed: {}\nPress Space or tap to retry{continue_prompt}\nPress Esc for the menu",
+                score.points, save.coins, run_seed.current
+            )
+        }
+        GameState::Victory => {
+            let best = best_score_line(
+                &difficulty,
+                &game_manager,
+                checkpoint.is_some(),
+                time_attack.is_some(),
+                GameMode::of(&config),
+                &rules,
+                &high_score,
+            );
+            format!(
+                "You made it!\nScore: {}\nBest: {best}\nCoins: {}\nSeed: {}\nPress Space or tap to play again\nPress Esc for the menu",
+                score.points, save.coins, run_seed.current
+            )
+        }
+    };
+}
+
+/// The best score shown on the result screens, or why the run didn't count.
+fn best_score_line(
+    difficulty: &Difficulty,
+    game_manager: &GameManager,
+    used_checkpoints: bool,
+    time_attack: bool,
+    game_mode: GameMode,
+    rules: &ModeRules,
+    high_score: &HighScore,
+) -> String {
+    if !rules.record_eligible {
+        format!(
+            "-, {} runs aren't recorded",
+            game_mode.name().to_lowercase()
+        )
+    } else if difficulty.is_practice() || used_checkpoints {
+        String::from("-, practice runs aren't recorded")
+    } else if difficulty.mercy {
+        String::from("-, runs given mercy aren't recorded")
+    } else if difficulty.is_assisted() {
+        String::from("-, runs with comeback gaps aren't recorded")
+    } else if game_manager.bird_enlarged() {
+        String::from("-, runs with a bigger bird aren't recorded")
+    } else if game_manager.autopilot {
+        String::from("-, autopilot runs aren't recorded")
+    } else {
+        high_score.best_for(time_attack).to_string()
+    }
+}
+
+/// Pops up whatever the score went up by since the last frame, which already
+/// includes the slipstream multiplier.
+pub fn spawn_score_popups(
+    mut commands: Commands,
+    score: Res<Score>,
+    mut last_points: Local<u32>,
+    bird_query: Query<&Transform, With<Lead>>,
+    popup_query: Query<(Entity, &ScorePopup)>,
+) {
+    let earned = score.points.saturating_sub(*last_points);
+    *last_points = score.points;
+    if earned == 0 {
+        return;
+    }
+    let Ok(bird_transform) = bird_query.get_single() else {
+        return;
+    };
+
+    let mut popups: Vec<_> = popup_query.iter().collect();
+    if popups.len() >= MAX_SCORE_POPUPS {
+        popups.sort_by(|(_, a), (_, b)| a.lifetime.total_cmp(&b.lifetime));
+        for (entity, _) in &popups[..=popups.len() - MAX_SCORE_POPUPS] {
+            commands.entity(*entity).despawn();
+        }
+    }
+
+    commands.spawn((
+        Text2d::new(format!("+{earned}")),
+        TextFont {
+            font_size: 24.,
+            ..Default::default()
+        },
+        TextColor(Color::srgb(1., 0.9, 0.3)),
+        Transform::from_translation(
+            (bird_transform.translation.truncate() + SCORE_POPUP_OFFSET).extend(POPUP_Z),
+        ),
+        ScorePopup {
+            lifetime: SCORE_POPUP_LIFETIME,
+        },
+    ));
+}
+
+pub fn animate_score_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ScorePopup, &mut Transform, &mut TextColor)>,
+) {
+    for (entity, mut popup, mut transform, mut color) in query.iter_mut() {
+        popup.lifetime -= time.delta_secs();
+        if popup.lifetime <= 0. {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation.y += SCORE_POPUP_RISE_SPEED * time.delta_secs();
+        color.0.set_alpha(popup.lifetime / SCORE_POPUP_LIFETIME);
+    }
+}
+
+pub fn clear_score_popups(mut commands: Commands, query: Query<Entity, With<ScorePopup>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Traces the path the autopilot would fly through the next few gaps from
+/// where the bird is now. There's none while gravity is flipped, which the
+/// autopilot can't fly.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn draw_perfect_line(
+    mut gizmos: Gizmos,
+    config: Res<GameConfig>,
+    game_manager: Res<GameManager>,
+    rules: Res<ModeRules>,
+    difficulty: Res<Difficulty>,
+    pipe_queue: Res<PipeQueue>,
+    score: Res<Score>,
+    bird_query: Query<(&Bird, &Transform), (With<Lead>, Without<GravityFlip>)>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+) {
+    if !config.perfect_line {
+        return;
+    }
+    let Ok((bird, bird_transform)) = bird_query.get_single() else {
+        return;
+    };
+
+    let bird_x = bird_transform.translation.x;
+    let gap_half_height = game_manager.effective_gap_size(difficulty.gap_size()) * PIXEL_RATIO;
+    let gaps: Vec<_> = pipe_queue
+        .0
+        .iter()
+        .filter_map(|&pair| obstacle_query.get_many(pair).ok())
+        .filter(|[(obstacle, _), _]| {
+            !score.has_scored(obstacle.pair_number)
+                && !game_manager.past_finish_line(obstacle.pair_number)
+        })
+        .take(PERFECT_LINE_PIPES)
+        .map(|[(_, top), (_, bottom)]| {
+            let cleared_at = (top.translation.x - bird_x) * game_manager.facing
+                + OBSTACLE_WIDTH * PIXEL_RATIO / 2.;
+            let center = gap_center(top.translation, bottom.translation);
+            (cleared_at, aim_height(center, gap_half_height))
+        })
+        .collect();
+
+    let path = simulate_path(
+        bird_transform.translation.y,
+        bird.velocity,
+        &gaps,
+        difficulty.scroll_speed(),
+        Flight::new(&config, &game_manager, &rules, difficulty.distance, false).plain_flaps(),
+    );
+    gizmos.linestrip_2d(
+        path.into_iter()
+            .map(|point| Vec2::new(bird_x + point.x * game_manager.facing, point.y)),
+        Color::WHITE.with_alpha(PERFECT_LINE_ALPHA),
+    );
+}
+
+pub fn draw_gap_guides(
+    mut gizmos: Gizmos,
+    config: Res<GameConfig>,
+    game_manager: Res<GameManager>,
+    pipe_queue: Res<PipeQueue>,
+    score: Res<Score>,
+    bird_query: Query<&Transform, With<Lead>>,
+    obstacle_query: Query<(&Obstacle, &Transform)>,
+) {
+    if !config.gap_guide {
+        return;
+    }
+    let Ok(bird_transform) = bird_query.get_single() else {
+        return;
+    };
+
+    for &pair in pipe_queue.0.iter() {
+        let Ok([(obstacle, top), (_, bottom)]) = obstacle_query.get_many(pair) else {
+            continue;
+        };
+        if score.has_scored(obstacle.pair_number)
+            || game_manager.past_finish_line(obstacle.pair_number)
+        {
+            continue;
+        }
+
+        let pipe_edge = top.translation.x - OBSTACLE_WIDTH * PIXEL_RATIO / 2. * game_manager.facing;
+        let distance = (pipe_edge - bird_transform.translation.x) * game_manager.facing;
+        let fade = ((distance - GAP_GUIDE_FADE_END) / (GAP_GUIDE_FADE_START - GAP_GUIDE_FADE_END))
+            .clamp(0., 1.);
+        if fade == 0. {
+            continue;
+        }
+
+        let y = gap_center(top.translation, bottom.translation);
+        gizmos.line_2d(
+            Vec2::new(pipe_edge, y),
+            Vec2::new(bird_transform.translation.x, y),
+            Color::WHITE.with_alpha(GAP_GUIDE_ALPHA * fade),
+        );
+    }
+}
+
+pub fn record_altitude(
+    time: Res<Time>,
+    mut history: ResMut<AltitudeHistory>,
+    bird_query: Query<&Transform, With<Lead>>,
+) {
+    history.since_last_sample += time.delta_secs();
+    if history.since_last_sample < ALTITUDE_SAMPLE_INTERVAL {
+        return;
+    }
+    history.since_last_sample = 0.;
+
+    if let Ok(transform) = bird_query.get_single() {
+        if history.samples.len() == ALTITUDE_HISTORY_LENGTH {
+            history.samples.pop_front();
+        }
+        history.samples.push_back(transform.translation.y);
+    }
+}
+
+pub fn draw_altitude_graph(
+    mut gizmos: Gizmos,
+    history: Res<AltitudeHistory>,
+    game_manager: Res<GameManager>,
+) {
+    let half_height = game_manager.window_dimentions.y / 2.;
+    let center = Vec2::new(
+        0.,
+        -half_height + ALTITUDE_GRAPH_MARGIN + ALTITUDE_GRAPH_SIZE.y / 2.,
+    );
+    gizmos.rect_2d(
+        Isometry2d::from_translation(center),
+        ALTITUDE_GRAPH_SIZE,
+        Color::WHITE.with_alpha(0.5),
+    );
+
+    // A full buffer spans the whole width, and the window's height maps onto
+    // the graph's so the line mirrors what the player saw.
+    let points = history.samples.iter().enumerate().map(|(i, &altitude)| {
+        Vec2::new(
+            center.x - ALTITUDE_GRAPH_SIZE.x / 2.
+                + ALTITUDE_GRAPH_SIZE.x * i as f32 / (ALTITUDE_HISTORY_LENGTH - 1) as f32,
+            center.y + (altitude / half_height).clamp(-1., 1.) * ALTITUDE_GRAPH_SIZE.y / 2.,
+        )
+    });
+    gizmos.linestrip_2d(points, Color::srgb(1., 0.9, 0.3));
+}
+
+/// Outlines the pipe the bird hit as collisions see it, along with the
+/// bird. For a rectangular hitbox that's the pipe grown for the bird and the
+/// center point that was checked, for a round one the pipe itself and the
+/// bird's circle.
+pub fn draw_kill_cam(mut gizmos: Gizmos, kill_cam: Res<KillCam>, game_manager: Res<GameManager>) {
+    if !kill_cam.showing() {
+        return;
+    }
+    let pipe = Isometry2d::from_translation(kill_cam.pipe_translation.truncate());
+    let pipe_color = Color::srgb(1., 0.2, 0.2);
+    let bird = Isometry2d::from_translation(kill_cam.bird_translation.truncate());
+    let bird_color = Color::srgb(1., 0.9, 0.3);
+
+    match game_manager.hitbox_shape {
+        HitboxShape::Rect => {
+            let pipe_size = Vec2::new(OBSTACLE_WIDTH * PIXEL_RATIO, kill_cam.pipe_height)
+                + game_manager.bird_growth() * 2.;
+            gizmos.rect_2d(pipe, pipe_size, pipe_color);
+            gizmos.rect_2d(
+                bird,
+                BIRD_HALF_SIZE * 2. * PIXEL_RATIO * game_manager.bird_scale,
+                bird_color,
+            );
+            gizmos.circle_2d(bird, 3., bird_color);
+        }
+        HitboxShape::Circle => {
+            gizmos.rect_2d(pipe, pipe_half_size(kill_cam.pipe_height) * 2., pipe_color);
+            gizmos.circle_2d(bird, game_manager.bird_radius(), bird_color);
+        }
+    }
+}
+
+/// `value` short enough for the run summary, counted in thousands or
+/// millions past five digits.
+pub fn compact_number(value: u64) -> String {
+    match value {
+        0..=99_999 => value.to_string(),
+        100_000..=99_999_999 => format!("{}k", value / 1_000),
+        _ => format!("{}M", value / 1_000_000),
+    }
+}
+
+/// Seconds under a minute with a tenth, and minutes and seconds past that.
+pub fn format_survival_time(seconds: f32) -> String {
+    let seconds = seconds.max(0.);
+    if seconds < 60. {
+        format!("{seconds:.1}s")
+    } else {
+        let whole = seconds as u64;
+        format!("{}:{:02}", compact_number(whole / 60), whole % 60)
+    }
+}
+
+/// Rows of the run summary, each a label and its value.
+pub fn run_summary_rows(
+    score: &Score,
+    difficulty: &Difficulty,
+    run_time: &RunTime,
+) -> [(&'static str, String); 6] {
+    [
+        ("Pipes passed", compact_number(score.pipes_passed.into())),
+        ("Near misses", compact_number(score.near_misses.into())),
+        ("Best combo", compact_number(score.best_combo.into())),
+        ("Time survived", format_survival_time(run_time.0)),
+        (
+            "Distance",
+            format!(
+                "{}m",
+                compact_number((difficulty.distance / DISTANCE_PER_METRE) as u64)
+            ),
+        ),
+        ("Coins earned", compact_number(score.coins_earned.into())),
+    ]
+}
+
+/// Left edge of the run summary `progress` of the way through sliding in,
+/// easing to a stop.
+pub fn run_summary_left(progress: f32) -> f32 {
+    let eased = 1. - (1. - progress.clamp(0., 1.)).powi(3);
+    -RUN_SUMMARY_WIDTH + (RUN_SUMMARY_WIDTH + RUN_SUMMARY_MARGIN) * eased
+}
+
+/// The daily challenge and quick restarts keep their result screens bare.
+pub fn spawn_run_summary(
+    mut commands: Commands,
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    run_time: Res<RunTime>,
+    config: Res<GameConfig>,
+    daily: Option<Res<DailyChallenge>>,
+) {
+    if daily.is_some() || config.quick_restart {
+        return;
+    }
+
+    let progress = if config.reduced_motion.enabled {
+        1.
+    } else {
+        0.
+    };
+    let mut timer = Timer::from_seconds(RUN_SUMMARY_SLIDE_DURATION, TimerMode::Once);
+    timer.set_elapsed(timer.duration().mul_f32(progress));
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(RUN_SUMMARY_MARGIN),
+                left: Val::Px(run_summary_left(progress)),
+                width: Val::Px(RUN_SUMMARY_WIDTH),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.),
+                padding: UiRect::all(Val::Px(8.)),
+                overflow: Overflow::clip(),
+                ..Default::default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.4)),
+            StateScoped(GameState::GameOver),
+            RunSummary { timer },
+        ))
+        .with_children(|parent| {
+            for (label, value) in run_summary_rows(&score, &difficulty, &run_time) {
+                parent
+                    .spawn(Node {
+                        justify_content: JustifyContent::SpaceBetween,
+                        column_gap: Val::Px(8.),
+                        ..Default::default()
+                    })
+                    .with_children(|row| {
+                        for text in [label.to_string(), value] {
+                            row.spawn((
+                                Text::new(text),
+                                TextFont {
+                                    font_size: 16.,
+                                    ..Default::default()
+                                },
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+pub fn animate_run_summary(time: Res<Time>, mut query: Query<(&mut RunSummary, &mut Node)>) {
+    for (mut summary, mut node) in query.iter_mut() {
+        if summary.timer.finished() {
+            continue;
+        }
+        summary.timer.tick(time.delta());
+        node.left = Val::Px(run_summary_left(summary.timer.fraction()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_time_shows_minutes_seconds_and_milliseconds() {
+        assert_eq!(format_run_time(0.), "00:00.000");
+        assert_eq!(format_run_time(1.5), "00:01.500");
+        assert_eq!(format_run_time(83.456), "01:23.456");
+        assert_eq!(format_run_time(3600.), "60:00.000");
+        assert_eq!(format_run_time(-2.), "00:00.000");
+    }
+
+    #[test]
+    fn numbers_shorten_past_five_digits() {
+        assert_eq!(compact_number(0), "0");
+        assert_eq!(compact_number(99_999), "99999");
+        assert_eq!(compact_number(100_000), "100k");
+        assert_eq!(compact_number(99_999_999), "99999k");
+        assert_eq!(compact_number(100_000_000), "100M");
+    }
+
+    #[test]
+    fn survival_time_switches_to_minutes() {
+        assert_eq!(format_survival_time(12.34), "12.3s");
+        assert_eq!(format_survival_time(75.), "1:15");
+    }
+}
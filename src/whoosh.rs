@@ -0,0 +1,55 @@
+//! A soft whoosh each time a pair of pipes scrolls onto the screen.
+
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    assets::GameAssets, config::GameConfig, Difficulty, GameManager, Obstacle, PipeQueue,
+    OBSTACLE_WIDTH, PIXEL_RATIO,
+};
+
+/// Whether a pipe whose leading edge is at `leading_edge` crossed the edge of
+/// the screen at `screen_edge` while scrolling `scrolled` towards the bird
+/// this frame. Going by the crossing itself means each entrance is heard
+/// exactly once, with nothing to keep track of between frames.
+pub fn entered_screen(leading_edge: f32, scrolled: f32, screen_edge: f32) -> bool {
+    leading_edge <= screen_edge && leading_edge + scrolled > screen_edge
+}
+
+/// Runs after the pipes have moved for the frame.
+#[allow(clippy::too_many_arguments)]
+pub fn play_pipe_whoosh(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    game_assets: Res<GameAssets>,
+    game_manager: Res<GameManager>,
+    difficulty: Res<Difficulty>,
+    pipe_queue: Res<PipeQueue>,
+    obstacle_query: Query<(&Transform, &Visibility), With<Obstacle>>,
+) {
+    let Some(sound) = &game_assets.pipe_whoosh_sound else {
+        return;
+    };
+    let scrolled = time.delta_secs() * difficulty.scroll_speed();
+    let screen_edge = game_manager.window_dimentions.x / 2.;
+
+    // The top pipe stands for its pair, and pairs hidden past the finish line
+    // make no sound
+    let entered = pipe_queue.0.iter().any(|pair| {
+        obstacle_query
+            .get(pair[0])
+            .is_ok_and(|(transform, visibility)| {
+                let leading_edge = transform.translation.x * game_manager.facing
+                    - OBSTACLE_WIDTH * PIXEL_RATIO / 2.;
+                *visibility != Visibility::Hidden
+                    && entered_screen(leading_edge, scrolled, screen_edge)
+            })
+    });
+    if entered {
+        commands.spawn((
+            AudioPlayer::new(sound.clone()),
+            PlaybackSettings::DESPAWN
+                .with_volume(Volume::new(config.pipe_whoosh_volume.clamp(0., 1.))),
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+//! Whole runs played headless from a script, checked by how they end.
+
+use bevy::prelude::*;
+use bevy_flappy_bird::{
+    config::{ControlScheme, GameConfig, GapPattern},
+    headless::{build_headless_app, play_scripted_run, ScriptedRun},
+    DeathCause, GameState, RunTime, Score,
+};
+
+const SEED: u64 = 7;
+
+/// Flapping every 8 frames holds the bird level with always centered gaps.
+fn steady_run(flap_until: u32, max_frames: u32) -> ScriptedRun {
+    ScriptedRun {
+        config: GameConfig {
+            gap_pattern: GapPattern::Fixed,
+            ..default()
+        },
+        seed: SEED,
+        flap_frames: (0..flap_until).step_by(8).collect(),
+        max_frames,
+    }
+}
+
+#[test]
+fn steady_flaps_score_through_centered_gaps() {
+    let mut app = build_headless_app(&steady_run(600, 600));
+    for _ in 0..600 {
+        app.update();
+    }
+
+    let world = app.world();
+    let score = world.resource::<Score>();
+    assert_eq!(score.pipes_passed, 5);
+    assert_eq!(score.points, 7);
+    assert_eq!(
+        *world.resource::<State<GameState>>().get(),
+        GameState::Playing
+    );
+}
+
+#[test]
+fn run_ends_once_the_flaps_stop() {
+    let outcome = play_scripted_run(&steady_run(600, 1200));
+    assert!(outcome.points >= 7, "lost points in {outcome:?}");
+    assert!(outcome.death.is_some());
+    assert_eq!(outcome.state, GameState::Dying);
+    assert!(outcome.frames < 1200);
+}
+
+#[test]
+fn no_flaps_hits_the_floor_without_scoring() {
+    let outcome = play_scripted_run(&ScriptedRun {
+        config: GameConfig::default(),
+        seed: SEED,
+        flap_frames: Vec::new(),
+        max_frames: 600,
+    });
+    assert_eq!(outcome.points, 0);
+    assert_eq!(outcome.death, Some(DeathCause::Floor));
+    assert_eq!(outcome.state, GameState::Dying);
+}
+
+#[test]
+fn same_script_ends_the_same_way() {
+    let run = ScriptedRun {
+        config: GameConfig::default(),
+        seed: SEED,
+        flap_frames: (0..600).step_by(20).collect(),
+        max_frames: 600,
+    };
+    assert_eq!(play_scripted_run(&run), play_scripted_run(&run));
+}
+
+#[test]
+fn paused_time_isnt_counted() {
+    let mut app = build_headless_app(&steady_run(600, 600));
+    for _ in 0..60 {
+        app.update();
+    }
+    let run_time = app.world().resource::<RunTime>().0;
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Paused);
+    for _ in 0..60 {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<RunTime>().0, run_time);
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+    assert!(app.world().resource::<RunTime>().0 > run_time);
+}
+
+#[test]
+fn autopilot_flies_through_pipes_with_any_controls() {
+    let configs = [
+        GameConfig::default(),
+        GameConfig {
+            control_scheme: ControlScheme::Glide,
+            ..default()
+        },
+        GameConfig {
+            variable_flap: true,
+            ..default()
+        },
+    ];
+    for config in configs {
+        let outcome = play_scripted_run(&ScriptedRun {
+            config: GameConfig {
+                autopilot: true,
+                ..config
+            },
+            seed: SEED,
+            flap_frames: Vec::new(),
+            max_frames: 1200,
+        });
+        assert!(outcome.pipes_passed >= 5, "stopped short in {outcome:?}");
+    }
+}